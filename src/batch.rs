@@ -0,0 +1,229 @@
+use color_eyre::eyre::{Result, WrapErr, eyre};
+use serde::Serialize;
+use std::fmt;
+use std::io::Read;
+use std::path::Path;
+
+use crate::cli::SiteArgs;
+use crate::config::{Config, load_config};
+use crate::network::engine;
+use crate::output::{self, OutputFormat, Table};
+use crate::utils::extract_hostname;
+
+/// A one-line description of a report, shown in the batch summary table.
+pub trait Summary {
+    fn summary(&self) -> String;
+}
+
+#[derive(Serialize)]
+pub struct SiteResult<T> {
+    pub site: String,
+    pub hostname: Option<String>,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct BatchReport<T> {
+    pub total: usize,
+    pub failed: usize,
+    pub results: Vec<SiteResult<T>>,
+}
+
+/// Runs a diagnostic for the site given on the command line, or for every
+/// site in `--sites-file` or matching `--tag`/`--group` in batch mode.
+/// Batch sites are checked on up to `concurrency` threads at once.
+pub fn run<T, F>(target: SiteArgs, format: OutputFormat, concurrency: usize, check: F) -> Result<()>
+where
+    T: Serialize + fmt::Display + Summary + Send,
+    F: Fn(&str) -> Result<T> + Sync,
+{
+    if let Some(site) = &target.site {
+        let config = load_config()?;
+        let hostname = extract_hostname(&config.resolve_site(site))?;
+        return output::print(&check(&hostname)?, format);
+    }
+
+    let report = collect(target, concurrency, check)?;
+    output::print(&report, format)?;
+    report.into_result()
+}
+
+/// Checks every selected site, including a single site given on the command
+/// line, and collects the outcomes without printing anything.
+pub fn collect<T, F>(target: SiteArgs, concurrency: usize, check: F) -> Result<BatchReport<T>>
+where
+    T: Send,
+    F: Fn(&str) -> Result<T> + Sync,
+{
+    let config = load_config()?;
+
+    let sites = match (target.site, &target.sites_file) {
+        (Some(site), _) => vec![site],
+        (None, Some(path)) => {
+            let sites = read_sites(path)?;
+            if sites.is_empty() {
+                return Err(eyre!("No sites found in {}", path.display()));
+            }
+            sites
+        }
+        (None, None) => select_registered(&config, target.tag.as_deref(), target.group.as_deref())?,
+    };
+
+    let results = engine::run_bounded(sites, concurrency, |site| {
+        let url = config.resolve_site(&site);
+        check_site(site, &url, &check)
+    });
+    let failed = results.iter().filter(|r| !r.ok).count();
+
+    Ok(BatchReport {
+        total: results.len(),
+        failed,
+        results,
+    })
+}
+
+impl<T> BatchReport<T> {
+    /// Turns failed sites into an error, so the process exits non-zero.
+    pub fn into_result(self) -> Result<()> {
+        if self.failed > 0 {
+            return Err(eyre!("{} of {} sites failed", self.failed, self.total));
+        }
+        Ok(())
+    }
+}
+
+fn check_site<T, F>(site: String, url: &str, check: &F) -> SiteResult<T>
+where
+    F: Fn(&str) -> Result<T>,
+{
+    let outcome = extract_hostname(url).map(|hostname| {
+        let report = check(&hostname);
+        (hostname, report)
+    });
+
+    match outcome {
+        Ok((hostname, Ok(report))) => SiteResult {
+            site,
+            hostname: Some(hostname),
+            ok: true,
+            report: Some(report),
+            error: None,
+        },
+        Ok((hostname, Err(e))) => SiteResult {
+            site,
+            hostname: Some(hostname),
+            ok: false,
+            report: None,
+            // The alternate form keeps the whole chain, e.g. the DNS or TLS
+            // error behind "Failed to fetch ...".
+            error: Some(format!("{:#}", e)),
+        },
+        Err(e) => SiteResult {
+            site,
+            hostname: None,
+            ok: false,
+            report: None,
+            error: Some(format!("{:#}", e)),
+        },
+    }
+}
+
+/// Aliases of the registered sites matching the `--tag`/`--group` selectors.
+fn select_registered(config: &Config, tag: Option<&str>, group: Option<&str>) -> Result<Vec<String>> {
+    if tag.is_none() && group.is_none() {
+        return Err(eyre!("A site, --sites-file, --tag or --group is required"));
+    }
+
+    let sites: Vec<String> = config
+        .select_sites(tag, group)
+        .into_iter()
+        .map(|entry| entry.alias.clone())
+        .collect();
+
+    if sites.is_empty() {
+        let mut selectors = Vec::new();
+        if let Some(tag) = tag {
+            selectors.push(format!("--tag {}", tag));
+        }
+        if let Some(group) = group {
+            selectors.push(format!("--group {}", group));
+        }
+        return Err(eyre!("No registered sites match {}", selectors.join(" ")));
+    }
+
+    Ok(sites)
+}
+
+/// Reads one site (hostname, URL or alias) per line from a file, or from
+/// stdin when the path is `-`. Blank lines and lines starting with `#` are
+/// ignored.
+pub fn read_sites(path: &Path) -> Result<Vec<String>> {
+    let contents = if path == Path::new("-") {
+        let mut buffer = String::new();
+        std::io::stdin()
+            .read_to_string(&mut buffer)
+            .wrap_err("Failed to read sites from stdin")?;
+        buffer
+    } else {
+        std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read sites file: {}", path.display()))?
+    };
+
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+impl<T: fmt::Display + Summary> fmt::Display for BatchReport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for result in &self.results {
+            match (&result.report, &result.error) {
+                (Some(report), _) => write!(f, "{}", report)?,
+                (None, Some(error)) => writeln!(f, "✗ {}: {}", result.site, error)?,
+                (None, None) => {}
+            }
+            writeln!(f)?;
+        }
+
+        let mut table = Table::new(&["Site", "Status", "Details"]);
+        for result in &self.results {
+            let name = result.hostname.clone().unwrap_or_else(|| result.site.clone());
+            let (status, details) = match (&result.report, &result.error) {
+                (Some(report), _) => ("✓ ok", report.summary()),
+                (None, error) => (
+                    "✗ failed",
+                    error.as_deref().and_then(|e| e.lines().next()).unwrap_or_default().to_string(),
+                ),
+            };
+            table.add_row(vec![name, status.to_string(), details]);
+        }
+
+        writeln!(f, "Summary ({} sites, {} failed):\n", self.total, self.failed)?;
+        write!(f, "{}", table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn site_errors_keep_their_causes() {
+        let check = |_: &str| -> Result<()> {
+            Err(eyre!("dns error: no record found")).wrap_err("Failed to fetch https://example.com")
+        };
+        let result = check_site("example".to_string(), "https://example.com", &check);
+        assert!(!result.ok);
+        assert_eq!(
+            result.error.as_deref(),
+            Some("Failed to fetch https://example.com: dns error: no record found")
+        );
+    }
+}
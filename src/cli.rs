@@ -1,20 +1,46 @@
-use clap::{Args as ClapArgs, Parser, Subcommand};
+use clap::{Args as ClapArgs, Parser, Subcommand, ValueEnum};
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config::Environment;
+use crate::network::engine::{DEFAULT_CONCURRENCY, ServerAddr};
+use crate::output::OutputFormat;
 
 const ABOUT: &str = "WordPress admin CLI: open admin pages and run network diagnostics.";
 const LONG_ABOUT: &str = "wpust is a WordPress administration CLI utility that opens WordPress \
 admin pages in the browser and performs network diagnostics (DNS, IP, SSL, HTTP response timing) \
-for sites.\n\nAll commands accept a site argument (hostname or URL).";
+for sites.\n\nAll commands accept a site argument (hostname, URL or an alias registered with \
+`wpust sites add`). Diagnostic commands \
+also accept --sites-file, --tag or --group to check many sites at once, and print JSON instead of text with \
+--format json.";
 
 #[derive(Parser, Debug)]
 #[command(version, about = ABOUT, long_about = LONG_ABOUT)]
 pub struct Args {
     #[command(subcommand)]
     pub command: Commands,
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value_t = OutputFormat::Text,
+        help = "Output format for diagnostic commands"
+    )]
+    pub format: OutputFormat,
+    #[arg(
+        long,
+        global = true,
+        default_value_t = DEFAULT_CONCURRENCY,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        help = "Maximum number of sites checked at the same time in batch mode"
+    )]
+    pub concurrency: usize,
 }
 
 #[derive(ClapArgs, Debug)]
 pub struct WordPressArgs {
-    #[arg(help = "Hostname or URL")]
+    #[arg(help = "Hostname, URL or site alias")]
     pub site: String,
     #[arg(long, requires = "password", help = "Basic auth username (overrides config)")]
     pub username: Option<String>,
@@ -22,6 +48,34 @@ pub struct WordPressArgs {
     pub password: Option<String>,
 }
 
+#[derive(ClapArgs, Debug)]
+pub struct SiteArgs {
+    #[arg(
+        help = "Hostname, URL or site alias",
+        required_unless_present_any = ["sites_file", "tag", "group"]
+    )]
+    pub site: Option<String>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with = "site",
+        help = "Run against every site in FILE, one per line (use - for stdin)"
+    )]
+    pub sites_file: Option<PathBuf>,
+    #[arg(
+        long,
+        conflicts_with_all = ["site", "sites_file"],
+        help = "Run against every registered site with this tag or environment"
+    )]
+    pub tag: Option<String>,
+    #[arg(
+        long,
+        conflicts_with_all = ["site", "sites_file"],
+        help = "Run against every registered site in this group"
+    )]
+    pub group: Option<String>,
+}
+
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     #[command(
@@ -33,20 +87,28 @@ pub enum Commands {
         wp: WordPressArgs,
     },
     #[command(
-        about = "Open WordPress themes page in the browser",
-        after_help = "Example:\n  wpust themes example.com"
+        about = "Open WordPress themes page in the browser, or list installed themes over the REST API",
+        after_help = "Example:\n  wpust themes example.com\n  wpust themes acme --list"
     )]
     Themes {
         #[command(flatten)]
         wp: WordPressArgs,
+        #[arg(long, help = "List installed themes and compare them with WordPress.org")]
+        list: bool,
     },
     #[command(
-        about = "Open WordPress plugins page in the browser",
-        after_help = "Example:\n  wpust plugins example.com"
+        about = "Open WordPress plugins page in the browser, or list and toggle plugins over the REST API",
+        after_help = "Example:\n  wpust plugins example.com\n  wpust plugins acme --list\n  wpust plugins acme --deactivate akismet"
     )]
     Plugins {
         #[command(flatten)]
         wp: WordPressArgs,
+        #[arg(long, conflicts_with_all = ["activate", "deactivate"], help = "List installed plugins and compare them with WordPress.org")]
+        list: bool,
+        #[arg(long, value_name = "PLUGIN", conflicts_with = "deactivate", help = "Activate a plugin, e.g. akismet or akismet/akismet")]
+        activate: Option<String>,
+        #[arg(long, value_name = "PLUGIN", help = "Deactivate a plugin")]
+        deactivate: Option<String>,
     },
     #[command(
         about = "Open WordPress site health (debug) page in the browser",
@@ -57,49 +119,234 @@ pub enum Commands {
         wp: WordPressArgs,
     },
     #[command(
-        about = "Look up DNS records (A, AAAA, MX, TXT, NS, CNAME, SOA)",
-        after_help = "Example:\n  wpust dns example.com"
+        about = "Call the WordPress REST API with the site's Application Password",
+        after_help = "Example:\n  wpust api acme /wp/v2/users/me\n  wpust api acme /wp/v2/posts --method post --data '{\"title\":\"Hello\"}'"
     )]
-    Dns {
-        #[arg(help = "Hostname or URL")]
+    Api {
+        #[arg(help = "Hostname, URL or site alias")]
         site: String,
+        #[arg(help = "REST route, e.g. /wp/v2/posts")]
+        route: String,
+        #[arg(long, value_enum, default_value = "get", help = "HTTP method")]
+        method: ApiMethod,
+        #[arg(long, value_name = "JSON", help = "JSON request body")]
+        data: Option<String>,
+    },
+    #[command(
+        about = "Detect whether a site runs WordPress and which core version",
+        after_help = "Example:\n  wpust detect example.com\n  wpust detect --sites-file sites.txt"
+    )]
+    Detect {
+        #[command(flatten)]
+        target: SiteArgs,
+    },
+    #[command(
+        about = "List the plugins and themes a site exposes in its front end, with versions",
+        after_help = "Example:\n  wpust inventory example.com\n  wpust inventory example.com --pages 5"
+    )]
+    Inventory {
+        #[command(flatten)]
+        target: SiteArgs,
+        #[arg(long, default_value_t = 0, help = "Also scan up to this many pages linked from the home page")]
+        pages: usize,
+    },
+    #[command(
+        about = "Match core, plugin and theme versions against the local vulnerability database",
+        after_help = "Example:\n  wpust vulns update --from wordfence-feed.json\n  wpust vulns acme\n  wpust vulns --tag production",
+        args_conflicts_with_subcommands = true,
+        subcommand_negates_reqs = true
+    )]
+    Vulns {
+        #[command(subcommand)]
+        command: Option<VulnsCommand>,
+        #[command(flatten)]
+        target: SiteArgs,
+    },
+    #[command(
+        about = "Check the SPF, DMARC and DKIM records that decide whether site mail lands in spam",
+        after_help = "Example:\n  wpust mail example.com\n  wpust mail example.com --selector mailpoet"
+    )]
+    Mail {
+        #[command(flatten)]
+        target: SiteArgs,
+        #[arg(long = "selector", value_name = "SELECTOR", help = "Also check this DKIM selector (repeatable)")]
+        selectors: Vec<String>,
+    },
+    #[command(
+        about = "Look up DNS records (A, AAAA, MX, TXT, NS, CNAME, SOA by default)",
+        after_help = "Example:\n  wpust dns example.com\n  wpust dns example.com --type CAA,HTTPS\n  wpust dns _sip._tcp.example.com --type SRV\n  wpust dns 203.0.113.10 --type PTR\n  wpust dns example.com --resolver 192.168.1.1\n  wpust dns example.com --doh\n  wpust dns example.com --dot --resolver 9.9.9.9\n  wpust dns example.com --format json"
+    )]
+    Dns {
+        #[command(flatten)]
+        target: SiteArgs,
+        #[arg(
+            long = "type",
+            value_name = "TYPES",
+            value_delimiter = ',',
+            help = "Record types to look up, e.g. A,CAA,SRV,PTR,DS,DNSKEY,HTTPS"
+        )]
+        record_types: Vec<String>,
+        #[arg(
+            long,
+            value_name = "IP[:PORT]",
+            value_parser = parse_resolver,
+            help = "Query this server instead of the default public resolver"
+        )]
+        resolver: Option<ServerAddr>,
+        #[arg(long, conflicts_with = "doh", help = "Use DNS-over-TLS (port 853); Cloudflare unless --resolver is given")]
+        dot: bool,
+        #[arg(long, help = "Use DNS-over-HTTPS (port 443); Cloudflare unless --resolver is given")]
+        doh: bool,
+        #[arg(
+            long,
+            value_name = "NAME",
+            requires = "resolver",
+            help = "Name on the resolver's TLS certificate, when it does not cover the IP address"
+        )]
+        tls_name: Option<String>,
+    },
+    #[command(
+        about = "Compare a DNS record across public, authoritative and custom resolvers",
+        after_help = "Example:\n  wpust propagation example.com\n  wpust propagation example.com --type MX --resolver 192.168.1.1\n  wpust propagation example.com --expect 203.0.113.10"
+    )]
+    Propagation {
+        #[command(flatten)]
+        target: SiteArgs,
+        #[arg(long = "type", default_value = "A", help = "Record type to compare, e.g. A, AAAA, CNAME, MX, TXT")]
+        record_type: String,
+        #[arg(
+            long = "resolver",
+            value_name = "IP[:PORT]",
+            value_parser = parse_resolver,
+            help = "Also query this resolver (repeatable)"
+        )]
+        resolvers: Vec<ServerAddr>,
+        #[arg(
+            long,
+            value_name = "VALUE",
+            value_delimiter = ',',
+            help = "The new value; defaults to what the authoritative name servers return"
+        )]
+        expect: Vec<String>,
     },
     #[command(
         about = "Look up IP address and reverse DNS",
         after_help = "Example:\n  wpust ip example.com"
     )]
     Ip {
-        #[arg(help = "Hostname or URL")]
-        site: String,
+        #[command(flatten)]
+        target: SiteArgs,
     },
     #[command(
-        about = "Show SSL certificate details and expiry",
-        after_help = "Example:\n  wpust ssl example.com"
+        about = "Show SSL certificate details, expiry and chain validation",
+        after_help = "Example:\n  wpust ssl example.com\n  wpust ssl --sites-file sites.txt\n  wpust ssl --tag production"
     )]
     Ssl {
-        #[arg(help = "Hostname or URL")]
-        site: String,
+        #[command(flatten)]
+        target: SiteArgs,
+        #[arg(long, value_name = "FILE", help = "Verify against this CA bundle instead of the system trust store")]
+        ca_file: Option<PathBuf>,
+    },
+    #[command(
+        about = "Check certificate expiry for many sites, soonest first",
+        after_help = "Example:\n  wpust ssl-expiry --sites-file sites.txt\n  wpust ssl-expiry --tag production --ics renewals.ics --remind 30,7"
+    )]
+    SslExpiry {
+        #[command(flatten)]
+        target: SiteArgs,
+        #[arg(long, value_name = "FILE", help = "Write an iCalendar file with expiry and reminder events")]
+        ics: Option<PathBuf>,
+        #[arg(
+            long,
+            value_name = "DAYS",
+            value_delimiter = ',',
+            default_value = "30,7",
+            help = "Days before expiry to add reminder events for (comma-separated)"
+        )]
+        remind: Vec<u32>,
+    },
+    #[command(
+        about = "Test which TLS protocol versions and cipher suites the server accepts",
+        after_help = "Example:\n  wpust tls-scan example.com"
+    )]
+    TlsScan {
+        #[command(flatten)]
+        target: SiteArgs,
     },
     #[command(
-        about = "Measure HTTP response timing",
-        after_help = "Example:\n  wpust response example.com"
+        about = "Measure HTTP response timing per phase (DNS, connect, TLS, TTFB, transfer)",
+        after_help = "Example:\n  wpust response example.com\n  wpust response example.com --samples 20 --interval 500ms\n  wpust response example.com --compression"
     )]
     Response {
-        #[arg(help = "Hostname or URL")]
-        site: String,
+        #[command(flatten)]
+        target: SiteArgs,
+        #[arg(
+            long,
+            default_value_t = 1,
+            value_parser = clap::value_parser!(u32).range(1..),
+            help = "Number of requests to send; more than one reports latency statistics"
+        )]
+        samples: u32,
+        #[arg(
+            long,
+            default_value = "1s",
+            value_parser = parse_interval,
+            help = "Pause between samples, e.g. 500ms, 2s or 1.5"
+        )]
+        interval: Duration,
+        #[arg(
+            long,
+            conflicts_with = "samples",
+            help = "Compare transfer sizes of the page, its CSS and JS with and without compression"
+        )]
+        compression: bool,
+    },
+    #[command(
+        about = "Follow redirects one hop at a time and flag loops and canonicalisation problems",
+        after_help = "Example:\n  wpust redirects example.com\n  wpust redirects example.com --https"
+    )]
+    Redirects {
+        #[command(flatten)]
+        target: SiteArgs,
+        #[arg(long, help = "Start from https:// instead of http://")]
+        https: bool,
+    },
+    #[command(
+        about = "Audit HTTP security headers and grade them from A to F",
+        after_help = "Example:\n  wpust headers example.com"
+    )]
+    Headers {
+        #[command(flatten)]
+        target: SiteArgs,
+    },
+    #[command(
+        about = "Detect the CDN, page cache and caching plugin, and check the cache is hit",
+        after_help = "Example:\n  wpust cache example.com"
+    )]
+    Cache {
+        #[command(flatten)]
+        target: SiteArgs,
     },
     #[command(
         about = "Analyse page performance via Google PageSpeed Insights",
         after_help = "Example:\n  wpust page-load example.com\n  wpust page-load example.com --strategy desktop"
     )]
     PageLoad {
-        #[arg(help = "Hostname or URL")]
-        site: String,
+        #[command(flatten)]
+        target: SiteArgs,
         #[arg(long, default_value = "mobile", help = "Analysis strategy: mobile or desktop")]
         strategy: String,
         #[arg(long, env = "PAGESPEED_API_KEY", help = "Google PageSpeed Insights API key (or set PAGESPEED_API_KEY)")]
         key: Option<String>,
     },
+    #[command(
+        about = "Manage registered sites and their aliases",
+        after_help = "Example:\n  wpust sites add acme-staging https://staging.acme.com --env staging --tag acme\n  wpust sites list\n  wpust plugins acme-staging"
+    )]
+    Sites {
+        #[command(subcommand)]
+        command: SitesCommand,
+    },
     #[command(
         about = "Stop it you silly goose.",
         after_help = "Example:\n  wpust goose"
@@ -132,3 +379,156 @@ pub enum Commands {
     )]
     About,
 }
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ApiMethod {
+    Get,
+    Post,
+    Put,
+    Patch,
+    Delete,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum FeedKind {
+    Plugin,
+    Theme,
+    Core,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum VulnsCommand {
+    #[command(
+        about = "Import a Wordfence Intelligence or WPScan JSON feed into the local database",
+        after_help = "Example:\n  wpust vulns update --from wordfence-feed.json\n  wpust vulns update --from wpscan-themes.json --kind theme"
+    )]
+    Update {
+        #[arg(long, value_name = "FILE", help = "Advisory feed to import")]
+        from: PathBuf,
+        #[arg(
+            long,
+            value_enum,
+            default_value = "plugin",
+            help = "What a WPScan feed describes; Wordfence feeds name it per entry"
+        )]
+        kind: FeedKind,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SitesCommand {
+    #[command(
+        about = "Register a site under an alias (replaces an existing alias)",
+        after_help = "Example:\n  wpust sites add acme https://acme.com --env production --tag client-acme"
+    )]
+    Add {
+        #[arg(help = "Short name used instead of the hostname, e.g. acme-staging")]
+        alias: String,
+        #[arg(help = "Canonical URL of the site")]
+        url: String,
+        #[arg(long, value_enum, help = "Environment of the site")]
+        env: Option<Environment>,
+        #[arg(long, help = "Group the site belongs to, e.g. a client name")]
+        group: Option<String>,
+        #[arg(long = "tag", value_delimiter = ',', help = "Tag for the site (repeatable or comma-separated)")]
+        tags: Vec<String>,
+        #[arg(long, help = "Custom WordPress admin path for this site")]
+        admin_path: Option<String>,
+        #[arg(long, requires = "password", help = "Basic auth username for this site")]
+        username: Option<String>,
+        #[arg(long, requires = "username", help = "Basic auth password for this site")]
+        password: Option<String>,
+        #[arg(long, requires = "app_password", help = "WordPress user for REST API access")]
+        api_user: Option<String>,
+        #[arg(long, requires = "api_user", help = "Application Password of the API user (Users → Profile)")]
+        app_password: Option<String>,
+    },
+    #[command(about = "List registered sites", after_help = "Example:\n  wpust sites list")]
+    List,
+    #[command(about = "Remove a registered site", after_help = "Example:\n  wpust sites remove acme")]
+    Remove {
+        #[arg(help = "Alias of the site")]
+        alias: String,
+    },
+    #[command(about = "Show the settings of a registered site", after_help = "Example:\n  wpust sites show acme")]
+    Show {
+        #[arg(help = "Alias of the site")]
+        alias: String,
+    },
+}
+
+/// Parses an interval given as `500ms`, `2s` or a plain number of seconds.
+fn parse_interval(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let (number, unit_millis) = if let Some(millis) = value.strip_suffix("ms") {
+        (millis, 1.0)
+    } else {
+        (value.strip_suffix('s').unwrap_or(value), 1000.0)
+    };
+
+    let number: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid interval '{}', expected e.g. 500ms, 2s or 1.5", value))?;
+    // Rejects negative, NaN and too large values, e.g. 1e30s.
+    Duration::try_from_secs_f64(number * unit_millis / 1000.0)
+        .map_err(|e| format!("invalid interval '{}': {}", value, e))
+}
+
+/// Parses a DNS server address such as `192.168.1.1`, `192.168.1.1:5353`
+/// or `[2001:db8::1]:53`; without a port, the transport's default is used.
+fn parse_resolver(value: &str) -> Result<ServerAddr, String> {
+    value
+        .parse::<SocketAddr>()
+        .map(|address| ServerAddr {
+            ip: address.ip(),
+            port: Some(address.port()),
+        })
+        .or_else(|_| value.parse::<IpAddr>().map(|ip| ServerAddr { ip, port: None }))
+        .map_err(|_| format!("invalid resolver '{}', expected an IP address with an optional port", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_interval_accepts_units() {
+        assert_eq!(parse_interval("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_interval("2s"), Ok(Duration::from_secs(2)));
+        assert_eq!(parse_interval("1.5"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse_interval(" 3 s "), Ok(Duration::from_secs(3)));
+        assert_eq!(parse_interval("0"), Ok(Duration::ZERO));
+    }
+
+    #[test]
+    fn parse_interval_rejects_invalid_values() {
+        for value in ["", "abc", "5m", "-1s", "1e30s", "inf", "NaN"] {
+            assert!(parse_interval(value).is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn parse_resolver_keeps_an_explicit_port() {
+        let server = parse_resolver("192.168.1.1:5353").unwrap();
+        assert_eq!(server.ip, "192.168.1.1".parse::<IpAddr>().unwrap());
+        assert_eq!(server.port, Some(5353));
+
+        let server = parse_resolver("[2001:db8::1]:53").unwrap();
+        assert_eq!(server.ip, "2001:db8::1".parse::<IpAddr>().unwrap());
+        assert_eq!(server.port, Some(53));
+    }
+
+    #[test]
+    fn parse_resolver_leaves_the_port_to_the_transport() {
+        assert_eq!(parse_resolver("192.168.1.1").unwrap().port, None);
+        assert_eq!(parse_resolver("2001:db8::1").unwrap().port, None);
+    }
+
+    #[test]
+    fn parse_resolver_rejects_names() {
+        for value in ["", "dns.google", "192.168.1", "192.168.1.1:99999"] {
+            assert!(parse_resolver(value).is_err(), "{}", value);
+        }
+    }
+}
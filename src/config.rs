@@ -1,5 +1,8 @@
+use clap::ValueEnum;
 use color_eyre::eyre::{Result, eyre, WrapErr};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::IpAddr;
 use std::path::PathBuf;
 
 #[derive(Deserialize, Serialize, Default)]
@@ -10,8 +13,17 @@ pub struct Config {
     pub wp_admin_path: Option<String>,
     #[serde(default)]
     pub pagespeed_api_key: Option<String>,
+    /// Base URL of the WordPress.org API, for testing against a fixture.
+    #[serde(default)]
+    pub wporg_api_url: Option<String>,
+    /// Public resolvers the propagation check queries; Google, Cloudflare,
+    /// Quad9 and OpenDNS when empty.
+    #[serde(default)]
+    pub propagation_resolvers: Vec<PublicResolver>,
     #[serde(default)]
     pub basic_auth: Vec<BasicAuthRule>,
+    #[serde(default)]
+    pub sites: Vec<SiteEntry>,
 }
 
 #[derive(Deserialize, Serialize, Clone)]
@@ -21,10 +33,106 @@ pub struct BasicAuthRule {
     pub password: String,
 }
 
+#[derive(Deserialize, Serialize, Clone)]
+pub struct PublicResolver {
+    pub name: String,
+    pub address: IpAddr,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct SiteEntry {
+    pub alias: String,
+    pub url: String,
+    #[serde(default)]
+    pub environment: Option<Environment>,
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub admin_path: Option<String>,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// WordPress user the Application Password belongs to, for the REST API.
+    #[serde(default)]
+    pub api_user: Option<String>,
+    #[serde(default)]
+    pub app_password: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Environment {
+    Production,
+    Staging,
+    Development,
+}
+
+impl fmt::Display for Environment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Environment::Production => write!(f, "production"),
+            Environment::Staging => write!(f, "staging"),
+            Environment::Development => write!(f, "development"),
+        }
+    }
+}
+
+impl SiteEntry {
+    /// The site's URL without scheme or trailing slash, e.g. `example.com/blog`.
+    pub fn address(&self) -> &str {
+        self.url
+            .trim_start_matches("https://")
+            .trim_start_matches("http://")
+            .trim_end_matches('/')
+    }
+
+    /// Whether the site carries `tag`, either as one of its tags or as the
+    /// name of its environment.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+            || self.environment.is_some_and(|e| e.to_string().eq_ignore_ascii_case(tag))
+    }
+}
+
 impl Config {
     pub fn find_basic_auth(&self, site: &str) -> Option<&BasicAuthRule> {
         self.basic_auth.iter().find(|rule| site.contains(&rule.pattern))
     }
+
+    pub fn find_site(&self, alias: &str) -> Option<&SiteEntry> {
+        self.sites.iter().find(|entry| entry.alias == alias)
+    }
+
+    /// The registered site served on `hostname`, if any.
+    pub fn find_site_by_host(&self, hostname: &str) -> Option<&SiteEntry> {
+        self.sites
+            .iter()
+            .find(|entry| entry.address().split('/').next() == Some(hostname))
+    }
+
+    /// Registered sites matching every given selector.
+    pub fn select_sites(&self, tag: Option<&str>, group: Option<&str>) -> Vec<&SiteEntry> {
+        self.sites
+            .iter()
+            .filter(|entry| tag.is_none_or(|tag| entry.has_tag(tag)))
+            .filter(|entry| {
+                group.is_none_or(|group| {
+                    entry.group.as_deref().is_some_and(|g| g.eq_ignore_ascii_case(group))
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the registered URL when `site` is an alias, otherwise `site`
+    /// unchanged.
+    pub fn resolve_site(&self, site: &str) -> String {
+        self.find_site(site)
+            .map(|entry| entry.url.clone())
+            .unwrap_or_else(|| site.to_string())
+    }
 }
 
 pub fn config_path() -> Result<PathBuf> {
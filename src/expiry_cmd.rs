@@ -0,0 +1,312 @@
+use color_eyre::eyre::{Result, WrapErr};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::batch;
+use crate::cli::SiteArgs;
+use crate::network::{SslReport, inspect_certificate};
+use crate::output::{self, OutputFormat, Table};
+use crate::utils::{UtcDateTime, unix_now};
+
+/// Certificates expiring within this many days are flagged.
+const WARN_DAYS: i64 = 30;
+
+#[derive(Serialize)]
+pub struct ExpiryDashboard {
+    pub total: usize,
+    pub failed: usize,
+    pub expired: usize,
+    pub expiring_soon: usize,
+    pub entries: Vec<ExpiryEntry>,
+}
+
+#[derive(Serialize)]
+pub struct ExpiryEntry {
+    pub site: String,
+    pub hostname: Option<String>,
+    pub days_until_expiry: Option<i64>,
+    pub expires_at: Option<String>,
+    pub issuer: Option<String>,
+    pub error: Option<String>,
+    #[serde(skip)]
+    expires_at_unix: Option<i64>,
+}
+
+pub fn ssl_expiry(
+    target: SiteArgs,
+    format: OutputFormat,
+    concurrency: usize,
+    ics: Option<PathBuf>,
+    remind_days: Vec<u32>,
+) -> Result<()> {
+    let report = batch::collect(target, concurrency, |hostname| inspect_certificate(hostname, None))?;
+
+    let mut entries: Vec<ExpiryEntry> = report.results
+        .iter()
+        .map(|result| match &result.report {
+            Some(ssl) => ExpiryEntry {
+                site: result.site.clone(),
+                hostname: result.hostname.clone(),
+                days_until_expiry: Some(ssl.days_until_expiry),
+                expires_at: Some(ssl.expires_at.clone()),
+                issuer: Some(issuer_name(ssl)),
+                error: None,
+                expires_at_unix: Some(ssl.expires_at_unix),
+            },
+            None => ExpiryEntry {
+                site: result.site.clone(),
+                hostname: result.hostname.clone(),
+                days_until_expiry: None,
+                expires_at: None,
+                issuer: None,
+                error: result.error.clone(),
+                expires_at_unix: None,
+            },
+        })
+        .collect();
+
+    // Soonest expiry first; sites that could not be checked go last.
+    entries.sort_by_key(|entry| (entry.days_until_expiry.is_none(), entry.days_until_expiry));
+
+    let dashboard = ExpiryDashboard {
+        total: entries.len(),
+        failed: report.failed,
+        expired: entries.iter().filter(|e| e.days_until_expiry.is_some_and(|d| d < 0)).count(),
+        expiring_soon: entries
+            .iter()
+            .filter(|e| e.days_until_expiry.is_some_and(|d| (0..=WARN_DAYS).contains(&d)))
+            .count(),
+        entries,
+    };
+
+    output::print(&dashboard, format)?;
+
+    if let Some(path) = ics {
+        let events = write_calendar(&path, &dashboard.entries, &remind_days)?;
+        // Keep stdout clean for JSON output.
+        eprintln!("\nWrote {} calendar events to {}", events, path.display());
+    }
+
+    report.into_result()
+}
+
+fn issuer_name(ssl: &SslReport) -> String {
+    ssl.issuer
+        .iter()
+        .find(|entry| entry.field == "O")
+        .or_else(|| ssl.issuer.iter().find(|entry| entry.field == "CN"))
+        .map(|entry| entry.value.clone())
+        .unwrap_or_default()
+}
+
+/// Writes an iCalendar file with the events from [`calendar_events`].
+fn write_calendar(path: &Path, entries: &[ExpiryEntry], remind_days: &[u32]) -> Result<usize> {
+    let events = calendar_events(entries, remind_days, unix_now());
+
+    let mut calendar = String::from("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//wpust//ssl-expiry//EN\r\nCALSCALE:GREGORIAN\r\n");
+    for event in &events {
+        calendar.push_str(event);
+    }
+    calendar.push_str("END:VCALENDAR\r\n");
+
+    std::fs::write(path, calendar)
+        .wrap_err_with(|| format!("Failed to write calendar file: {}", path.display()))?;
+
+    Ok(events.len())
+}
+
+/// An all-day event on each expiry date, plus a reminder event the given
+/// number of days before it. Reminders that are already in the past and
+/// expired certificates are skipped, and a host listed under several sites
+/// gets its events once, since calendar UIDs are derived from the host and
+/// the number of days.
+fn calendar_events(entries: &[ExpiryEntry], remind_days: &[u32], now: i64) -> Vec<String> {
+    let stamp = ical_datetime(now);
+    // Earliest reminder first; a repeated number of days would repeat a UID.
+    let mut remind_days = remind_days.to_vec();
+    remind_days.sort_unstable_by(|a, b| b.cmp(a));
+    remind_days.dedup();
+    let mut seen = HashSet::new();
+    let mut events = Vec::new();
+
+    for entry in entries {
+        let (Some(hostname), Some(expires_at)) = (&entry.hostname, entry.expires_at_unix) else {
+            continue;
+        };
+        if expires_at < now || !seen.insert(hostname.to_ascii_lowercase()) {
+            continue;
+        }
+
+        let issuer = entry.issuer.as_deref().unwrap_or("unknown issuer");
+        events.push(ical_event(
+            &format!("{}-expiry@wpust", hostname),
+            &stamp,
+            expires_at,
+            &format!("SSL certificate expires: {}", hostname),
+            &format!("The SSL certificate for {} issued by {} expires on {}.", hostname, issuer,
+                UtcDateTime::from_unix(expires_at).iso8601()),
+        ));
+
+        for days in &remind_days {
+            let remind_at = expires_at - i64::from(*days) * 86_400;
+            if remind_at < now {
+                continue;
+            }
+            events.push(ical_event(
+                &format!("{}-remind-{}d@wpust", hostname, days),
+                &stamp,
+                remind_at,
+                &format!("Renew SSL certificate for {} ({} days left)", hostname, days),
+                &format!("The SSL certificate for {} issued by {} expires in {} days, on {}.",
+                    hostname, issuer, days, UtcDateTime::from_unix(expires_at).date()),
+            ));
+        }
+    }
+
+    events
+}
+
+fn ical_event(uid: &str, stamp: &str, at: i64, summary: &str, description: &str) -> String {
+    let start = UtcDateTime::from_unix(at);
+    let end = UtcDateTime::from_unix(at + 86_400);
+    let lines = [
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}", uid),
+        format!("DTSTAMP:{}", stamp),
+        format!("DTSTART;VALUE=DATE:{:04}{:02}{:02}", start.year, start.month, start.day),
+        format!("DTEND;VALUE=DATE:{:04}{:02}{:02}", end.year, end.month, end.day),
+        format!("SUMMARY:{}", ical_escape(summary)),
+        format!("DESCRIPTION:{}", ical_escape(description)),
+        "TRANSP:TRANSPARENT".to_string(),
+        "END:VEVENT".to_string(),
+    ];
+    lines.iter().map(|line| ical_fold(line)).collect()
+}
+
+fn ical_datetime(timestamp: i64) -> String {
+    let t = UtcDateTime::from_unix(timestamp);
+    format!("{:04}{:02}{:02}T{:02}{:02}{:02}Z", t.year, t.month, t.day, t.hour, t.minute, t.second)
+}
+
+fn ical_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Folds a content line at 75 octets as required by RFC 5545, ending it
+/// with CRLF.
+fn ical_fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut width = 0;
+    for ch in line.chars() {
+        if width + ch.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(ch);
+        width += ch.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+impl fmt::Display for ExpiryDashboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "SSL certificate expiry ({} sites)\n", self.total)?;
+
+        let mut table = Table::new(&["Site", "Days left", "Expires", "Issuer", "Status"]);
+        for entry in &self.entries {
+            let name = entry.hostname.clone().unwrap_or_else(|| entry.site.clone());
+            let status = match entry.days_until_expiry {
+                Some(days) if days < 0 => "❌ expired".to_string(),
+                Some(days) if days <= WARN_DAYS => "⚠️  renew soon".to_string(),
+                Some(_) => "✓ ok".to_string(),
+                None => format!(
+                    "✗ {}",
+                    entry.error.as_deref().and_then(|e| e.lines().next()).unwrap_or("failed")
+                ),
+            };
+            table.add_row(vec![
+                name,
+                entry.days_until_expiry.map(|d| d.to_string()).unwrap_or_else(|| "-".to_string()),
+                entry.expires_at.as_deref().map(|e| e[..10].to_string()).unwrap_or_else(|| "-".to_string()),
+                entry.issuer.clone().unwrap_or_default(),
+                status,
+            ]);
+        }
+        write!(f, "{}", table)?;
+
+        writeln!(
+            f,
+            "\n{} expired, {} expiring within {} days, {} failed",
+            self.expired, self.expiring_soon, WARN_DAYS, self.failed
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn entry(site: &str, hostname: &str, expires_in_days: i64) -> ExpiryEntry {
+        let expires_at = NOW + expires_in_days * 86_400;
+        ExpiryEntry {
+            site: site.to_string(),
+            hostname: Some(hostname.to_string()),
+            days_until_expiry: Some(expires_in_days),
+            expires_at: Some(UtcDateTime::from_unix(expires_at).iso8601()),
+            issuer: Some("Let's Encrypt".to_string()),
+            error: None,
+            expires_at_unix: Some(expires_at),
+        }
+    }
+
+    fn uids(events: &[String]) -> Vec<&str> {
+        events
+            .iter()
+            .filter_map(|event| event.lines().find_map(|line| line.strip_prefix("UID:")))
+            .collect()
+    }
+
+    #[test]
+    fn a_host_listed_under_several_sites_gets_one_set_of_events() {
+        let entries = [
+            entry("shop", "example.com", 40),
+            entry("https://example.com/", "example.com", 40),
+            entry("blog", "blog.example.com", 40),
+        ];
+        let events = calendar_events(&entries, &[14], NOW);
+        assert_eq!(
+            uids(&events),
+            vec![
+                "example.com-expiry@wpust",
+                "example.com-remind-14d@wpust",
+                "blog.example.com-expiry@wpust",
+                "blog.example.com-remind-14d@wpust",
+            ]
+        );
+    }
+
+    #[test]
+    fn past_reminders_and_expired_certificates_are_skipped() {
+        let entries = [entry("soon", "soon.example.com", 10), entry("gone", "gone.example.com", -1)];
+        let events = calendar_events(&entries, &[30, 7], NOW);
+        assert_eq!(uids(&events), vec!["soon.example.com-expiry@wpust", "soon.example.com-remind-7d@wpust"]);
+    }
+
+    #[test]
+    fn repeated_reminder_days_get_one_event() {
+        let entries = [entry("shop", "example.com", 40)];
+        let events = calendar_events(&entries, &[7, 30, 7, 30], NOW);
+        assert_eq!(
+            uids(&events),
+            vec!["example.com-expiry@wpust", "example.com-remind-30d@wpust", "example.com-remind-7d@wpust"]
+        );
+    }
+}
@@ -1,8 +1,12 @@
+mod batch;
 mod cli;
 mod config;
 mod config_cmd;
+mod expiry_cmd;
 mod goose;
 mod network;
+mod output;
+mod sites_cmd;
 mod uninstall;
 mod update;
 mod utils;
@@ -10,24 +14,97 @@ mod wordpress;
 
 use color_eyre::eyre::Result;
 use clap::{CommandFactory, Parser};
-use cli::{Args, Commands};
-use wordpress::{perma, themes, plugins, site_health};
-use network::{dns, ip, ssl, response, page_load};
+use cli::{Args, Commands, VulnsCommand};
+use network::engine::{ResolverChoice, Transport};
+use wordpress::{perma, themes, list_themes, plugins, list_plugins, set_plugin_status, site_health, detect_wordpress, inventory, api, load_vuln_db, scan_vulnerabilities, update_vulnerabilities};
+use network::{lookup_dns, lookup_ip, check_mail, check_propagation, inspect_certificate, scan_tls, measure_response, analyse_compression, sample_response, trace_redirects, audit_headers, detect_cache, analyse_page_load};
 
 fn main() -> Result<()> {
     color_eyre::install()?;
     let args = Args::parse();
+    let format = args.format;
+    let concurrency = args.concurrency;
 
     match args.command {
         Commands::Perma { wp } => perma(wp.site, wp.username, wp.password)?,
-        Commands::Themes { wp } => themes(wp.site, wp.username, wp.password)?,
-        Commands::Plugins { wp } => plugins(wp.site, wp.username, wp.password)?,
+        Commands::Themes { wp, list } => {
+            if list {
+                output::print(&list_themes(&wp.site)?, format)?
+            } else {
+                themes(wp.site, wp.username, wp.password)?
+            }
+        }
+        Commands::Plugins { wp, list, activate, deactivate } => match (activate, deactivate) {
+            _ if list => output::print(&list_plugins(&wp.site)?, format)?,
+            (Some(plugin), _) => set_plugin_status(&wp.site, &plugin, true)?,
+            (_, Some(plugin)) => set_plugin_status(&wp.site, &plugin, false)?,
+            _ => plugins(wp.site, wp.username, wp.password)?,
+        },
         Commands::Siteinfo { wp } => site_health(wp.site, wp.username, wp.password)?,
-        Commands::Dns { site } => dns(site)?,
-        Commands::Ip { site } => ip(site)?,
-        Commands::Ssl { site } => ssl(site)?,
-        Commands::Response { site } => response(site)?,
-        Commands::PageLoad { site, strategy, key } => page_load(site, strategy, key)?,
+        Commands::Api { site, route, method, data } => api(site, route, method, data)?,
+        Commands::Detect { target } => batch::run(target, format, concurrency, detect_wordpress)?,
+        Commands::Inventory { target, pages } => {
+            batch::run(target, format, concurrency, |hostname| inventory(hostname, pages))?
+        }
+        Commands::Vulns { command, target } => match command {
+            Some(VulnsCommand::Update { from, kind }) => update_vulnerabilities(&from, kind)?,
+            None => {
+                let db = load_vuln_db()?;
+                batch::run(target, format, concurrency, |hostname| scan_vulnerabilities(hostname, &db))?
+            }
+        },
+        Commands::Dns { target, record_types, resolver, dot, doh, tls_name } => {
+            let transport = if dot {
+                Transport::Tls
+            } else if doh {
+                Transport::Https
+            } else {
+                Transport::Plain
+            };
+            let choice = ResolverChoice { server: resolver, transport, tls_name };
+            batch::run(target, format, concurrency, |hostname| lookup_dns(hostname, &record_types, &choice))?
+        }
+        Commands::Mail { target, selectors } => {
+            batch::run(target, format, concurrency, |hostname| check_mail(hostname, &selectors))?
+        }
+        Commands::Propagation { target, record_type, resolvers, expect } => {
+            let resolvers: Vec<_> = resolvers
+                .iter()
+                .map(|server| server.socket_addr(Transport::Plain.default_port()))
+                .collect();
+            batch::run(target, format, concurrency, |hostname| {
+                check_propagation(hostname, &record_type, &resolvers, &expect)
+            })?
+        }
+        Commands::Ip { target } => batch::run(target, format, concurrency, lookup_ip)?,
+        Commands::Ssl { target, ca_file } => batch::run(target, format, concurrency, |hostname| {
+            inspect_certificate(hostname, ca_file.as_deref())
+        })?,
+        Commands::SslExpiry { target, ics, remind } => {
+            expiry_cmd::ssl_expiry(target, format, concurrency, ics, remind)?
+        }
+        Commands::TlsScan { target } => batch::run(target, format, concurrency, scan_tls)?,
+        Commands::Response { target, samples, interval, compression } => {
+            if compression {
+                batch::run(target, format, concurrency, analyse_compression)?
+            } else if samples > 1 {
+                batch::run(target, format, concurrency, |hostname| sample_response(hostname, samples, interval))?
+            } else {
+                batch::run(target, format, concurrency, measure_response)?
+            }
+        }
+        Commands::Redirects { target, https } => {
+            batch::run(target, format, concurrency, |hostname| trace_redirects(hostname, https))?
+        }
+        Commands::Headers { target } => batch::run(target, format, concurrency, audit_headers)?,
+        Commands::Cache { target } => batch::run(target, format, concurrency, detect_cache)?,
+        Commands::PageLoad { target, strategy, key } => {
+            let single_site = target.site.is_some();
+            batch::run(target, format, concurrency, |hostname| {
+                analyse_page_load(hostname, &strategy, key.clone(), single_site)
+            })?
+        }
+        Commands::Sites { command } => sites_cmd::sites(command, format)?,
         Commands::Config => config_cmd::setup()?,
         Commands::Update => update::update()?,
         Commands::Goose => goose::goose()?,
@@ -0,0 +1,389 @@
+use crate::batch::Summary;
+use color_eyre::eyre::{Result, eyre};
+use reqwest::blocking::Client;
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+use std::fmt;
+
+use crate::utils::http_client;
+
+/// Headers that report whether a cache served the response.
+const STATUS_HEADERS: [&str; 12] = [
+    "cf-cache-status",
+    "x-cache",
+    "x-cache-status",
+    "x-litespeed-cache",
+    "x-kinsta-cache",
+    "x-proxy-cache",
+    "x-nginx-cache",
+    "x-fastcgi-cache",
+    "x-sg-cache",
+    "x-hcdn-cache-status",
+    "x-ac",
+    "x-wpe-cached",
+];
+
+/// CDNs and the header (and optional value fragment) that gives them away.
+const CDNS: [(&str, &str, &str); 9] = [
+    ("Cloudflare", "cf-ray", ""),
+    ("Amazon CloudFront", "x-amz-cf-id", ""),
+    ("Fastly", "x-fastly-request-id", ""),
+    ("Fastly", "x-served-by", "cache-"),
+    ("Akamai", "x-akamai-transformed", ""),
+    ("Sucuri", "x-sucuri-id", ""),
+    ("BunnyCDN", "server", "bunnycdn"),
+    ("KeyCDN", "server", "keycdn"),
+    ("StackPath", "x-hw", ""),
+];
+
+/// Server-side page caches and hosting caches.
+const PAGE_CACHES: [(&str, &str, &str); 9] = [
+    ("Varnish", "x-varnish", ""),
+    ("Varnish", "via", "varnish"),
+    ("LiteSpeed", "x-litespeed-cache", ""),
+    ("Nginx FastCGI cache", "x-fastcgi-cache", ""),
+    ("Nginx proxy cache", "x-nginx-cache", ""),
+    ("Kinsta", "x-kinsta-cache", ""),
+    ("SiteGround", "x-sg-cache", ""),
+    ("Hostinger", "x-hcdn-cache-status", ""),
+    ("WordPress.com / VIP", "x-ac", ""),
+];
+
+/// Caching plugins, recognised by the comment they add to cached pages or
+/// by a header they set.
+const PLUGIN_MARKERS: [(&str, &str); 9] = [
+    ("WP Rocket", "This website is like a Rocket"),
+    ("W3 Total Cache", "Performance optimized by W3 Total Cache"),
+    ("WP Super Cache", "WP-Super-Cache"),
+    ("WP Fastest Cache", "WP Fastest Cache file was created"),
+    ("LiteSpeed Cache", "Page generated by LiteSpeed Cache"),
+    ("Cache Enabler", "Cache Enabler by KeyCDN"),
+    ("Hummingbird", "Hummingbird cache file was created"),
+    ("Breeze", "Cache served by breeze"),
+    ("Comet Cache", "Comet Cache is Fully Functional"),
+];
+
+#[derive(Serialize)]
+pub struct CacheReport {
+    pub hostname: String,
+    pub url: String,
+    pub cdn: Vec<&'static str>,
+    pub page_cache: Vec<&'static str>,
+    pub plugins: Vec<&'static str>,
+    pub cache_control: Option<String>,
+    pub first: CacheProbe,
+    pub second: CacheProbe,
+    pub working: bool,
+    pub notes: Vec<String>,
+}
+
+/// What the cache headers said about one request.
+#[derive(Serialize)]
+pub struct CacheProbe {
+    pub status: CacheStatus,
+    pub evidence: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheStatus {
+    Hit,
+    Miss,
+    Bypass,
+    Unknown,
+}
+
+/// A fetched page after redirects.
+struct Page {
+    url: String,
+    headers: HeaderMap,
+    body: String,
+}
+
+/// Requests the front page twice and works out which CDN, page cache and
+/// caching plugin are in front of the site, and whether the second request
+/// was served from cache.
+pub fn detect_cache(hostname: &str) -> Result<CacheReport> {
+    let client = http_client()?;
+    let url = format!("https://{}/", hostname);
+
+    let first = fetch(&client, &url)?;
+    let second = fetch(&client, &url)?;
+
+    let headers = &first.headers;
+    let cdn = detect_cdn(headers);
+    let page_cache = detect_page_cache(headers);
+    let plugins = detect_plugins(headers, &first.body);
+
+    let first_probe = probe(headers);
+    let second_probe = probe(&second.headers);
+    let cache_control = header(headers, "cache-control");
+    let working = second_probe.status == CacheStatus::Hit;
+
+    let mut notes = Vec::new();
+    if let Some(cache_control) = &cache_control {
+        let lower = cache_control.to_ascii_lowercase();
+        if ["no-store", "no-cache", "private", "max-age=0"].iter().any(|d| lower.contains(d)) {
+            notes.push(format!("Cache-Control: {} tells shared caches not to store the page", cache_control));
+        }
+    }
+    if let Some(cookie) = header(headers, "set-cookie") {
+        let name = cookie.split('=').next().unwrap_or_default();
+        notes.push(format!("The page sets a cookie ({}), which makes most page caches bypass it", name));
+    }
+    if first.url != url {
+        notes.push(format!("The request was redirected to {}", first.url));
+    }
+    if !working && first_probe.status == CacheStatus::Unknown && second_probe.status == CacheStatus::Unknown {
+        notes.push("No cache status headers found; a plugin may still cache pages on disk".to_string());
+    }
+
+    Ok(CacheReport {
+        hostname: hostname.to_string(),
+        url: first.url,
+        cdn,
+        page_cache,
+        plugins,
+        cache_control,
+        first: first_probe,
+        second: second_probe,
+        working,
+        notes,
+    })
+}
+
+fn fetch(client: &Client, url: &str) -> Result<Page> {
+    let response = client
+        .get(url)
+        .send()
+        .map_err(|e| eyre!("HTTP request failed: {}", e))?;
+    let url = response.url().to_string();
+    let headers = response.headers().clone();
+    let body = response.text().map_err(|e| eyre!("Failed to read response: {}", e))?;
+    Ok(Page { url, headers, body })
+}
+
+fn header(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers.get(name).and_then(|value| value.to_str().ok()).map(str::to_string)
+}
+
+fn detect(headers: &HeaderMap, signatures: &[(&'static str, &str, &str)]) -> Vec<&'static str> {
+    let mut found = Vec::new();
+    for (name, header_name, fragment) in signatures {
+        let matches = header(headers, header_name)
+            .is_some_and(|value| value.to_ascii_lowercase().contains(fragment));
+        if matches && !found.contains(name) {
+            found.push(*name);
+        }
+    }
+    found
+}
+
+fn detect_cdn(headers: &HeaderMap) -> Vec<&'static str> {
+    let mut cdn = detect(headers, &CDNS);
+    if header(headers, "server").is_some_and(|s| s.eq_ignore_ascii_case("cloudflare")) && !cdn.contains(&"Cloudflare") {
+        cdn.push("Cloudflare");
+    }
+    cdn
+}
+
+fn detect_page_cache(headers: &HeaderMap) -> Vec<&'static str> {
+    let mut page_cache = detect(headers, &PAGE_CACHES);
+    // WP Engine sets a family of x-wpe-* headers, not all of them every time.
+    if headers.keys().any(|name| name.as_str().starts_with("x-wpe-")) {
+        page_cache.push("WP Engine");
+    }
+    page_cache
+}
+
+fn detect_plugins(headers: &HeaderMap, body: &str) -> Vec<&'static str> {
+    let mut plugins: Vec<&'static str> = PLUGIN_MARKERS
+        .iter()
+        .filter(|(_, marker)| body.contains(marker))
+        .map(|(plugin, _)| *plugin)
+        .collect();
+    if header(headers, "x-powered-by").is_some_and(|v| v.contains("WP Rocket")) && !plugins.contains(&"WP Rocket") {
+        plugins.push("WP Rocket");
+    }
+    plugins
+}
+
+fn probe(headers: &HeaderMap) -> CacheProbe {
+    let mut evidence = Vec::new();
+    let mut statuses = Vec::new();
+
+    for name in STATUS_HEADERS {
+        if let Some(value) = header(headers, name) {
+            statuses.push(classify(&value));
+            evidence.push(format!("{}: {}", name, value));
+        }
+    }
+
+    // Varnish lists two transaction ids when it served a cached object.
+    if let Some(value) = header(headers, "x-varnish") {
+        statuses.push(if value.split_whitespace().count() > 1 { CacheStatus::Hit } else { CacheStatus::Miss });
+        evidence.push(format!("x-varnish: {}", value));
+    }
+    if let Some(age) = header(headers, "age") {
+        if age.trim().parse::<u64>().is_ok_and(|age| age > 0) {
+            statuses.push(CacheStatus::Hit);
+        }
+        evidence.push(format!("age: {}", age));
+    }
+
+    // Any layer serving from cache counts as a hit.
+    let status = [CacheStatus::Hit, CacheStatus::Miss, CacheStatus::Bypass]
+        .into_iter()
+        .find(|status| statuses.contains(status))
+        .unwrap_or(CacheStatus::Unknown);
+
+    CacheProbe { status, evidence }
+}
+
+fn classify(value: &str) -> CacheStatus {
+    let value = value.to_ascii_uppercase();
+    if value.contains("HIT") {
+        CacheStatus::Hit
+    } else if value.contains("BYPASS") || value.contains("DYNAMIC") || value.contains("UNCACHEABLE") {
+        CacheStatus::Bypass
+    } else if value.contains("MISS") || value.contains("EXPIRED") || value.contains("STALE") {
+        CacheStatus::Miss
+    } else {
+        CacheStatus::Unknown
+    }
+}
+
+impl fmt::Display for CacheStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            CacheStatus::Hit => "HIT",
+            CacheStatus::Miss => "MISS",
+            CacheStatus::Bypass => "BYPASS",
+            CacheStatus::Unknown => "unknown",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+impl fmt::Display for CacheReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Cache detection for: {}", self.hostname)?;
+        writeln!(f, "URL: {}\n", self.url)?;
+
+        let list = |items: &[&str]| if items.is_empty() { "none detected".to_string() } else { items.join(", ") };
+        writeln!(f, "CDN:            {}", list(&self.cdn))?;
+        writeln!(f, "Page cache:     {}", list(&self.page_cache))?;
+        writeln!(f, "Cache plugin:   {}", list(&self.plugins))?;
+        writeln!(f, "Cache-Control:  {}\n", self.cache_control.as_deref().unwrap_or("-"))?;
+
+        for (label, probe) in [("First request", &self.first), ("Second request", &self.second)] {
+            writeln!(f, "{}: {}", label, probe.status)?;
+            for evidence in &probe.evidence {
+                writeln!(f, "  {}", evidence)?;
+            }
+        }
+
+        if self.working {
+            writeln!(f, "\n✓ Cache is working: the second request was served from cache")?;
+        } else {
+            writeln!(f, "\n❌ The second request was not a cache hit")?;
+        }
+        for note in &self.notes {
+            writeln!(f, "  - {}", note)?;
+        }
+        Ok(())
+    }
+}
+
+impl Summary for CacheReport {
+    fn summary(&self) -> String {
+        let layers: Vec<&str> = self.cdn.iter().chain(&self.page_cache).chain(&self.plugins).copied().collect();
+        format!(
+            "{} → {}, {}",
+            self.first.status,
+            self.second.status,
+            if layers.is_empty() { "no cache detected".to_string() } else { layers.join(", ") }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderName, HeaderValue};
+
+    fn headers(pairs: &[(&str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(
+                HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                HeaderValue::from_str(value).unwrap(),
+            );
+        }
+        headers
+    }
+
+    fn probe_status(pairs: &[(&str, &str)]) -> CacheStatus {
+        probe(&headers(pairs)).status
+    }
+
+    #[test]
+    fn classifies_cache_status_values() {
+        let cases = [
+            ("HIT", CacheStatus::Hit),
+            ("Hit from cloudfront", CacheStatus::Hit),
+            ("hit, hit", CacheStatus::Hit),
+            ("MISS", CacheStatus::Miss),
+            ("EXPIRED", CacheStatus::Miss),
+            ("STALE", CacheStatus::Miss),
+            ("BYPASS", CacheStatus::Bypass),
+            ("DYNAMIC", CacheStatus::Bypass),
+            ("UNCACHEABLE", CacheStatus::Bypass),
+            ("1", CacheStatus::Unknown),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(classify(value), expected, "{}", value);
+        }
+    }
+
+    #[test]
+    fn any_layer_hit_counts_as_a_hit() {
+        let probe = probe(&headers(&[("cf-cache-status", "DYNAMIC"), ("x-litespeed-cache", "hit")]));
+        assert_eq!(probe.status, CacheStatus::Hit);
+        assert_eq!(probe.evidence, vec!["cf-cache-status: DYNAMIC", "x-litespeed-cache: hit"]);
+
+        assert_eq!(probe_status(&[("cf-cache-status", "BYPASS"), ("x-cache", "MISS")]), CacheStatus::Miss);
+        assert_eq!(probe_status(&[("cf-cache-status", "BYPASS")]), CacheStatus::Bypass);
+        assert_eq!(probe_status(&[]), CacheStatus::Unknown);
+    }
+
+    #[test]
+    fn varnish_and_age_headers() {
+        assert_eq!(probe_status(&[("x-varnish", "32781 32770")]), CacheStatus::Hit);
+        assert_eq!(probe_status(&[("x-varnish", "32781")]), CacheStatus::Miss);
+        assert_eq!(probe_status(&[("age", "120")]), CacheStatus::Hit);
+        assert_eq!(probe_status(&[("age", "0")]), CacheStatus::Unknown);
+    }
+
+    #[test]
+    fn detects_cdns_and_page_caches() {
+        let map = headers(&[
+            ("server", "cloudflare"),
+            ("x-served-by", "cache-ams21080-AMS"),
+            ("via", "1.1 varnish (Varnish/7.1)"),
+            ("x-varnish", "12 34"),
+            ("x-wpe-loopback-upstream-addr", "127.0.0.1"),
+        ]);
+        assert_eq!(detect_cdn(&map), vec!["Fastly", "Cloudflare"]);
+        assert_eq!(detect_page_cache(&map), vec!["Varnish", "WP Engine"]);
+        assert!(detect_cdn(&headers(&[("server", "nginx")])).is_empty());
+    }
+
+    #[test]
+    fn detects_caching_plugins() {
+        let body = "<html></html>\n<!-- This website is like a Rocket, isn't it? -->\n<!-- Cache served by breeze CACHE -->";
+        assert_eq!(detect_plugins(&HeaderMap::new(), body), vec!["WP Rocket", "Breeze"]);
+        assert_eq!(detect_plugins(&headers(&[("x-powered-by", "WP Rocket/3.15")]), "<html>"), vec!["WP Rocket"]);
+        assert!(detect_plugins(&HeaderMap::new(), "<html>").is_empty());
+    }
+}
@@ -0,0 +1,324 @@
+use crate::batch::Summary;
+use crate::output::{Table, format_bytes};
+use crate::utils::{html, raw_http_client};
+use color_eyre::eyre::{Result, eyre};
+use reqwest::blocking::Client;
+use reqwest::header::{ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH};
+use serde::Serialize;
+use std::fmt;
+use url::Url;
+
+const ACCEPT: &str = "gzip, br, zstd";
+
+/// Sent on the plain request, so the server is asked for the uncompressed
+/// body rather than left to pick a default.
+const IDENTITY: &str = "identity";
+
+/// Stylesheets and scripts checked per page, besides the page itself.
+const MAX_ASSETS: usize = 3;
+
+/// Responses smaller than this gain little from compression and are not
+/// flagged when sent uncompressed.
+const MIN_COMPRESSIBLE: usize = 1024;
+
+#[derive(Serialize)]
+pub struct CompressionReport {
+    pub hostname: String,
+    pub resources: Vec<ResourceCompression>,
+    pub uncompressed: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct ResourceCompression {
+    pub url: String,
+    pub kind: &'static str,
+    pub encoding: Option<String>,
+    pub uncompressed_bytes: Option<usize>,
+    pub compressed_bytes: Option<usize>,
+    /// Share of bytes saved, from 0.0 (nothing) to 1.0.
+    pub saving: Option<f64>,
+    pub error: Option<String>,
+}
+
+/// Requests the front page and a few of its stylesheets and scripts
+/// accepting only `identity` and accepting compression, and compares the
+/// transferred sizes.
+pub fn analyse_compression(hostname: &str) -> Result<CompressionReport> {
+    // Decompression stays off, so bodies arrive exactly as the server sent
+    // them.
+    let client = raw_http_client()?;
+
+    let page_url = format!("https://{}/", hostname);
+    let (base, body) = client
+        .get(&page_url)
+        .send()
+        .and_then(|response| {
+            let url = response.url().clone();
+            response.text().map(|body| (url, body))
+        })
+        .map_err(|e| eyre!("HTTP request failed: {}", e))?;
+
+    let mut resources = vec![measure(&client, base.as_str(), "HTML")];
+    for (kind, url) in asset_urls(&base, &body) {
+        resources.push(measure(&client, &url, kind));
+    }
+
+    let uncompressed = uncompressed(&resources);
+
+    Ok(CompressionReport {
+        hostname: hostname.to_string(),
+        resources,
+        uncompressed,
+    })
+}
+
+/// The `Content-Encoding` of a response and the size of its body as sent.
+type Fetched = std::result::Result<(Option<String>, usize), String>;
+
+fn measure(client: &Client, url: &str, kind: &'static str) -> ResourceCompression {
+    let fetch = |accept: &str| -> Fetched {
+        let request = client.get(url).header(ACCEPT_ENCODING, accept);
+        let fetched = request.send().and_then(|response| response.error_for_status()).and_then(|response| {
+            let header = |name| response.headers().get(name).and_then(|value| value.to_str().ok());
+            let encoding = header(CONTENT_ENCODING).map(str::to_string);
+            let content_length = header(CONTENT_LENGTH).and_then(|value| value.trim().parse().ok());
+            let body = response.bytes()?;
+            Ok((encoding, transferred_size(content_length, body.len())))
+        });
+        fetched.map_err(|e| e.to_string())
+    };
+
+    compare(url, kind, fetch(IDENTITY), fetch(ACCEPT))
+}
+
+/// The declared `Content-Length` of a body, or the bytes read when the
+/// server sent none (a chunked response).
+fn transferred_size(content_length: Option<usize>, read: usize) -> usize {
+    content_length.unwrap_or(read)
+}
+
+/// Compares the response accepting only `identity` (`plain`) with the one
+/// accepting compression (`accepted`).
+fn compare(url: &str, kind: &'static str, plain: Fetched, accepted: Fetched) -> ResourceCompression {
+    let mut resource = ResourceCompression {
+        url: url.to_string(),
+        kind,
+        encoding: None,
+        uncompressed_bytes: None,
+        compressed_bytes: None,
+        saving: None,
+        error: None,
+    };
+
+    match (plain, accepted) {
+        (Ok((_, plain)), Ok((encoding, compressed))) => {
+            resource.uncompressed_bytes = Some(plain);
+            resource.compressed_bytes = Some(compressed);
+            resource.saving = (plain > 0).then(|| 1.0 - compressed as f64 / plain as f64);
+            resource.encoding = encoding.filter(|encoding| !encoding.eq_ignore_ascii_case("identity"));
+        }
+        (Err(e), _) | (_, Err(e)) => resource.error = Some(e),
+    }
+
+    resource
+}
+
+/// Resources large enough to benefit from compression that were sent
+/// without it.
+fn uncompressed(resources: &[ResourceCompression]) -> Vec<String> {
+    resources
+        .iter()
+        .filter(|r| r.encoding.is_none() && r.uncompressed_bytes.is_some_and(|size| size >= MIN_COMPRESSIBLE))
+        .map(|r| r.url.clone())
+        .collect()
+}
+
+/// The first few stylesheets and scripts on the page, resolved against the
+/// page URL.
+fn asset_urls(base: &Url, page: &str) -> Vec<(&'static str, String)> {
+    let stylesheets = html::tags(page, "link")
+        .into_iter()
+        .filter(|tag| tag.to_ascii_lowercase().contains("stylesheet"))
+        .filter_map(|tag| html::attribute_value(tag, "href"));
+    let scripts = html::tags(page, "script")
+        .into_iter()
+        .filter_map(|tag| html::attribute_value(tag, "src"));
+
+    let mut assets = Vec::new();
+    for (kind, values) in [("CSS", stylesheets.collect::<Vec<_>>()), ("JS", scripts.collect())] {
+        let mut urls: Vec<String> = Vec::new();
+        for url in values.iter().filter_map(|value| base.join(value).ok()) {
+            if matches!(url.scheme(), "http" | "https") && !urls.iter().any(|seen| seen == url.as_str()) {
+                urls.push(url.to_string());
+            }
+        }
+        assets.extend(urls.into_iter().take(MAX_ASSETS).map(|url| (kind, url)));
+    }
+    assets
+}
+
+impl fmt::Display for CompressionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Compression for: {}\n", self.hostname)?;
+
+        let mut table = Table::new(&["Type", "Encoding", "Uncompressed", "Compressed", "Saving", "URL"]);
+        for resource in &self.resources {
+            match &resource.error {
+                Some(error) => table.add_row(vec![
+                    resource.kind.to_string(),
+                    "error".to_string(),
+                    "-".to_string(),
+                    "-".to_string(),
+                    "-".to_string(),
+                    format!("{} ({})", resource.url, error),
+                ]),
+                None => table.add_row(vec![
+                    resource.kind.to_string(),
+                    resource.encoding.clone().unwrap_or_else(|| "none".to_string()),
+                    resource.uncompressed_bytes.map(format_bytes).unwrap_or_default(),
+                    resource.compressed_bytes.map(format_bytes).unwrap_or_default(),
+                    resource.saving.map(|s| format!("{:.0}%", s * 100.0)).unwrap_or_default(),
+                    resource.url.clone(),
+                ]),
+            }
+        }
+        write!(f, "{}", table)?;
+
+        if self.uncompressed.is_empty() {
+            writeln!(f, "\n✓ All text resources are served compressed")
+        } else {
+            writeln!(f, "\n⚠️  Served uncompressed even though the client accepts {}:", ACCEPT)?;
+            for url in &self.uncompressed {
+                writeln!(f, "  - {}", url)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+impl Summary for CompressionReport {
+    fn summary(&self) -> String {
+        let page = &self.resources[0];
+        let page = match (&page.encoding, page.saving) {
+            _ if page.error.is_some() => "HTML failed".to_string(),
+            (Some(encoding), Some(saving)) => format!("HTML {} ({:.0}% smaller)", encoding, saving * 100.0),
+            _ => "HTML uncompressed".to_string(),
+        };
+        format!("{}, {} uncompressed resources", page, self.uncompressed.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fetched(encoding: Option<&str>, size: usize) -> Fetched {
+        Ok((encoding.map(str::to_string), size))
+    }
+
+    #[test]
+    fn compares_plain_and_compressed_sizes() {
+        let gzip = compare("https://example.com/", "HTML", fetched(None, 40_000), fetched(Some("gzip"), 10_000));
+        assert_eq!(gzip.encoding.as_deref(), Some("gzip"));
+        assert_eq!(gzip.uncompressed_bytes, Some(40_000));
+        assert_eq!(gzip.compressed_bytes, Some(10_000));
+        assert_eq!(gzip.saving, Some(0.75));
+        assert_eq!(gzip.error, None);
+
+        let identity = compare("https://example.com/a.css", "CSS", fetched(None, 5000), fetched(Some("identity"), 5000));
+        assert_eq!(identity.encoding, None);
+        assert_eq!(identity.saving, Some(0.0));
+
+        let empty = compare("https://example.com/b.js", "JS", fetched(None, 0), fetched(None, 0));
+        assert_eq!(empty.saving, None);
+    }
+
+    #[test]
+    fn a_failed_request_is_an_error() {
+        let failed = compare(
+            "https://example.com/gone.js",
+            "JS",
+            Err("HTTP status client error (404 Not Found)".to_string()),
+            fetched(Some("br"), 100),
+        );
+        assert_eq!(failed.error.as_deref(), Some("HTTP status client error (404 Not Found)"));
+        assert_eq!(failed.compressed_bytes, None);
+    }
+
+    #[test]
+    fn flags_large_uncompressed_resources_only() {
+        let resources = vec![
+            compare("https://example.com/", "HTML", fetched(None, 40_000), fetched(Some("br"), 8_000)),
+            compare("https://example.com/big.css", "CSS", fetched(None, 20_000), fetched(None, 20_000)),
+            compare("https://example.com/tiny.js", "JS", fetched(None, 300), fetched(None, 300)),
+            compare("https://example.com/gone.js", "JS", Err("timed out".to_string()), fetched(None, 0)),
+        ];
+        assert_eq!(uncompressed(&resources), vec!["https://example.com/big.css"]);
+    }
+
+    #[test]
+    fn prefers_the_declared_length() {
+        assert_eq!(transferred_size(Some(10_000), 10_000), 10_000);
+        assert_eq!(transferred_size(None, 4_096), 4_096);
+    }
+
+    #[test]
+    fn measures_the_bytes_as_sent() {
+        use std::io::{BufRead, BufReader, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            for _ in 0..2 {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(&stream);
+                let mut accept = String::new();
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("accept-encoding:") {
+                        accept = value.trim().to_string();
+                    }
+                    line.clear();
+                }
+                // Not valid gzip: a client that tried to decompress it would fail.
+                let response = if accept.contains("gzip") {
+                    "HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\nContent-Length: 4\r\nConnection: close\r\n\r\nabcd"
+                } else {
+                    assert_eq!(accept, IDENTITY);
+                    "HTTP/1.1 200 OK\r\nContent-Length: 10\r\nConnection: close\r\n\r\nabcdefghij"
+                };
+                (&stream).write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        let resource = measure(&raw_http_client().unwrap(), &url, "HTML");
+        server.join().unwrap();
+        assert_eq!(resource.error, None);
+        assert_eq!(resource.encoding.as_deref(), Some("gzip"));
+        assert_eq!(resource.uncompressed_bytes, Some(10));
+        assert_eq!(resource.compressed_bytes, Some(4));
+    }
+
+    #[test]
+    fn finds_stylesheets_and_scripts() {
+        let base = Url::parse("https://example.com/blog/").unwrap();
+        let page = r#"
+            <link rel="stylesheet" href="/wp-content/style.css?ver=1">
+            <link rel="icon" href="/favicon.ico">
+            <link rel='stylesheet' href='theme.css'>
+            <script src="https://cdn.example.net/lib.js"></script>
+            <script>inline()</script>
+            <script src="data:text/javascript,1"></script>
+            <script src="https://cdn.example.net/lib.js"></script>
+        "#;
+        assert_eq!(
+            asset_urls(&base, page),
+            vec![
+                ("CSS", "https://example.com/wp-content/style.css?ver=1".to_string()),
+                ("CSS", "https://example.com/blog/theme.css".to_string()),
+                ("JS", "https://cdn.example.net/lib.js".to_string()),
+            ]
+        );
+    }
+}
@@ -1,106 +1,786 @@
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{Result, eyre};
 use hickory_resolver::{
-    config::{ResolverConfig, ResolverOpts},
-    proto::rr::{Name, RecordType, RData},
-    Resolver,
+    error::{ResolveError, ResolveErrorKind},
+    proto::op::ResponseCode,
+    proto::rr::{
+        rdata::{caa, SVCB},
+        Name, RData, RecordType,
+    },
+    TokioAsyncResolver,
 };
+use openssl::base64;
+use serde::Serialize;
+use std::fmt;
+use std::net::IpAddr;
 use std::str::FromStr;
-use crate::utils::{extract_hostname, extract_root_domain};
-
-pub fn dns(url: String) -> Result<()> {
-    let hostname = extract_hostname(&url)?;
-    let name = Name::from_str(&hostname)
-        .map_err(|e| color_eyre::eyre::eyre!("Invalid hostname: {}", e))?;
-
-    println!("DNS lookup for: {}\n", hostname);
-
-    let resolver = Resolver::new(ResolverConfig::default(), ResolverOpts::default())
-        .map_err(|e| color_eyre::eyre::eyre!("Could not create resolver: {}", e))?;
-
-    let record_types = vec![
-        (RecordType::A, "A (IPv4)"),
-        (RecordType::AAAA, "AAAA (IPv6)"),
-        (RecordType::MX, "MX (Mail)"),
-        (RecordType::TXT, "TXT"),
-        (RecordType::NS, "NS (Name Servers)"),
-        (RecordType::CNAME, "CNAME"),
-        (RecordType::SOA, "SOA"),
-    ];
-
-    for (record_type, label) in record_types {
-        lookup_dns_records(&resolver, &name, record_type, label)?;
-    }
-
-    let root_domain = extract_root_domain(&hostname)?;
-    if root_domain != hostname {
-        println!("--- Records for root domain: {} ---\n", root_domain);
-        if let Ok(root_name) = Name::from_str(&root_domain) {
-            let root_record_types = vec![
-                (RecordType::MX, "MX (Mail)"),
-                (RecordType::TXT, "TXT"),
-                (RecordType::NS, "NS (Name Servers)"),
-                (RecordType::SOA, "SOA"),
-            ];
-
-            for (record_type, label) in root_record_types {
-                lookup_dns_records(&resolver, &root_name, record_type, label)?;
+use crate::batch::Summary;
+use crate::network::engine::{self, ResolverChoice};
+
+#[derive(Serialize)]
+pub struct DnsReport {
+    pub hostname: String,
+    /// The resolver chosen with `--resolver`, `--dot` or `--doh`.
+    pub resolver: Option<String>,
+    pub records: Vec<RecordSet>,
+    pub root_domain: Option<String>,
+    pub root_records: Vec<RecordSet>,
+    /// Queries that failed, e.g. with a timeout, SERVFAIL or REFUSED, as
+    /// opposed to names without records of the type.
+    pub errors: Vec<LookupError>,
+}
+
+#[derive(Serialize)]
+pub struct LookupError {
+    pub name: String,
+    pub record_type: String,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct RecordSet {
+    pub record_type: String,
+    #[serde(skip)]
+    pub label: String,
+    pub values: Vec<DnsRecord>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "UPPERCASE")]
+pub enum DnsRecord {
+    A { address: String },
+    Aaaa { address: String },
+    Mx { exchange: String, preference: u16 },
+    Txt { text: String },
+    Ns { name: String },
+    Cname { name: String },
+    Soa {
+        mname: String,
+        rname: String,
+        serial: u32,
+        refresh: i32,
+        retry: i32,
+        expire: i32,
+        minimum: u32,
+    },
+    Caa {
+        /// 128 when the issuer-critical flag is set.
+        flags: u8,
+        tag: String,
+        value: String,
+    },
+    Srv { priority: u16, weight: u16, port: u16, target: String },
+    Ptr { name: String },
+    Ds {
+        key_tag: u16,
+        algorithm: u8,
+        digest_type: u8,
+        digest: String,
+    },
+    Dnskey {
+        flags: u16,
+        protocol: u8,
+        algorithm: u8,
+        /// Computed from the key, to match it with the parent's DS record.
+        key_tag: u16,
+        public_key: String,
+    },
+    Https { priority: u16, target: String, params: Vec<String> },
+    Svcb { priority: u16, target: String, params: Vec<String> },
+    Other { data: String },
+}
+
+/// Queried on the host when no `--type` is given.
+const HOST_RECORD_TYPES: [RecordType; 7] = [
+    RecordType::A,
+    RecordType::AAAA,
+    RecordType::MX,
+    RecordType::TXT,
+    RecordType::NS,
+    RecordType::CNAME,
+    RecordType::SOA,
+];
+
+/// The in-addr.arpa or ip6.arpa name a PTR lookup on `hostname` queries,
+/// when it is an IP address.
+fn reverse_name(hostname: &str) -> Option<Name> {
+    hostname.parse::<IpAddr>().ok().map(Name::from)
+}
+
+/// Queried on the zone apex when no `--type` is given.
+const ROOT_RECORD_TYPES: [RecordType; 4] = [RecordType::MX, RecordType::TXT, RecordType::NS, RecordType::SOA];
+
+/// Looks up the host's records through the chosen resolver. Without
+/// `record_types`, the common host records and the zone apex's mail and
+/// name server records are looked up; otherwise only the given types on the
+/// host. An IP address is looked up under its reverse DNS name.
+pub fn lookup_dns(hostname: &str, record_types: &[String], choice: &ResolverChoice) -> Result<DnsReport> {
+    // An IPv6 address is not even a valid DNS name.
+    let reverse_name = reverse_name(hostname);
+    let name = match &reverse_name {
+        Some(reverse_name) => reverse_name.clone(),
+        None => Name::from_str(hostname).map_err(|e| eyre!("Invalid hostname: {}", e))?,
+    };
+
+    let host_types = if record_types.is_empty() {
+        HOST_RECORD_TYPES.to_vec()
+    } else {
+        parse_record_types(record_types)?
+    };
+    let mut queries: Vec<(Name, RecordType)> = host_types
+        .iter()
+        .map(|record_type| (name.clone(), *record_type))
+        .collect();
+
+    // The zone apex, not the last two labels, so `shop.example.co.uk` gets
+    // the records of `example.co.uk` as the mail and propagation checks do.
+    let resolver = choice.resolver();
+    let root_domain = if record_types.is_empty() && reverse_name.is_none() {
+        let root_domain = zone_apex(&resolver, hostname);
+        (root_domain != hostname.trim_end_matches('.')).then_some(root_domain)
+    } else {
+        None
+    };
+    if let Some(root_name) = root_domain.as_deref().and_then(|d| Name::from_str(d).ok()) {
+        queries.extend(ROOT_RECORD_TYPES.iter().map(|record_type| (root_name.clone(), *record_type)));
+    }
+
+    // All record types (for the host and its zone apex) are queried at once.
+    let lookups = queries
+        .iter()
+        .cloned()
+        .map(|(name, record_type)| {
+            let resolver = resolver.clone();
+            async move { lookup_dns_records(&resolver, name, record_type).await }
+        })
+        .collect();
+    let mut results = engine::block_on_all(lookups);
+
+    // A resolver that fails every query is unreachable or refusing us, which
+    // should not look like a name without records.
+    if results.iter().all(|result| result.is_err()) {
+        if let Some(Err(e)) = results.first() {
+            return Err(eyre!("DNS lookup failed: {}", e));
+        }
+    }
+
+    let mut errors = Vec::new();
+    let mut sets = Vec::new();
+    for ((name, record_type), result) in queries.into_iter().zip(results.drain(..)) {
+        match result {
+            Ok(set) => sets.push(set),
+            Err(message) => {
+                errors.push(LookupError {
+                    name: name.to_string(),
+                    record_type: record_type.to_string(),
+                    message,
+                });
+                sets.push(None);
             }
         }
     }
+    let root_sets = sets.split_off(host_types.len());
 
-    Ok(())
+    Ok(DnsReport {
+        hostname: hostname.to_string(),
+        resolver: (!choice.is_default()).then(|| choice.to_string()),
+        records: sets.into_iter().flatten().collect(),
+        root_domain,
+        root_records: root_sets.into_iter().flatten().collect(),
+        errors,
+    })
 }
 
-fn lookup_dns_records(
-    resolver: &Resolver,
-    name: &Name,
+/// The records of one type at `name`, or `None` when there are none. Any
+/// other failure is returned as a message.
+async fn lookup_dns_records(
+    resolver: &TokioAsyncResolver,
+    name: Name,
     record_type: RecordType,
-    label: &str,
-) -> Result<()> {
-    let response = match resolver.lookup(name.clone(), record_type) {
+) -> std::result::Result<Option<RecordSet>, String> {
+    let response = match resolver.lookup(name, record_type).await {
         Ok(response) => response,
-        Err(_) => {
-            return Ok(());
+        Err(e) => return failure(e).map(|_| None),
+    };
+
+    let values: Vec<DnsRecord> = response
+        .record_iter()
+        .filter_map(|record| record.data())
+        .map(convert_rdata)
+        .collect();
+
+    if values.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(RecordSet {
+        record_type: record_type.to_string(),
+        label: record_label(record_type),
+        values,
+    }))
+}
+
+/// Parses `--type` values such as `a` or `CAA`.
+fn parse_record_types(values: &[String]) -> Result<Vec<RecordType>> {
+    let mut record_types = Vec::new();
+    for value in values {
+        let record_type = RecordType::from_str(&value.trim().to_ascii_uppercase())
+            .map_err(|_| eyre!("Unknown record type '{}'", value))?;
+        if !record_types.contains(&record_type) {
+            record_types.push(record_type);
         }
+    }
+    Ok(record_types)
+}
+
+/// The heading for a record type, with a short explanation for the less
+/// familiar ones.
+fn record_label(record_type: RecordType) -> String {
+    let description = match record_type {
+        RecordType::A => "IPv4",
+        RecordType::AAAA => "IPv6",
+        RecordType::MX => "Mail",
+        RecordType::NS => "Name Servers",
+        RecordType::CAA => "Certificate Authorities",
+        RecordType::SRV => "Services",
+        RecordType::PTR => "Reverse DNS",
+        RecordType::DS => "DNSSEC Delegation",
+        RecordType::DNSKEY => "DNSSEC Keys",
+        RecordType::HTTPS | RecordType::SVCB => "Service Binding",
+        _ => return record_type.to_string(),
     };
+    format!("{} ({})", record_type, description)
+}
 
-    let records = response.record_iter();
-    let mut has_records = false;
-    for record in records {
-        if !has_records {
-            println!("{} records:", label);
-            has_records = true;
-        }
-        if let Some(rdata) = record.data() {
-                match rdata {
-                    RData::A(ipv4) => println!("  - {}", ipv4),
-                    RData::AAAA(ipv6) => println!("  - {}", ipv6),
-                    RData::MX(mx) => println!("  - {} (priority: {})", mx.exchange(), mx.preference()),
-                    RData::TXT(txt) => {
-                        let text: String = txt.iter()
-                            .filter_map(|bytes| std::str::from_utf8(bytes).ok())
-                            .collect();
-                        println!("  - {}", text);
-                    }
-                    RData::NS(ns) => println!("  - {}", ns),
-                    RData::CNAME(cname) => println!("  - {}", cname),
-                    RData::SOA(soa) => {
-                        println!("  - MNAME: {}", soa.mname());
-                        println!("    RNAME: {}", soa.rname());
-                        println!("    Serial: {}", soa.serial());
-                        println!("    Refresh: {}", soa.refresh());
-                        println!("    Retry: {}", soa.retry());
-                        println!("    Expire: {}", soa.expire());
-                        println!("    Minimum: {}", soa.minimum());
+/// The TXT records published at `name`, each with its character strings
+/// joined. A missing name gives an empty list; a failed lookup (a timeout,
+/// SERVFAIL or REFUSED) is returned as a message.
+pub async fn lookup_txt(resolver: TokioAsyncResolver, name: String) -> std::result::Result<Vec<String>, String> {
+    let name = Name::from_str(&name).map_err(|e| format!("Invalid hostname: {}", e))?;
+    let records = lookup_dns_records(&resolver, name, RecordType::TXT)
+        .await?
+        .map(|set| {
+            set.values
+                .into_iter()
+                .filter_map(|record| match record {
+                    DnsRecord::Txt { text } => Some(text),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(records)
+}
+
+/// The apex of the DNS zone `hostname` belongs to: the closest parent domain
+/// with an SOA record of its own. For `shop.example.co.uk` this is
+/// `example.co.uk`, where the last two labels would give the registry's
+/// `co.uk`. All parent domains are queried at once.
+pub fn zone_apex(resolver: &TokioAsyncResolver, hostname: &str) -> String {
+    let candidates = parent_domains(hostname);
+    let answers = engine::block_on_all(
+        candidates
+            .iter()
+            .map(|name| has_soa(resolver.clone(), name.clone()))
+            .collect(),
+    );
+    let apexes: Vec<&String> = candidates.iter().zip(answers).filter(|(_, soa)| *soa).map(|(name, _)| name).collect();
+    closest_apex(hostname, |name| apexes.iter().any(|apex| *apex == name))
+}
+
+/// `hostname` and its parent domains, longest first, down to two labels.
+fn parent_domains(hostname: &str) -> Vec<String> {
+    let labels: Vec<&str> = hostname.trim_end_matches('.').split('.').collect();
+    (0..labels.len().saturating_sub(1)).map(|i| labels[i..].join(".")).collect()
+}
+
+/// The first of `hostname`'s parent domains that `is_apex` accepts, or the
+/// last two labels when none does, e.g. when the resolver is unreachable.
+fn closest_apex(hostname: &str, is_apex: impl Fn(&str) -> bool) -> String {
+    let candidates = parent_domains(hostname);
+    candidates
+        .iter()
+        .find(|name| is_apex(name))
+        .or(candidates.last())
+        .cloned()
+        .unwrap_or_else(|| hostname.to_string())
+}
+
+/// Whether `name` has an SOA record itself, rather than one reached through
+/// a CNAME.
+async fn has_soa(resolver: TokioAsyncResolver, name: String) -> bool {
+    let Ok(name) = Name::from_str(&name) else {
+        return false;
+    };
+    resolver.lookup(name.clone(), RecordType::SOA).await.is_ok_and(|lookup| {
+        lookup
+            .record_iter()
+            .any(|record| record.record_type() == RecordType::SOA && record.name() == &name)
+    })
+}
+
+/// One resolver's answer for a name and record type.
+pub struct Answer {
+    /// Each record on one line, sorted so answers can be compared.
+    pub values: Vec<String>,
+    pub ttl: Option<u32>,
+}
+
+/// Looks up a single record type. A name without records of that type gives
+/// an empty answer; any other failure is returned as a message.
+pub async fn lookup_answer(
+    resolver: TokioAsyncResolver,
+    name: String,
+    record_type: RecordType,
+) -> std::result::Result<Answer, String> {
+    let name = Name::from_str(&name).map_err(|e| format!("Invalid hostname: {}", e))?;
+
+    match resolver.lookup(name, record_type).await {
+        Ok(lookup) => {
+            let mut values: Vec<String> = lookup
+                .record_iter()
+                .filter(|record| record.record_type() == record_type)
+                .filter_map(|record| record.data())
+                .map(|rdata| convert_rdata(rdata).value())
+                .collect();
+            values.sort();
+            values.dedup();
+            let ttl = lookup.record_iter().map(|record| record.ttl()).min();
+            Ok(Answer { values, ttl })
+        }
+        Err(e) => failure(e).map(|_| Answer {
+            values: Vec::new(),
+            ttl: None,
+        }),
+    }
+}
+
+/// `Ok` when a failed lookup only means there are no records of the type.
+/// hickory also reports SERVFAIL, REFUSED and other error responses as "no
+/// records found", so the response code tells them apart.
+fn failure(error: ResolveError) -> std::result::Result<(), String> {
+    match error.kind() {
+        ResolveErrorKind::NoRecordsFound {
+            response_code: ResponseCode::NoError | ResponseCode::NXDomain,
+            ..
+        } => Ok(()),
+        ResolveErrorKind::NoRecordsFound { response_code, .. } => {
+            Err(format!("{} ({})", response_code, u16::from(*response_code)))
+        }
+        _ => Err(error.to_string()),
+    }
+}
+
+fn convert_rdata(rdata: &RData) -> DnsRecord {
+    match rdata {
+        RData::A(ipv4) => DnsRecord::A { address: ipv4.to_string() },
+        RData::AAAA(ipv6) => DnsRecord::Aaaa { address: ipv6.to_string() },
+        RData::MX(mx) => DnsRecord::Mx {
+            exchange: mx.exchange().to_string(),
+            preference: mx.preference(),
+        },
+        RData::TXT(txt) => DnsRecord::Txt {
+            text: txt.iter()
+                .filter_map(|bytes| std::str::from_utf8(bytes).ok())
+                .collect(),
+        },
+        RData::NS(ns) => DnsRecord::Ns { name: ns.to_string() },
+        RData::CNAME(cname) => DnsRecord::Cname { name: cname.to_string() },
+        RData::SOA(soa) => DnsRecord::Soa {
+            mname: soa.mname().to_string(),
+            rname: soa.rname().to_string(),
+            serial: soa.serial(),
+            refresh: soa.refresh(),
+            retry: soa.retry(),
+            expire: soa.expire(),
+            minimum: soa.minimum(),
+        },
+        RData::CAA(caa) => DnsRecord::Caa {
+            flags: if caa.issuer_critical() { 128 } else { 0 },
+            tag: caa.tag().as_str().to_string(),
+            value: match caa.value() {
+                caa::Value::Issuer(name, options) => {
+                    let mut value = name.as_ref().map(|name| name.to_string()).unwrap_or_default();
+                    for option in options {
+                        value.push_str(&format!("; {}", option));
                     }
-                    _ => println!("  - {:?}", rdata),
+                    value
                 }
+                caa::Value::Url(url) => url.to_string(),
+                caa::Value::Unknown(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+            },
+        },
+        RData::SRV(srv) => DnsRecord::Srv {
+            priority: srv.priority(),
+            weight: srv.weight(),
+            port: srv.port(),
+            target: srv.target().to_string(),
+        },
+        RData::PTR(ptr) => DnsRecord::Ptr { name: ptr.to_string() },
+        RData::HTTPS(https) => {
+            let (priority, target, params) = service_binding(https);
+            DnsRecord::Https { priority, target, params }
+        }
+        RData::SVCB(svcb) => {
+            let (priority, target, params) = service_binding(svcb);
+            DnsRecord::Svcb { priority, target, params }
+        }
+        // Without DNSSEC support in the resolver, DS and DNSKEY records
+        // arrive undecoded.
+        RData::Unknown { code: RecordType::DS, rdata } => {
+            convert_ds(rdata.anything()).unwrap_or_else(|| DnsRecord::Other { data: rdata.to_string() })
         }
+        RData::Unknown { code: RecordType::DNSKEY, rdata } => {
+            convert_dnskey(rdata.anything()).unwrap_or_else(|| DnsRecord::Other { data: rdata.to_string() })
+        }
+        _ => DnsRecord::Other { data: rdata.to_string() },
+    }
+}
+
+/// The priority, target and parameters of an HTTPS or SVCB record.
+fn service_binding(svcb: &SVCB) -> (u16, String, Vec<String>) {
+    let params = svcb
+        .svc_params()
+        .iter()
+        .map(|(key, value)| {
+            // List values such as alpn and ipv4hint display with a trailing comma.
+            let value = value.to_string();
+            let value = value.trim_end_matches(',');
+            if value.is_empty() { key.to_string() } else { format!("{}={}", key, value) }
+        })
+        .collect();
+    (svcb.svc_priority(), svcb.target_name().to_string(), params)
+}
+
+/// Decodes DS record data (RFC 4034, section 5.1).
+fn convert_ds(data: &[u8]) -> Option<DnsRecord> {
+    let (header, digest) = data.split_at_checked(4)?;
+    Some(DnsRecord::Ds {
+        key_tag: u16::from_be_bytes([header[0], header[1]]),
+        algorithm: header[2],
+        digest_type: header[3],
+        digest: hex::encode_upper(digest),
+    })
+}
+
+/// Decodes DNSKEY record data (RFC 4034, section 2.1) and computes the key
+/// tag as in appendix B.
+fn convert_dnskey(data: &[u8]) -> Option<DnsRecord> {
+    let (header, public_key) = data.split_at_checked(4)?;
+    let sum: u32 = data
+        .iter()
+        .enumerate()
+        .map(|(i, byte)| if i % 2 == 0 { u32::from(*byte) << 8 } else { u32::from(*byte) })
+        .sum();
+    Some(DnsRecord::Dnskey {
+        flags: u16::from_be_bytes([header[0], header[1]]),
+        protocol: header[2],
+        algorithm: header[3],
+        key_tag: (sum.wrapping_add(sum >> 16) & 0xffff) as u16,
+        public_key: base64::encode_block(public_key),
+    })
+}
+
+/// The mnemonic of a DNSSEC signing algorithm number.
+fn dnssec_algorithm(number: u8) -> &'static str {
+    match number {
+        5 => "RSASHA1",
+        7 => "RSASHA1-NSEC3-SHA1",
+        8 => "RSASHA256",
+        10 => "RSASHA512",
+        13 => "ECDSAP256SHA256",
+        14 => "ECDSAP384SHA384",
+        15 => "ED25519",
+        16 => "ED448",
+        _ => "unknown",
     }
+}
 
-    if has_records {
-        println!();
+/// The name of a DS digest type number.
+fn ds_digest_type(number: u8) -> &'static str {
+    match number {
+        1 => "SHA-1",
+        2 => "SHA-256",
+        4 => "SHA-384",
+        _ => "unknown",
     }
+}
 
-    Ok(())
+impl DnsRecord {
+    /// The record's data on one line, in zone file order.
+    pub fn value(&self) -> String {
+        match self {
+            DnsRecord::A { address } | DnsRecord::Aaaa { address } => address.clone(),
+            DnsRecord::Mx { exchange, preference } => format!("{} {}", preference, exchange),
+            DnsRecord::Txt { text } => text.clone(),
+            DnsRecord::Ns { name } | DnsRecord::Cname { name } => name.clone(),
+            DnsRecord::Soa { mname, rname, serial, refresh, retry, expire, minimum } => format!(
+                "{} {} {} {} {} {} {}",
+                mname, rname, serial, refresh, retry, expire, minimum
+            ),
+            DnsRecord::Caa { flags, tag, value } => format!("{} {} \"{}\"", flags, tag, value),
+            DnsRecord::Srv { priority, weight, port, target } => {
+                format!("{} {} {} {}", priority, weight, port, target)
+            }
+            DnsRecord::Ptr { name } => name.clone(),
+            DnsRecord::Ds { key_tag, algorithm, digest_type, digest } => {
+                format!("{} {} {} {}", key_tag, algorithm, digest_type, digest)
+            }
+            DnsRecord::Dnskey { flags, protocol, algorithm, public_key, .. } => {
+                format!("{} {} {} {}", flags, protocol, algorithm, public_key)
+            }
+            DnsRecord::Https { priority, target, params } | DnsRecord::Svcb { priority, target, params } => {
+                let mut value = format!("{} {}", priority, target);
+                for param in params {
+                    value.push(' ');
+                    value.push_str(param);
+                }
+                value
+            }
+            DnsRecord::Other { data } => data.clone(),
+        }
+    }
+}
+
+impl fmt::Display for DnsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "DNS lookup for: {}", self.hostname)?;
+        if let Some(resolver) = &self.resolver {
+            writeln!(f, "Resolver: {}", resolver)?;
+        }
+        writeln!(f)?;
+
+        for set in &self.records {
+            write!(f, "{}", set)?;
+        }
+
+        if let Some(root_domain) = &self.root_domain {
+            writeln!(f, "--- Records for root domain: {} ---\n", root_domain)?;
+            for set in &self.root_records {
+                write!(f, "{}", set)?;
+            }
+        }
+
+        if !self.errors.is_empty() {
+            writeln!(f, "⚠️  Failed lookups (the records may exist; the resolver did not answer):")?;
+            for error in &self.errors {
+                writeln!(f, "  - {} {}: {}", error.name, error.record_type, error.message)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for RecordSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} records:", self.label)?;
+        for value in &self.values {
+            writeln!(f, "{}", value)?;
+        }
+        writeln!(f)
+    }
+}
+
+impl fmt::Display for DnsRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DnsRecord::A { address } | DnsRecord::Aaaa { address } => write!(f, "  - {}", address),
+            DnsRecord::Mx { exchange, preference } => {
+                write!(f, "  - {} (priority: {})", exchange, preference)
+            }
+            DnsRecord::Txt { text } => write!(f, "  - {}", text),
+            DnsRecord::Ns { name } | DnsRecord::Cname { name } => write!(f, "  - {}", name),
+            DnsRecord::Soa { mname, rname, serial, refresh, retry, expire, minimum } => {
+                writeln!(f, "  - MNAME: {}", mname)?;
+                writeln!(f, "    RNAME: {}", rname)?;
+                writeln!(f, "    Serial: {}", serial)?;
+                writeln!(f, "    Refresh: {}", refresh)?;
+                writeln!(f, "    Retry: {}", retry)?;
+                writeln!(f, "    Expire: {}", expire)?;
+                write!(f, "    Minimum: {}", minimum)
+            }
+            DnsRecord::Caa { flags, tag, value } => {
+                write!(f, "  - {} \"{}\"", tag, value)?;
+                if flags & 128 != 0 {
+                    write!(f, " (critical)")?;
+                }
+                Ok(())
+            }
+            DnsRecord::Srv { priority, weight, port, target } => {
+                write!(f, "  - {}:{} (priority: {}, weight: {})", target, port, priority, weight)
+            }
+            DnsRecord::Ptr { name } => write!(f, "  - {}", name),
+            DnsRecord::Ds { key_tag, algorithm, digest_type, digest } => write!(
+                f,
+                "  - Key tag {}, algorithm {} ({}), {} digest {}",
+                key_tag,
+                algorithm,
+                dnssec_algorithm(*algorithm),
+                ds_digest_type(*digest_type),
+                digest
+            ),
+            DnsRecord::Dnskey { flags, algorithm, key_tag, .. } => write!(
+                f,
+                "  - {} ({}), algorithm {} ({}), key tag {}",
+                if flags & 1 != 0 { "Key-signing key" } else { "Zone-signing key" },
+                flags,
+                algorithm,
+                dnssec_algorithm(*algorithm),
+                key_tag
+            ),
+            DnsRecord::Https { priority, target, params } | DnsRecord::Svcb { priority, target, params } => {
+                // Priority 0 is alias mode, which points at another name
+                // without parameters.
+                if *priority == 0 {
+                    return write!(f, "  - Alias of {}", target);
+                }
+                // A target of "." means the record's own name.
+                let target = if target == "." { "same name" } else { target.as_str() };
+                write!(f, "  - {} (priority: {})", target, priority)?;
+                for param in params {
+                    write!(f, " {}", param)?;
+                }
+                Ok(())
+            }
+            DnsRecord::Other { data } => write!(f, "  - {}", data),
+        }
+    }
+}
+
+impl Summary for DnsReport {
+    fn summary(&self) -> String {
+        let addresses: Vec<String> = self.records
+            .iter()
+            .flat_map(|set| &set.values)
+            .filter_map(|record| match record {
+                DnsRecord::A { address } | DnsRecord::Aaaa { address } => Some(address.clone()),
+                _ => None,
+            })
+            .collect();
+
+        let summary = if addresses.is_empty() {
+            format!("{} record types, no A/AAAA", self.records.len())
+        } else {
+            addresses.join(", ")
+        };
+        match self.errors.len() {
+            0 => summary,
+            failed => format!("{} ({} lookups failed)", summary, failed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hickory_resolver::proto::error::ProtoResult;
+    use hickory_resolver::proto::serialize::binary::{BinDecoder, Restrict};
+
+    #[test]
+    fn reverse_names() {
+        assert_eq!(reverse_name("192.0.2.1").unwrap().to_string(), "1.2.0.192.in-addr.arpa.");
+        assert_eq!(
+            reverse_name("2001:db8::1").unwrap().to_string(),
+            "1.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa."
+        );
+        assert_eq!(reverse_name("example.com"), None);
+    }
+
+    /// Decodes record data the way the resolver does for an answer.
+    fn decode(record_type: RecordType, data: &[u8]) -> ProtoResult<RData> {
+        let length = u16::try_from(data.len()).unwrap();
+        RData::read(&mut BinDecoder::new(data), record_type, Restrict::new(length))
+    }
+
+    fn value(record_type: RecordType, data: &[u8]) -> String {
+        convert_rdata(&decode(record_type, data).unwrap()).value()
+    }
+
+    // The root zone's 2017 key signing key.
+    const ROOT_KSK: &str = "AwEAAaz/tAm8yTn4Mfeh5eyI96WSVexTBAvkMgJzkKTOiW1vkIbzxeF3+/4RgWOq7HrxRixHlFlExOLAJr5emLvN7SWXgnLh4+B5xQlNVz8Og8kvArMtNROxVQuCaSnIDdD5LKyWbRd2n9WGe2R8PzgCmr3EgVLrjyBxWezF0jLHwVN8efS3rCj/EWgvIWgb9tarpVUDK/b58Da+sqqls3eNbuv7pr+eoZG+SrDK6nWeL3c6H5Apxz7LjVc1uTIdsIXxuOLYA4/ilBmSVIzuDWfdRUfhHdY6+cn8HFRm+2hM8AnXGXws9555KrUB5qihylGa8subX2Nn6UwNR1AkUTV74bU=";
+
+    #[test]
+    fn ds_records_are_decoded() {
+        let mut data = vec![0x4f, 0x66, 8, 2];
+        data.extend(hex::decode("E06D44B80B8F1D39A95C0B0D7C65D08458E880409BBC683457104237C7F8EC8D").unwrap());
+        assert_eq!(
+            value(RecordType::DS, &data),
+            "20326 8 2 E06D44B80B8F1D39A95C0B0D7C65D08458E880409BBC683457104237C7F8EC8D"
+        );
+    }
+
+    #[test]
+    fn dnskey_records_are_decoded_with_their_key_tag() {
+        let mut data = vec![0x01, 0x01, 3, 8];
+        data.extend(base64::decode_block(ROOT_KSK).unwrap());
+        let record = convert_rdata(&decode(RecordType::DNSKEY, &data).unwrap());
+        assert_eq!(record.value(), format!("257 3 8 {}", ROOT_KSK));
+        assert!(matches!(record, DnsRecord::Dnskey { key_tag: 20326, .. }));
+    }
+
+    #[test]
+    fn truncated_ds_and_dnskey_records_are_kept_undecoded() {
+        for record_type in [RecordType::DS, RecordType::DNSKEY] {
+            assert_eq!(value(record_type, &[0x4f, 0x66, 8]), "T2YI");
+            assert_eq!(value(record_type, &[]), "");
+        }
+        // A DS record with no digest is still well formed up to its header.
+        assert_eq!(value(RecordType::DS, &[0x4f, 0x66, 8, 2]), "20326 8 2 ");
+    }
+
+    #[test]
+    fn caa_records_are_formatted_in_zone_file_order() {
+        let mut issue = vec![0, 5];
+        issue.extend(b"issueletsencrypt.org; validationmethods=dns-01");
+        assert_eq!(value(RecordType::CAA, &issue), "0 issue \"letsencrypt.org; validationmethods=dns-01\"");
+
+        let mut iodef = vec![128, 5];
+        iodef.extend(b"iodefmailto:security@example.com");
+        assert_eq!(value(RecordType::CAA, &iodef), "128 iodef \"mailto:security@example.com\"");
+
+        let mut forbidden = vec![0, 9];
+        forbidden.extend(b"issuewild;");
+        assert_eq!(value(RecordType::CAA, &forbidden), "0 issuewild \"\"");
+    }
+
+    #[test]
+    fn truncated_caa_records_fail_to_decode() {
+        for data in [&[][..], &[0], &[0, 5, b'i', b's'], &[0, 0]] {
+            assert!(decode(RecordType::CAA, data).is_err(), "{:?}", data);
+        }
+    }
+
+    #[test]
+    fn https_records_list_their_parameters() {
+        // Priority 1, the owner name as target, alpn=h2,h3.
+        let data = [0, 1, 0, 0, 1, 0, 6, 2, b'h', b'2', 2, b'h', b'3'];
+        assert_eq!(value(RecordType::HTTPS, &data), "1 . alpn=h2,h3");
+    }
+
+    #[test]
+    fn parent_domains_stop_at_two_labels() {
+        assert_eq!(parent_domains("shop.example.co.uk"), vec!["shop.example.co.uk", "example.co.uk", "co.uk"]);
+        assert_eq!(parent_domains("example.com."), vec!["example.com"]);
+        assert!(parent_domains("localhost").is_empty());
+    }
+
+    #[test]
+    fn closest_apex_skips_the_registry_zone_of_a_cctld() {
+        let zones = ["example.co.uk", "co.uk"];
+        let is_apex = |name: &str| zones.contains(&name);
+        assert_eq!(closest_apex("shop.example.co.uk", is_apex), "example.co.uk");
+        assert_eq!(closest_apex("example.co.uk", is_apex), "example.co.uk");
+    }
+
+    #[test]
+    fn closest_apex_finds_delegated_subdomains() {
+        let zones = ["blog.example.com", "example.com"];
+        assert_eq!(closest_apex("www.blog.example.com", |name| zones.contains(&name)), "blog.example.com");
+    }
+
+    #[test]
+    fn closest_apex_falls_back_to_the_last_two_labels() {
+        assert_eq!(closest_apex("www.example.com", |_| false), "example.com");
+        assert_eq!(closest_apex("localhost", |_| false), "localhost");
+    }
 }
@@ -0,0 +1,225 @@
+use hickory_resolver::{
+    config::{NameServerConfig, NameServerConfigGroup, Protocol, ResolverConfig, ResolverOpts},
+    TokioAsyncResolver,
+};
+use std::fmt;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+/// Number of sites (or other independent jobs) checked at the same time
+/// when `--concurrency` is not given.
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+/// The process-wide async runtime shared by all network lookups.
+pub fn runtime() -> &'static Runtime {
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .thread_name("wpust-network")
+            .build()
+            .expect("Failed to start the network runtime")
+    })
+}
+
+/// An async resolver using Google Public DNS, bound to the shared runtime.
+/// The system's resolv.conf is deliberately not used, so results are the
+/// same on every machine and are not rewritten by ISP resolvers.
+pub fn resolver() -> TokioAsyncResolver {
+    let _guard = runtime().enter();
+    TokioAsyncResolver::tokio(ResolverConfig::default(), ResolverOpts::default())
+}
+
+/// An async resolver that sends every query to `servers` only, over UDP
+/// with TCP fallback. It starts with an empty cache and retries a query
+/// once, so a lost packet is not mistaken for a missing record while an
+/// unreachable server is still reported instead of stalling the lookup.
+pub fn resolver_for(servers: &[SocketAddr]) -> TokioAsyncResolver {
+    let mut config = ResolverConfig::new();
+    for server in servers {
+        config.add_name_server(NameServerConfig::new(*server, Protocol::Udp));
+        config.add_name_server(NameServerConfig::new(*server, Protocol::Tcp));
+    }
+    let mut opts = ResolverOpts::default();
+    opts.timeout = Duration::from_secs(3);
+    opts.attempts = 2;
+
+    let _guard = runtime().enter();
+    TokioAsyncResolver::tokio(config, opts)
+}
+
+/// How queries reach a resolver chosen on the command line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transport {
+    /// Plain DNS over UDP, retried over TCP.
+    Plain,
+    /// DNS-over-TLS.
+    Tls,
+    /// DNS-over-HTTPS.
+    Https,
+}
+
+impl Transport {
+    pub fn default_port(self) -> u16 {
+        match self {
+            Transport::Plain => 53,
+            Transport::Tls => 853,
+            Transport::Https => 443,
+        }
+    }
+}
+
+/// A name server given as `IP` or `IP:PORT`. Without a port, the standard
+/// one for the transport is used.
+#[derive(Clone, Copy, Debug)]
+pub struct ServerAddr {
+    pub ip: IpAddr,
+    pub port: Option<u16>,
+}
+
+impl ServerAddr {
+    /// The server's socket address, on `default_port` when none was given.
+    pub fn socket_addr(&self, default_port: u16) -> SocketAddr {
+        SocketAddr::new(self.ip, self.port.unwrap_or(default_port))
+    }
+}
+
+/// The resolver picked with `--resolver`, `--dot`, `--doh` and `--tls-name`.
+#[derive(Clone, Debug)]
+pub struct ResolverChoice {
+    pub server: Option<ServerAddr>,
+    pub transport: Transport,
+    pub tls_name: Option<String>,
+}
+
+impl ResolverChoice {
+    /// Whether anything other than the default resolver was chosen.
+    pub fn is_default(&self) -> bool {
+        self.server.is_none() && self.transport == Transport::Plain
+    }
+
+    fn address(&self) -> Option<SocketAddr> {
+        self.server.map(|server| server.socket_addr(self.transport.default_port()))
+    }
+
+    /// An async resolver for the choice. Without a server, encrypted
+    /// transports go to Cloudflare's IPv4 addresses and plain DNS uses
+    /// [`resolver`]. For TLS and HTTPS the server's certificate must cover
+    /// `tls_name`, or its IP address when no name is given.
+    pub fn resolver(&self) -> TokioAsyncResolver {
+        let config = match (self.address(), self.transport) {
+            (None, Transport::Plain) => return resolver(),
+            (None, transport) => {
+                let servers = if transport == Transport::Tls {
+                    NameServerConfigGroup::cloudflare_tls()
+                } else {
+                    NameServerConfigGroup::cloudflare_https()
+                };
+                // IPv4 only: on hosts without a working IPv6 route, a TLS
+                // connection to the IPv6 addresses stalls instead of failing.
+                let mut config = ResolverConfig::new();
+                for server in servers.iter().filter(|server| server.socket_addr.is_ipv4()) {
+                    config.add_name_server(server.clone());
+                }
+                config
+            }
+            (Some(address), Transport::Plain) => return resolver_for(&[address]),
+            (Some(address), transport) => {
+                let protocol = if transport == Transport::Tls { Protocol::Tls } else { Protocol::Https };
+                let mut name_server = NameServerConfig::new(address, protocol);
+                name_server.tls_dns_name = Some(self.tls_name.clone().unwrap_or_else(|| address.ip().to_string()));
+                let mut config = ResolverConfig::new();
+                config.add_name_server(name_server);
+                config
+            }
+        };
+        let mut opts = ResolverOpts::default();
+        opts.timeout = Duration::from_secs(5);
+        opts.attempts = 2;
+
+        let _guard = runtime().enter();
+        TokioAsyncResolver::tokio(config, opts)
+    }
+}
+
+impl fmt::Display for ResolverChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.address() {
+            Some(address) => write!(f, "{} ({})", address, self.transport)?,
+            None if self.transport == Transport::Plain => write!(f, "default ({})", self.transport)?,
+            None => write!(f, "Cloudflare ({})", self.transport)?,
+        }
+        if let Some(name) = &self.tls_name {
+            write!(f, ", certificate name {}", name)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Transport::Plain => "DNS",
+            Transport::Tls => "DNS-over-TLS",
+            Transport::Https => "DNS-over-HTTPS",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// Runs all futures concurrently on the shared runtime and returns their
+/// outputs in the original order.
+pub fn block_on_all<F>(futures: Vec<F>) -> Vec<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let runtime = runtime();
+    let handles: Vec<_> = futures.into_iter().map(|future| runtime.spawn(future)).collect();
+
+    runtime.block_on(async {
+        let mut outputs = Vec::with_capacity(handles.len());
+        for handle in handles {
+            match handle.await {
+                Ok(output) => outputs.push(output),
+                Err(e) => std::panic::resume_unwind(e.into_panic()),
+            }
+        }
+        outputs
+    })
+}
+
+/// Calls `job` for every item on at most `limit` worker threads and returns
+/// the results in the original order.
+pub fn run_bounded<T, R, F>(items: Vec<T>, limit: usize, job: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync,
+{
+    let total = items.len();
+    let workers = limit.clamp(1, total.max(1));
+    let queue = Mutex::new(items.into_iter().enumerate());
+    let results = Mutex::new(Vec::with_capacity(total));
+
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let next = queue.lock().expect("work queue poisoned").next();
+                let Some((index, item)) = next else {
+                    break;
+                };
+                let result = job(item);
+                results.lock().expect("result list poisoned").push((index, result));
+            });
+        }
+    });
+
+    let mut results = results.into_inner().expect("result list poisoned");
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
@@ -0,0 +1,480 @@
+use crate::batch::Summary;
+use color_eyre::eyre::{Result, eyre};
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+use std::fmt;
+
+use crate::utils::http_client;
+
+/// HSTS max-age below six months is too short to protect returning visitors.
+const HSTS_MIN_AGE: u64 = 15_552_000;
+
+#[derive(Serialize)]
+pub struct HeadersReport {
+    pub hostname: String,
+    pub url: String,
+    pub status: u16,
+    pub score: u32,
+    pub grade: char,
+    pub checks: Vec<HeaderCheck>,
+    pub leaks: Vec<InformationLeak>,
+}
+
+#[derive(Serialize)]
+pub struct HeaderCheck {
+    pub header: &'static str,
+    pub value: Option<String>,
+    pub rating: Rating,
+    pub recommendation: Option<String>,
+    #[serde(skip)]
+    penalty: u32,
+}
+
+#[derive(Serialize)]
+pub struct InformationLeak {
+    pub header: String,
+    pub value: String,
+    pub recommendation: &'static str,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Rating {
+    Good,
+    Weak,
+    Missing,
+}
+
+/// Fetches the site's front page (following redirects) and grades its
+/// security headers from A to F.
+pub fn audit_headers(hostname: &str) -> Result<HeadersReport> {
+    let response = http_client()?
+        .get(format!("https://{}", hostname))
+        .send()
+        .map_err(|e| eyre!("HTTP request failed: {}", e))?;
+
+    let url = response.url().clone();
+    let headers = response.headers();
+
+    let checks = vec![
+        check_hsts(headers, url.scheme() == "https"),
+        check_csp(headers),
+        check_frame_options(headers),
+        check_content_type_options(headers),
+        check_referrer_policy(headers),
+        check_permissions_policy(headers),
+    ];
+    let leaks = find_leaks(headers);
+    let score = score(&checks, &leaks);
+
+    Ok(HeadersReport {
+        hostname: hostname.to_string(),
+        url: url.to_string(),
+        status: response.status().as_u16(),
+        score,
+        grade: grade(score),
+        checks,
+        leaks,
+    })
+}
+
+/// 100 minus the penalties of the checks and 5 points per leak.
+fn score(checks: &[HeaderCheck], leaks: &[InformationLeak]) -> u32 {
+    let penalty: u32 = checks.iter().map(|check| check.penalty).sum::<u32>() + 5 * leaks.len() as u32;
+    100u32.saturating_sub(penalty)
+}
+
+fn grade(score: u32) -> char {
+    match score {
+        90.. => 'A',
+        80..=89 => 'B',
+        70..=79 => 'C',
+        60..=69 => 'D',
+        _ => 'F',
+    }
+}
+
+/// All values of a header joined with ", ", as if sent on one line.
+fn header_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    let values: Vec<&str> = headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .collect();
+    (!values.is_empty()).then(|| values.join(", "))
+}
+
+fn good(header: &'static str, value: String, recommendation: Option<String>) -> HeaderCheck {
+    HeaderCheck { header, value: Some(value), rating: Rating::Good, recommendation, penalty: 0 }
+}
+
+fn weak(header: &'static str, value: String, penalty: u32, recommendation: String) -> HeaderCheck {
+    HeaderCheck { header, value: Some(value), rating: Rating::Weak, recommendation: Some(recommendation), penalty }
+}
+
+fn missing(header: &'static str, penalty: u32, recommendation: &str) -> HeaderCheck {
+    HeaderCheck {
+        header,
+        value: None,
+        rating: Rating::Missing,
+        recommendation: Some(recommendation.to_string()),
+        penalty,
+    }
+}
+
+fn check_hsts(headers: &HeaderMap, https: bool) -> HeaderCheck {
+    const HEADER: &str = "Strict-Transport-Security";
+    const ADD: &str = "Add `Strict-Transport-Security: max-age=31536000; includeSubDomains`";
+
+    if !https {
+        return missing(HEADER, 25, "The page is served over plain HTTP; redirect to HTTPS and add HSTS there");
+    }
+    let Some(value) = header_value(headers, HEADER) else {
+        return missing(HEADER, 25, ADD);
+    };
+
+    let directives: Vec<String> = value.split(';').map(|d| d.trim().to_ascii_lowercase()).collect();
+    let max_age = directives
+        .iter()
+        .find_map(|d| d.strip_prefix("max-age="))
+        .and_then(|age| age.trim_matches('"').parse::<u64>().ok());
+    let include_subdomains = directives.iter().any(|d| d == "includesubdomains");
+    let preload = directives.iter().any(|d| d == "preload");
+
+    match max_age {
+        None | Some(0) => weak(HEADER, value, 20, "Set a max-age; without one (or with 0) HSTS is disabled".to_string()),
+        Some(age) if age < HSTS_MIN_AGE => weak(
+            HEADER,
+            value,
+            10,
+            format!("max-age is {} days; use at least 180 days, ideally one year (31536000)", age / 86_400),
+        ),
+        Some(_) if !include_subdomains => weak(
+            HEADER,
+            value,
+            5,
+            "Add includeSubDomains so subdomains cannot be downgraded either".to_string(),
+        ),
+        Some(_) if !preload => good(
+            HEADER,
+            value,
+            Some("Consider adding preload and submitting the domain to hstspreload.org".to_string()),
+        ),
+        Some(_) => good(HEADER, value, None),
+    }
+}
+
+fn check_csp(headers: &HeaderMap) -> HeaderCheck {
+    const HEADER: &str = "Content-Security-Policy";
+
+    let Some(value) = header_value(headers, HEADER) else {
+        return match header_value(headers, "Content-Security-Policy-Report-Only") {
+            Some(value) => weak(
+                HEADER,
+                format!("(report-only) {}", value),
+                15,
+                "The policy is only reported, not enforced; switch to Content-Security-Policy once it is clean".to_string(),
+            ),
+            None => missing(
+                HEADER,
+                20,
+                "Add a Content-Security-Policy; start with Content-Security-Policy-Report-Only to find violations",
+            ),
+        };
+    };
+
+    // script-src governs scripts; default-src is the fallback without it.
+    // Names are compared whole, so script-src-elem is not taken for
+    // script-src.
+    let directive = |name: &str| {
+        value
+            .split(';')
+            .map(str::trim)
+            .find(|d| d.split_whitespace().next().is_some_and(|d| d.eq_ignore_ascii_case(name)))
+            .map(str::to_ascii_lowercase)
+    };
+    let scripts = directive("script-src").or_else(|| directive("default-src"));
+
+    match scripts {
+        None => weak(HEADER, value, 10, "Add a script-src or default-src directive to restrict scripts".to_string()),
+        Some(scripts) if scripts.contains("'unsafe-inline'") || scripts.contains("'unsafe-eval'") => weak(
+            HEADER,
+            value,
+            10,
+            "Scripts allow 'unsafe-inline' or 'unsafe-eval', which defeats most XSS protection; use nonces or hashes"
+                .to_string(),
+        ),
+        Some(_) => good(HEADER, value, None),
+    }
+}
+
+fn check_frame_options(headers: &HeaderMap) -> HeaderCheck {
+    const HEADER: &str = "X-Frame-Options";
+
+    let frame_ancestors = header_value(headers, "Content-Security-Policy")
+        .is_some_and(|csp| csp.to_ascii_lowercase().contains("frame-ancestors"));
+
+    match header_value(headers, HEADER) {
+        Some(value) if matches!(value.to_ascii_uppercase().as_str(), "DENY" | "SAMEORIGIN") => {
+            good(HEADER, value, None)
+        }
+        Some(value) if frame_ancestors => good(
+            HEADER,
+            value,
+            Some("Invalid value, but CSP frame-ancestors already prevents clickjacking".to_string()),
+        ),
+        Some(value) => weak(
+            HEADER,
+            value,
+            10,
+            "Use DENY or SAMEORIGIN; ALLOW-FROM is obsolete and ignored by browsers".to_string(),
+        ),
+        None if frame_ancestors => HeaderCheck {
+            header: HEADER,
+            value: None,
+            rating: Rating::Good,
+            recommendation: Some("Not set, but CSP frame-ancestors prevents clickjacking".to_string()),
+            penalty: 0,
+        },
+        None => missing(HEADER, 15, "Add `X-Frame-Options: SAMEORIGIN` to prevent clickjacking"),
+    }
+}
+
+fn check_content_type_options(headers: &HeaderMap) -> HeaderCheck {
+    const HEADER: &str = "X-Content-Type-Options";
+
+    match header_value(headers, HEADER) {
+        Some(value) if value.eq_ignore_ascii_case("nosniff") => good(HEADER, value, None),
+        Some(value) => weak(HEADER, value, 10, "The only valid value is nosniff".to_string()),
+        None => missing(HEADER, 10, "Add `X-Content-Type-Options: nosniff`"),
+    }
+}
+
+fn check_referrer_policy(headers: &HeaderMap) -> HeaderCheck {
+    const HEADER: &str = "Referrer-Policy";
+
+    match header_value(headers, HEADER) {
+        // With a list of policies the browser uses the last one it knows.
+        Some(value) => match value.rsplit(',').next().unwrap_or_default().trim().to_ascii_lowercase().as_str() {
+            "unsafe-url" | "no-referrer-when-downgrade" => weak(
+                HEADER,
+                value,
+                5,
+                "This leaks full URLs to other sites; use strict-origin-when-cross-origin".to_string(),
+            ),
+            _ => good(HEADER, value, None),
+        },
+        None => missing(HEADER, 10, "Add `Referrer-Policy: strict-origin-when-cross-origin`"),
+    }
+}
+
+fn check_permissions_policy(headers: &HeaderMap) -> HeaderCheck {
+    const HEADER: &str = "Permissions-Policy";
+
+    match header_value(headers, HEADER) {
+        Some(value) => good(HEADER, value, None),
+        None => match header_value(headers, "Feature-Policy") {
+            Some(value) => weak(
+                HEADER,
+                format!("(Feature-Policy) {}", value),
+                5,
+                "Feature-Policy is deprecated; replace it with Permissions-Policy".to_string(),
+            ),
+            None => missing(
+                HEADER,
+                5,
+                "Add a Permissions-Policy that disables unused features, e.g. `camera=(), microphone=(), geolocation=()`",
+            ),
+        },
+    }
+}
+
+/// Headers that reveal software and versions, which helps attackers pick
+/// known exploits.
+fn find_leaks(headers: &HeaderMap) -> Vec<InformationLeak> {
+    let mut leaks = Vec::new();
+
+    for (name, recommendation) in [
+        ("x-powered-by", "Remove X-Powered-By (expose_php = Off in php.ini)"),
+        ("x-aspnet-version", "Remove X-AspNet-Version"),
+        ("x-aspnetmvc-version", "Remove X-AspNetMvc-Version"),
+        ("x-generator", "Remove X-Generator"),
+    ] {
+        if let Some(value) = header_value(headers, name) {
+            leaks.push(InformationLeak { header: name.to_string(), value, recommendation });
+        }
+    }
+
+    // A bare "nginx" or "cloudflare" is harmless, a version number is not.
+    if let Some(value) = header_value(headers, "server").filter(|v| v.chars().any(|c| c.is_ascii_digit())) {
+        leaks.push(InformationLeak {
+            header: "server".to_string(),
+            value,
+            recommendation: "Hide the version (server_tokens off in nginx, ServerTokens Prod in Apache)",
+        });
+    }
+
+    leaks
+}
+
+impl fmt::Display for HeadersReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Security headers for: {}", self.hostname)?;
+        writeln!(f, "Final URL: {} (HTTP {})\n", self.url, self.status)?;
+
+        for check in &self.checks {
+            let marker = match check.rating {
+                Rating::Good => "✓",
+                Rating::Weak => "⚠️ ",
+                Rating::Missing => "❌",
+            };
+            match &check.value {
+                Some(value) => writeln!(f, "{} {}: {}", marker, check.header, value)?,
+                None => writeln!(f, "{} {}: missing", marker, check.header)?,
+            }
+            if let Some(recommendation) = &check.recommendation {
+                writeln!(f, "     → {}", recommendation)?;
+            }
+        }
+
+        if !self.leaks.is_empty() {
+            writeln!(f, "\nInformation leaks:")?;
+            for leak in &self.leaks {
+                writeln!(f, "⚠️  {}: {}", leak.header, leak.value)?;
+                writeln!(f, "     → {}", leak.recommendation)?;
+            }
+        }
+
+        writeln!(f, "\nScore: {}/100, grade: {}", self.score, self.grade)
+    }
+}
+
+impl Summary for HeadersReport {
+    fn summary(&self) -> String {
+        let missing: Vec<&str> = self
+            .checks
+            .iter()
+            .filter(|check| check.rating == Rating::Missing)
+            .map(|check| check.header)
+            .collect();
+        if missing.is_empty() {
+            format!("grade {}, {} leaks", self.grade, self.leaks.len())
+        } else {
+            format!("grade {}, missing {}, {} leaks", self.grade, missing.join(", "), self.leaks.len())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderName, HeaderValue};
+
+    fn headers(pairs: &[(&str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(
+                HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                HeaderValue::from_str(value).unwrap(),
+            );
+        }
+        headers
+    }
+
+    fn rating(check: HeaderCheck) -> (Rating, u32) {
+        (check.rating, check.penalty)
+    }
+
+    #[test]
+    fn hsts_ratings() {
+        let hsts = |value: &str| rating(check_hsts(&headers(&[("strict-transport-security", value)]), true));
+        assert_eq!(hsts("max-age=63072000; includeSubDomains; preload"), (Rating::Good, 0));
+        assert_eq!(hsts("max-age=31536000; includeSubDomains"), (Rating::Good, 0));
+        assert_eq!(hsts("max-age=31536000"), (Rating::Weak, 5));
+        assert_eq!(hsts("max-age=86400; includeSubDomains"), (Rating::Weak, 10));
+        assert_eq!(hsts("max-age=0"), (Rating::Weak, 20));
+        assert_eq!(rating(check_hsts(&headers(&[]), true)), (Rating::Missing, 25));
+        assert_eq!(rating(check_hsts(&headers(&[]), false)), (Rating::Missing, 25));
+    }
+
+    #[test]
+    fn csp_directives_are_matched_by_their_whole_name() {
+        let csp = |value: &str| rating(check_csp(&headers(&[("content-security-policy", value)])));
+        assert_eq!(csp("default-src 'self'"), (Rating::Good, 0));
+        assert_eq!(csp("SCRIPT-SRC 'self'; object-src 'none'"), (Rating::Good, 0));
+        assert_eq!(csp("default-src 'self'; script-src 'self' 'unsafe-inline'"), (Rating::Weak, 10));
+        // script-src-elem is not script-src, so default-src applies.
+        assert_eq!(csp("script-src-elem 'self'; default-src 'self' 'unsafe-eval'"), (Rating::Weak, 10));
+        assert_eq!(csp("script-src-elem 'self'; img-src *"), (Rating::Weak, 10));
+        assert_eq!(csp("upgrade-insecure-requests"), (Rating::Weak, 10));
+    }
+
+    #[test]
+    fn csp_report_only_and_missing() {
+        let report_only = check_csp(&headers(&[("content-security-policy-report-only", "default-src 'self'")]));
+        assert_eq!(rating(report_only), (Rating::Weak, 15));
+        assert_eq!(rating(check_csp(&headers(&[]))), (Rating::Missing, 20));
+    }
+
+    #[test]
+    fn frame_options_accept_csp_frame_ancestors() {
+        let frame = |pairs: &[(&str, &str)]| rating(check_frame_options(&headers(pairs)));
+        assert_eq!(frame(&[("x-frame-options", "sameorigin")]), (Rating::Good, 0));
+        assert_eq!(frame(&[("x-frame-options", "ALLOW-FROM https://example.com")]), (Rating::Weak, 10));
+        assert_eq!(frame(&[("content-security-policy", "frame-ancestors 'none'")]), (Rating::Good, 0));
+        assert_eq!(frame(&[]), (Rating::Missing, 15));
+    }
+
+    #[test]
+    fn other_headers() {
+        let map = headers(&[
+            ("x-content-type-options", "nosniff"),
+            ("referrer-policy", "no-referrer, unsafe-url"),
+            ("feature-policy", "camera 'none'"),
+        ]);
+        assert_eq!(rating(check_content_type_options(&map)), (Rating::Good, 0));
+        assert_eq!(rating(check_referrer_policy(&map)), (Rating::Weak, 5));
+        assert_eq!(rating(check_permissions_policy(&map)), (Rating::Weak, 5));
+    }
+
+    #[test]
+    fn leaks_report_versions_only() {
+        let leaks = find_leaks(&headers(&[("server", "nginx"), ("x-powered-by", "PHP/8.2.1")]));
+        assert_eq!(leaks.len(), 1);
+        assert_eq!(leaks[0].header, "x-powered-by");
+
+        let leaks = find_leaks(&headers(&[("server", "Apache/2.4.57 (Debian)")]));
+        assert_eq!(leaks[0].header, "server");
+    }
+
+    #[test]
+    fn scores_and_grades() {
+        let hardened = headers(&[
+            ("strict-transport-security", "max-age=63072000; includeSubDomains; preload"),
+            ("content-security-policy", "default-src 'self'; frame-ancestors 'self'"),
+            ("x-content-type-options", "nosniff"),
+            ("referrer-policy", "strict-origin-when-cross-origin"),
+            ("permissions-policy", "camera=()"),
+        ]);
+        let all = |map: &HeaderMap| {
+            vec![
+                check_hsts(map, true),
+                check_csp(map),
+                check_frame_options(map),
+                check_content_type_options(map),
+                check_referrer_policy(map),
+                check_permissions_policy(map),
+            ]
+        };
+        assert_eq!(score(&all(&hardened), &[]), 100);
+        assert_eq!(score(&all(&HeaderMap::new()), &[]), 15);
+        assert_eq!(score(&all(&hardened), &find_leaks(&headers(&[("x-powered-by", "PHP/8.2")]))), 95);
+
+        assert_eq!(grade(100), 'A');
+        assert_eq!(grade(90), 'A');
+        assert_eq!(grade(89), 'B');
+        assert_eq!(grade(70), 'C');
+        assert_eq!(grade(60), 'D');
+        assert_eq!(grade(15), 'F');
+    }
+}
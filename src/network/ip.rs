@@ -1,27 +1,69 @@
 use color_eyre::eyre::Result;
 use dns_lookup::{lookup_addr, lookup_host};
-use crate::utils::extract_hostname;
+use serde::Serialize;
+use std::fmt;
+use std::net::IpAddr;
+use crate::batch::Summary;
+use crate::network::engine;
 
-pub fn ip(url: String) -> Result<()> {
-    let hostname = extract_hostname(&url)?;
+#[derive(Serialize)]
+pub struct IpReport {
+    pub hostname: String,
+    pub addresses: Vec<IpAddress>,
+}
 
-    println!("IP lookup for: {}\n", hostname);
+#[derive(Serialize)]
+pub struct IpAddress {
+    pub address: IpAddr,
+    pub reverse_dns: Option<String>,
+}
 
-    match lookup_host(&hostname) {
-        Ok(ips) => {
-            println!("IP addresses:");
-            for ip in ips {
-                println!("  - {}", ip);
+pub fn lookup_ip(hostname: &str) -> Result<IpReport> {
+    let ips = lookup_host(hostname)
+        .map_err(|e| color_eyre::eyre::eyre!("IP lookup failed: {}", e))?;
 
-                if let Ok(host) = lookup_addr(&ip) {
-                    println!("    (reverse DNS: {})", host);
-                }
+    // Reverse lookups are blocking system calls, so each runs on its own
+    // blocking thread of the shared runtime.
+    let lookups = ips
+        .map(|ip| async move {
+            let reverse_dns = tokio::task::spawn_blocking(move || lookup_addr(&ip).ok())
+                .await
+                .ok()
+                .flatten();
+            IpAddress {
+                address: ip,
+                reverse_dns,
+            }
+        })
+        .collect();
+    let addresses = engine::block_on_all(lookups);
+
+    Ok(IpReport {
+        hostname: hostname.to_string(),
+        addresses,
+    })
+}
+
+impl fmt::Display for IpReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "IP lookup for: {}\n", self.hostname)?;
+        writeln!(f, "IP addresses:")?;
+        for ip in &self.addresses {
+            writeln!(f, "  - {}", ip.address)?;
+            if let Some(host) = &ip.reverse_dns {
+                writeln!(f, "    (reverse DNS: {})", host)?;
             }
         }
-        Err(e) => {
-            return Err(color_eyre::eyre::eyre!("IP lookup failed: {}", e));
-        }
+        Ok(())
     }
+}
 
-    Ok(())
+impl Summary for IpReport {
+    fn summary(&self) -> String {
+        self.addresses
+            .iter()
+            .map(|ip| ip.address.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
 }
@@ -0,0 +1,886 @@
+use color_eyre::eyre::Result;
+use openssl::base64;
+use openssl::pkey::PKey;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::batch::Summary;
+use crate::network::dns::{lookup_txt, zone_apex};
+use crate::network::engine;
+
+/// TXT records, or why looking them up failed.
+type TxtAnswer = std::result::Result<Vec<String>, String>;
+
+/// Receivers give up with a permanent error after this many DNS lookups
+/// while evaluating SPF (RFC 7208, section 4.6.4).
+const SPF_LOOKUP_LIMIT: usize = 10;
+
+/// Selectors used by common mail providers and WordPress SMTP plugins:
+/// Google Workspace, Microsoft 365, Mailchimp/Mandrill, SendGrid, Mailjet,
+/// Zoho, Fastmail, Proton, Amazon SES and generic cPanel/Plesk setups.
+const COMMON_DKIM_SELECTORS: [&str; 21] = [
+    "default",
+    "dkim",
+    "mail",
+    "google",
+    "selector1",
+    "selector2",
+    "k1",
+    "k2",
+    "k3",
+    "mandrill",
+    "s1",
+    "s2",
+    "mailjet",
+    "zmail",
+    "fm1",
+    "fm2",
+    "fm3",
+    "protonmail",
+    "protonmail2",
+    "amazonses",
+    "smtp",
+];
+
+#[derive(Serialize)]
+pub struct MailReport {
+    pub hostname: String,
+    pub domain: String,
+    pub spf: SpfCheck,
+    pub dmarc: DmarcCheck,
+    pub dkim: DkimCheck,
+}
+
+#[derive(Serialize)]
+pub struct SpfCheck {
+    pub record: Option<String>,
+    /// The qualifier and `all` mechanism ending the record, e.g. `-all`.
+    pub all: Option<String>,
+    pub includes: Vec<String>,
+    /// DNS lookups needed to evaluate the record, nested includes included.
+    pub dns_lookups: usize,
+    /// Why the domain's TXT records could not be looked up.
+    pub lookup_error: Option<String>,
+    pub issues: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct DmarcCheck {
+    pub record: Option<String>,
+    pub policy: Option<String>,
+    pub subdomain_policy: Option<String>,
+    pub pct: u8,
+    pub rua: Vec<String>,
+    pub ruf: Vec<String>,
+    /// Why the `_dmarc` TXT records could not be looked up.
+    pub lookup_error: Option<String>,
+    pub issues: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct DkimCheck {
+    pub selectors_checked: usize,
+    /// Selectors with a key, plus selectors given with `--selector`.
+    pub selectors: Vec<DkimSelector>,
+    pub issues: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct DkimSelector {
+    pub selector: String,
+    pub found: bool,
+    pub key_type: Option<String>,
+    pub key_bits: Option<u32>,
+    /// An empty `p=` tag: the key was deliberately withdrawn.
+    pub revoked: bool,
+    pub lookup_error: Option<String>,
+}
+
+/// Interprets the SPF, DMARC and DKIM records of the site's domain, the apex
+/// of its DNS zone. DKIM keys can only be found by selector, so a list of
+/// common selectors is tried, plus any given in `extra_selectors`.
+pub fn check_mail(hostname: &str, extra_selectors: &[String]) -> Result<MailReport> {
+    let resolver = engine::resolver();
+    let domain = zone_apex(&resolver, hostname);
+
+    let mut selectors: Vec<String> = extra_selectors.to_vec();
+    for selector in COMMON_DKIM_SELECTORS {
+        if !selectors.iter().any(|s| s == selector) {
+            selectors.push(selector.to_string());
+        }
+    }
+
+    // The root and DMARC records and every DKIM selector are queried at once.
+    let mut names = vec![domain.clone(), format!("_dmarc.{}", domain)];
+    names.extend(selectors.iter().map(|selector| format!("{}._domainkey.{}", selector, domain)));
+    let mut results = engine::block_on_all(
+        names
+            .into_iter()
+            .map(|name| lookup_txt(resolver.clone(), name))
+            .collect(),
+    );
+    let dkim_records = results.split_off(2);
+    let dmarc_records = results.pop().unwrap_or(Ok(Vec::new()));
+    let root_records = results.pop().unwrap_or(Ok(Vec::new()));
+
+    Ok(MailReport {
+        hostname: hostname.to_string(),
+        spf: match root_records {
+            Ok(records) => check_spf(&records),
+            Err(error) => SpfCheck::lookup_failed(error),
+        },
+        dmarc: match dmarc_records {
+            Ok(records) => check_dmarc(&records),
+            Err(error) => DmarcCheck::lookup_failed(error),
+        },
+        dkim: check_dkim(&selectors, extra_selectors, dkim_records),
+        domain,
+    })
+}
+
+/// The SPF record among a domain's TXT records; receivers treat more than
+/// one as an error.
+fn spf_records(records: &[String]) -> Vec<&String> {
+    records
+        .iter()
+        .filter(|record| {
+            let lower = record.to_ascii_lowercase();
+            lower == "v=spf1" || lower.starts_with("v=spf1 ")
+        })
+        .collect()
+}
+
+/// The mechanisms of an SPF record that cost a DNS lookup, and the domains
+/// of its `include:` and `redirect=` terms.
+struct SpfTerms {
+    lookups: usize,
+    targets: Vec<String>,
+    all: Option<String>,
+    uses_ptr: bool,
+}
+
+fn parse_spf(record: &str) -> SpfTerms {
+    let mut terms = SpfTerms {
+        lookups: 0,
+        targets: Vec::new(),
+        all: None,
+        uses_ptr: false,
+    };
+
+    for term in record.split_whitespace().skip(1) {
+        let mechanism = term.trim_start_matches(['+', '-', '~', '?']);
+        let (name, argument) = match mechanism.split_once([':', '=', '/']) {
+            Some((name, argument)) => (name.to_ascii_lowercase(), Some(argument)),
+            None => (mechanism.to_ascii_lowercase(), None),
+        };
+        match name.as_str() {
+            "include" | "redirect" => {
+                terms.lookups += 1;
+                if let Some(target) = argument {
+                    terms.targets.push(target.to_string());
+                }
+            }
+            "a" | "mx" | "exists" => terms.lookups += 1,
+            "ptr" => {
+                terms.lookups += 1;
+                terms.uses_ptr = true;
+            }
+            "all" => terms.all = Some(term.to_ascii_lowercase()),
+            _ => {}
+        }
+    }
+
+    terms
+}
+
+fn check_spf(root_records: &[String]) -> SpfCheck {
+    let mut check = SpfCheck {
+        record: None,
+        all: None,
+        includes: Vec::new(),
+        dns_lookups: 0,
+        lookup_error: None,
+        issues: Vec::new(),
+    };
+
+    let records = spf_records(root_records);
+    let Some(record) = records.first() else {
+        check.issues.push("No SPF record: any server can claim to send mail for this domain".to_string());
+        return check;
+    };
+    if records.len() > 1 {
+        check.issues.push(format!(
+            "{} SPF records found; receivers treat this as an error and ignore SPF entirely",
+            records.len()
+        ));
+    }
+    check.record = Some(record.to_string());
+
+    let top = parse_spf(record);
+    check.all = top.all.clone();
+    let mut uses_ptr = top.uses_ptr;
+
+    let resolver = engine::resolver();
+    let followed = follow_includes(top.targets, top.lookups, |level| {
+        engine::block_on_all(
+            level
+                .iter()
+                .map(|target| lookup_txt(resolver.clone(), target.clone()))
+                .collect(),
+        )
+    });
+    check.dns_lookups = followed.lookups;
+    check.includes = followed.includes;
+    uses_ptr |= followed.uses_ptr;
+    for target in followed.missing {
+        check.issues.push(format!(
+            "{} has no SPF record; receivers treat the include as an error",
+            target
+        ));
+    }
+    for (target, error) in followed.failed {
+        check.issues.push(format!(
+            "Lookup of {} failed ({}); its DNS lookups are not counted",
+            target, error
+        ));
+    }
+
+    if check.dns_lookups > SPF_LOOKUP_LIMIT {
+        check.issues.push(format!(
+            "{} DNS lookups, over the limit of {}: SPF fails with a permanent error. Replace includes with ip4:/ip6: ranges or drop unused senders",
+            check.dns_lookups, SPF_LOOKUP_LIMIT
+        ));
+    }
+    if uses_ptr {
+        check.issues.push("The ptr mechanism is deprecated, slow and ignored by some receivers".to_string());
+    }
+    match check.all.as_deref() {
+        Some("+all" | "all") => check
+            .issues
+            .push("+all authorises every server on the internet to send mail for this domain".to_string()),
+        Some("?all") => check
+            .issues
+            .push("?all (neutral) gives no protection against spoofing; use ~all or -all".to_string()),
+        Some(_) => {}
+        None if !record.to_ascii_lowercase().contains("redirect=") => check
+            .issues
+            .push("No all mechanism: mail from unlisted servers gets a neutral result".to_string()),
+        None => {}
+    }
+
+    check
+}
+
+/// What following the `include:` and `redirect=` terms of an SPF record
+/// found.
+struct FollowedIncludes {
+    /// Lookups so far, including those of the top-level record.
+    lookups: usize,
+    /// Every domain followed, once each.
+    includes: Vec<String>,
+    uses_ptr: bool,
+    /// Followed domains without an SPF record.
+    missing: Vec<String>,
+    /// Followed domains whose lookup failed, with the error.
+    failed: Vec<(String, String)>,
+}
+
+/// Follows `targets` one level at a time, calling `lookup` with the domains
+/// of each level to get their TXT records, until everything is resolved or
+/// the limit is clearly exceeded. Receivers evaluate every include, so a
+/// domain included twice is counted twice, but it is only queried once.
+fn follow_includes(
+    targets: Vec<String>,
+    lookups: usize,
+    mut lookup: impl FnMut(&[String]) -> Vec<TxtAnswer>,
+) -> FollowedIncludes {
+    let mut followed = FollowedIncludes {
+        lookups,
+        includes: Vec::new(),
+        uses_ptr: false,
+        missing: Vec::new(),
+        failed: Vec::new(),
+    };
+    let mut answers: HashMap<String, TxtAnswer> = HashMap::new();
+
+    let mut pending = targets;
+    while !pending.is_empty() && followed.lookups <= SPF_LOOKUP_LIMIT * 2 {
+        let mut level: Vec<String> = Vec::new();
+        for target in &pending {
+            if !answers.contains_key(target) && !level.contains(target) {
+                level.push(target.clone());
+            }
+        }
+        let results = lookup(&level);
+        for (target, records) in level.into_iter().zip(results) {
+            followed.includes.push(target.clone());
+            answers.insert(target, records);
+        }
+
+        let mut next = Vec::new();
+        for target in pending {
+            let records = match answers.get(&target) {
+                Some(Ok(records)) => records.as_slice(),
+                Some(Err(error)) => {
+                    if !followed.failed.iter().any(|(failed, _)| *failed == target) {
+                        followed.failed.push((target, error.clone()));
+                    }
+                    continue;
+                }
+                None => &[],
+            };
+            match spf_records(records).first() {
+                Some(record) => {
+                    let nested = parse_spf(record);
+                    followed.lookups += nested.lookups;
+                    followed.uses_ptr |= nested.uses_ptr;
+                    next.extend(nested.targets);
+                }
+                None if !followed.missing.contains(&target) => followed.missing.push(target),
+                None => {}
+            }
+        }
+        pending = next;
+    }
+
+    followed
+}
+
+fn check_dmarc(records: &[String]) -> DmarcCheck {
+    let mut check = DmarcCheck {
+        record: None,
+        policy: None,
+        subdomain_policy: None,
+        pct: 100,
+        rua: Vec::new(),
+        ruf: Vec::new(),
+        lookup_error: None,
+        issues: Vec::new(),
+    };
+
+    let Some(record) = records
+        .iter()
+        .find(|record| record.to_ascii_uppercase().starts_with("V=DMARC1"))
+    else {
+        check.issues.push(
+            "No DMARC record: receivers get no policy for mail that fails SPF and DKIM, and you get no reports"
+                .to_string(),
+        );
+        return check;
+    };
+    check.record = Some(record.clone());
+
+    let addresses = |value: &str| -> Vec<String> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|address| !address.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    for tag in record.split(';') {
+        let Some((name, value)) = tag.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "p" => check.policy = Some(value.to_ascii_lowercase()),
+            "sp" => check.subdomain_policy = Some(value.to_ascii_lowercase()),
+            // An invalid pct is ignored by receivers, so the policy applies
+            // to all failing mail.
+            "pct" => match value.parse::<u8>() {
+                Ok(pct) if pct <= 100 => check.pct = pct,
+                _ => check.issues.push(format!(
+                    "Invalid pct={}: it must be a whole number from 0 to 100, so receivers ignore it",
+                    value
+                )),
+            },
+            "rua" => check.rua = addresses(value),
+            "ruf" => check.ruf = addresses(value),
+            _ => {}
+        }
+    }
+
+    match check.policy.as_deref() {
+        None => check
+            .issues
+            .push("The record has no p= tag, so receivers ignore it".to_string()),
+        Some("none") => check.issues.push(
+            "p=none only monitors: spoofed mail is still delivered. Move to quarantine once reports look clean"
+                .to_string(),
+        ),
+        Some("quarantine" | "reject") => {}
+        Some(other) => check
+            .issues
+            .push(format!("Unknown policy p={}; receivers ignore the record", other)),
+    }
+    if check.subdomain_policy.as_deref() == Some("none") && check.policy.as_deref().is_some_and(|p| p != "none") {
+        check
+            .issues
+            .push("sp=none leaves subdomains unprotected".to_string());
+    }
+    if check.pct < 100 {
+        check.issues.push(format!(
+            "pct={}: the policy applies to only {}% of failing mail",
+            check.pct, check.pct
+        ));
+    }
+    if check.rua.is_empty() {
+        check
+            .issues
+            .push("No rua address: you receive no aggregate reports about who sends mail as you".to_string());
+    }
+
+    check
+}
+
+fn check_dkim(selectors: &[String], extra_selectors: &[String], records: Vec<TxtAnswer>) -> DkimCheck {
+    let mut check = DkimCheck {
+        selectors_checked: selectors.len(),
+        selectors: Vec::new(),
+        issues: Vec::new(),
+    };
+    let mut failed = Vec::new();
+
+    for (selector, records) in selectors.iter().zip(records) {
+        let records = match records {
+            Ok(records) => records,
+            Err(error) => {
+                failed.push(error.clone());
+                check.selectors.push(DkimSelector {
+                    selector: selector.clone(),
+                    found: false,
+                    key_type: None,
+                    key_bits: None,
+                    revoked: false,
+                    lookup_error: Some(error),
+                });
+                continue;
+            }
+        };
+        let record = records.into_iter().find(|record| record.contains("p="));
+        let Some(record) = record else {
+            if extra_selectors.contains(selector) {
+                check.selectors.push(DkimSelector {
+                    selector: selector.clone(),
+                    found: false,
+                    key_type: None,
+                    key_bits: None,
+                    revoked: false,
+                    lookup_error: None,
+                });
+                check
+                    .issues
+                    .push(format!("No DKIM key published for selector '{}'", selector));
+            }
+            continue;
+        };
+
+        let tag = |name: &str| {
+            record.split(';').find_map(|tag| {
+                let (key, value) = tag.split_once('=')?;
+                (key.trim() == name).then(|| value.split_whitespace().collect::<String>())
+            })
+        };
+        let key_type = tag("k").unwrap_or_else(|| "rsa".to_string()).to_ascii_lowercase();
+        let public_key = tag("p").unwrap_or_default();
+        let revoked = public_key.is_empty();
+        let key_bits = if revoked {
+            None
+        } else if key_type == "ed25519" {
+            Some(256)
+        } else {
+            base64::decode_block(&public_key)
+                .ok()
+                .and_then(|der| PKey::public_key_from_der(&der).ok())
+                .map(|key| key.bits())
+        };
+
+        if revoked {
+            check
+                .issues
+                .push(format!("Selector '{}' has an empty key (revoked)", selector));
+        } else if key_type == "rsa" {
+            match key_bits {
+                Some(bits) if bits < 2048 => check.issues.push(format!(
+                    "Selector '{}' uses a {}-bit RSA key; 2048 bits is the current recommendation",
+                    selector, bits
+                )),
+                Some(_) => {}
+                None => check
+                    .issues
+                    .push(format!("Selector '{}' has a key that cannot be decoded", selector)),
+            }
+        }
+
+        check.selectors.push(DkimSelector {
+            selector: selector.clone(),
+            found: true,
+            key_type: Some(key_type),
+            key_bits,
+            revoked,
+            lookup_error: None,
+        });
+    }
+
+    if let Some(error) = failed.first() {
+        check.issues.push(format!(
+            "Lookup failed for {} of {} selectors ({}); keys published under them were not checked",
+            failed.len(),
+            selectors.len(),
+            error
+        ));
+    } else if !check.selectors.iter().any(|s| s.found && !s.revoked) {
+        check.issues.push(
+            "No DKIM key found. The site may use a selector not in the common list; pass it with --selector"
+                .to_string(),
+        );
+    }
+
+    check
+}
+
+impl SpfCheck {
+    /// The check when the domain's TXT records could not be looked up, so
+    /// whether it has an SPF record is unknown.
+    fn lookup_failed(error: String) -> Self {
+        SpfCheck {
+            record: None,
+            all: None,
+            includes: Vec::new(),
+            dns_lookups: 0,
+            issues: vec![format!("SPF lookup failed ({}); whether the domain has an SPF record is unknown", error)],
+            lookup_error: Some(error),
+        }
+    }
+}
+
+impl DmarcCheck {
+    /// The check when the `_dmarc` TXT records could not be looked up.
+    fn lookup_failed(error: String) -> Self {
+        DmarcCheck {
+            record: None,
+            policy: None,
+            subdomain_policy: None,
+            pct: 100,
+            rua: Vec::new(),
+            ruf: Vec::new(),
+            issues: vec![format!("DMARC lookup failed ({}); whether the domain has a DMARC record is unknown", error)],
+            lookup_error: Some(error),
+        }
+    }
+}
+
+impl MailReport {
+    fn issue_count(&self) -> usize {
+        self.spf.issues.len() + self.dmarc.issues.len() + self.dkim.issues.len()
+    }
+}
+
+fn write_issues(f: &mut fmt::Formatter<'_>, issues: &[String], ok: &str) -> fmt::Result {
+    if issues.is_empty() {
+        return writeln!(f, "  ✓ {}", ok);
+    }
+    for issue in issues {
+        writeln!(f, "  ⚠️  {}", issue)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for MailReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Email authentication for: {}\n", self.domain)?;
+
+        writeln!(f, "SPF")?;
+        if let Some(record) = &self.spf.record {
+            writeln!(f, "  Record: {}", record)?;
+            writeln!(f, "  DNS lookups: {} of {}", self.spf.dns_lookups, SPF_LOOKUP_LIMIT)?;
+            if !self.spf.includes.is_empty() {
+                writeln!(f, "  Includes: {}", self.spf.includes.join(", "))?;
+            }
+            let all = match self.spf.all.as_deref() {
+                Some("-all") => "-all (fail)",
+                Some("~all") => "~all (soft fail)",
+                Some("?all") => "?all (neutral)",
+                Some(_) => "+all (pass)",
+                None => "not set",
+            };
+            writeln!(f, "  Other servers: {}", all)?;
+        }
+        write_issues(f, &self.spf.issues, "No SPF problems found")?;
+
+        writeln!(f, "\nDMARC")?;
+        if let Some(record) = &self.dmarc.record {
+            writeln!(f, "  Record: {}", record)?;
+            writeln!(
+                f,
+                "  Policy: {} (subdomains: {}), applied to {}% of failing mail",
+                self.dmarc.policy.as_deref().unwrap_or("missing"),
+                self.dmarc
+                    .subdomain_policy
+                    .as_deref()
+                    .or(self.dmarc.policy.as_deref())
+                    .unwrap_or("missing"),
+                self.dmarc.pct
+            )?;
+            let list = |addresses: &[String]| {
+                if addresses.is_empty() { "none".to_string() } else { addresses.join(", ") }
+            };
+            writeln!(f, "  Aggregate reports (rua): {}", list(&self.dmarc.rua))?;
+            writeln!(f, "  Failure reports (ruf): {}", list(&self.dmarc.ruf))?;
+        }
+        write_issues(f, &self.dmarc.issues, "No DMARC problems found")?;
+
+        writeln!(f, "\nDKIM ({} selectors checked)", self.dkim.selectors_checked)?;
+        for selector in &self.dkim.selectors {
+            if let Some(error) = &selector.lookup_error {
+                writeln!(f, "  ✗ {}: lookup failed ({})", selector.selector, error)?;
+                continue;
+            }
+            match (selector.found, selector.revoked, selector.key_bits) {
+                (false, _, _) => writeln!(f, "  ✗ {}: not found", selector.selector)?,
+                (true, true, _) => writeln!(f, "  ✗ {}: revoked", selector.selector)?,
+                (true, false, bits) => writeln!(
+                    f,
+                    "  - {}: {} key{}",
+                    selector.selector,
+                    selector.key_type.as_deref().unwrap_or("rsa"),
+                    bits.map(|bits| format!(", {} bits", bits)).unwrap_or_default()
+                )?,
+            }
+        }
+        write_issues(f, &self.dkim.issues, "No DKIM problems found")
+    }
+}
+
+impl Summary for MailReport {
+    fn summary(&self) -> String {
+        let spf = match (&self.spf.record, &self.spf.lookup_error) {
+            (Some(_), _) => "SPF",
+            (None, Some(_)) => "SPF lookup failed",
+            (None, None) => "no SPF",
+        };
+        let dmarc = match (&self.dmarc.policy, &self.dmarc.lookup_error) {
+            (Some(policy), _) => format!("DMARC {}", policy),
+            (None, Some(_)) => "DMARC lookup failed".to_string(),
+            (None, None) => "no DMARC".to_string(),
+        };
+        let keys = self.dkim.selectors.iter().filter(|s| s.found && !s.revoked).count();
+        format!("{}, {}, {} DKIM keys, {} issues", spf, dmarc, keys, self.issue_count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(records: &[&str]) -> Vec<String> {
+        records.iter().map(|record| record.to_string()).collect()
+    }
+
+    /// A `lookup` for `follow_includes` answering from `zone`, which also
+    /// records every domain queried.
+    fn fake_lookup<'a>(
+        zone: &'a [(&'a str, &'a str)],
+        queried: &'a mut Vec<String>,
+    ) -> impl FnMut(&[String]) -> Vec<TxtAnswer> + 'a {
+        move |level| {
+            level
+                .iter()
+                .map(|target| {
+                    queried.push(target.clone());
+                    if target.starts_with("servfail.") {
+                        return Err("Server Failure (2)".to_string());
+                    }
+                    Ok(zone
+                        .iter()
+                        .filter(|(name, _)| name == target)
+                        .map(|(_, record)| record.to_string())
+                        .collect())
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn parse_spf_counts_lookup_mechanisms() {
+        let terms = parse_spf("v=spf1 a/24 mx:mail.example.com ip4:192.0.2.0/24 include:_spf.google.com exists:%{i}.x.example -all");
+        assert_eq!(terms.lookups, 4);
+        assert_eq!(terms.targets, vec!["_spf.google.com"]);
+        assert_eq!(terms.all.as_deref(), Some("-all"));
+        assert!(!terms.uses_ptr);
+    }
+
+    #[test]
+    fn parse_spf_redirect_and_ptr() {
+        let terms = parse_spf("v=spf1 ptr:example.com redirect=_spf.example.net");
+        assert_eq!(terms.lookups, 2);
+        assert_eq!(terms.targets, vec!["_spf.example.net"]);
+        assert_eq!(terms.all, None);
+        assert!(terms.uses_ptr);
+    }
+
+    #[test]
+    fn parse_spf_qualifiers_on_all() {
+        assert_eq!(parse_spf("v=spf1 +all").all.as_deref(), Some("+all"));
+        assert_eq!(parse_spf("v=spf1 ?ALL").all.as_deref(), Some("?all"));
+        assert_eq!(parse_spf("v=spf1 mx ~all").all.as_deref(), Some("~all"));
+        assert_eq!(parse_spf("v=spf1 mx").all, None);
+    }
+
+    #[test]
+    fn check_spf_flags_weak_and_missing_all() {
+        let plus = check_spf(&records(&["v=spf1 ip4:192.0.2.1 +all"]));
+        assert!(plus.issues.iter().any(|issue| issue.starts_with("+all")));
+
+        let neutral = check_spf(&records(&["v=spf1 ip4:192.0.2.1 ?all"]));
+        assert!(neutral.issues.iter().any(|issue| issue.starts_with("?all")));
+
+        let missing = check_spf(&records(&["v=spf1 ip4:192.0.2.1"]));
+        assert!(missing.issues.iter().any(|issue| issue.starts_with("No all mechanism")));
+
+        let strict = check_spf(&records(&["google-site-verification=abc", "v=spf1 ip4:192.0.2.1 -all"]));
+        assert!(strict.issues.is_empty(), "{:?}", strict.issues);
+        assert_eq!(strict.dns_lookups, 0);
+    }
+
+    #[test]
+    fn check_spf_multiple_and_missing_records() {
+        let multiple = check_spf(&records(&["v=spf1 -all", "v=spf1 mx -all"]));
+        assert!(multiple.issues.iter().any(|issue| issue.starts_with("2 SPF records")));
+
+        let none = check_spf(&records(&["v=spf10 -all"]));
+        assert_eq!(none.record, None);
+        assert!(none.issues[0].starts_with("No SPF record"));
+    }
+
+    #[test]
+    fn follow_includes_counts_repeated_includes() {
+        let zone = [
+            ("_spf.a.example", "v=spf1 include:_spf.google.com mx -all"),
+            ("_spf.b.example", "v=spf1 include:_spf.google.com a -all"),
+            ("_spf.google.com", "v=spf1 include:_netblocks.google.com include:_netblocks2.google.com include:_netblocks3.google.com ~all"),
+            ("_netblocks.google.com", "v=spf1 ip4:192.0.2.0/24 ~all"),
+            ("_netblocks2.google.com", "v=spf1 ip6:2001:db8::/32 ~all"),
+            ("_netblocks3.google.com", "v=spf1 ip4:198.51.100.0/24 ~all"),
+        ];
+        let mut queried = Vec::new();
+        // The top-level record: include:_spf.a.example include:_spf.b.example.
+        let followed = follow_includes(
+            records(&["_spf.a.example", "_spf.b.example"]),
+            2,
+            fake_lookup(&zone, &mut queried),
+        );
+
+        // 2 top-level includes, mx and include in a, a and include in b,
+        // then 3 netblock includes for each of the two google includes.
+        assert_eq!(followed.lookups, 12);
+        assert!(followed.lookups > SPF_LOOKUP_LIMIT);
+        assert_eq!(followed.includes.len(), 6, "each domain is listed once");
+        assert_eq!(queried.len(), 6, "each domain is queried once");
+        assert!(followed.missing.is_empty());
+    }
+
+    #[test]
+    fn follow_includes_reports_missing_records_once() {
+        let mut queried = Vec::new();
+        let followed = follow_includes(
+            records(&["gone.example", "gone.example"]),
+            2,
+            fake_lookup(&[], &mut queried),
+        );
+        assert_eq!(followed.lookups, 2);
+        assert_eq!(followed.missing, vec!["gone.example"]);
+        assert_eq!(queried, vec!["gone.example"]);
+    }
+
+    #[test]
+    fn follow_includes_reports_failed_lookups_as_failed() {
+        let mut queried = Vec::new();
+        let followed = follow_includes(
+            records(&["servfail.example", "servfail.example"]),
+            2,
+            fake_lookup(&[], &mut queried),
+        );
+        assert!(followed.missing.is_empty());
+        assert_eq!(
+            followed.failed,
+            vec![("servfail.example".to_string(), "Server Failure (2)".to_string())]
+        );
+        assert_eq!(queried.len(), 1);
+    }
+
+    #[test]
+    fn failed_lookups_are_not_reported_as_missing_records() {
+        let spf = SpfCheck::lookup_failed("Query Refused (5)".to_string());
+        assert!(spf.issues[0].starts_with("SPF lookup failed"));
+        let dmarc = DmarcCheck::lookup_failed("request timed out".to_string());
+        assert!(dmarc.issues[0].starts_with("DMARC lookup failed"));
+
+        let selectors = records(&["default", "google"]);
+        let dkim = check_dkim(
+            &selectors,
+            &[],
+            vec![Err("request timed out".to_string()), Ok(Vec::new())],
+        );
+        assert_eq!(dkim.selectors.len(), 1);
+        assert_eq!(dkim.selectors[0].lookup_error.as_deref(), Some("request timed out"));
+        assert_eq!(dkim.issues.len(), 1);
+        assert!(dkim.issues[0].starts_with("Lookup failed for 1 of 2 selectors"));
+    }
+
+    #[test]
+    fn follow_includes_stops_on_loops() {
+        let zone = [("loop.example", "v=spf1 include:loop.example -all")];
+        let mut queried = Vec::new();
+        let followed = follow_includes(records(&["loop.example"]), 1, fake_lookup(&zone, &mut queried));
+        assert!(followed.lookups > SPF_LOOKUP_LIMIT);
+        assert_eq!(queried.len(), 1);
+    }
+
+    #[test]
+    fn check_dmarc_parses_tags() {
+        let check = check_dmarc(&records(&[
+            "v=DMARC1; p=Quarantine; sp=reject; pct=50; rua=mailto:dmarc@example.com, mailto:reports@example.net; ruf=mailto:forensic@example.com; adkim=s",
+        ]));
+        assert_eq!(check.policy.as_deref(), Some("quarantine"));
+        assert_eq!(check.subdomain_policy.as_deref(), Some("reject"));
+        assert_eq!(check.pct, 50);
+        assert_eq!(check.rua, vec!["mailto:dmarc@example.com", "mailto:reports@example.net"]);
+        assert_eq!(check.ruf, vec!["mailto:forensic@example.com"]);
+    }
+
+    #[test]
+    fn check_dmarc_defaults_and_issues() {
+        let monitoring = check_dmarc(&records(&["v=DMARC1; p=none"]));
+        assert_eq!(monitoring.pct, 100);
+        assert_eq!(monitoring.subdomain_policy, None);
+        assert!(monitoring.rua.is_empty() && monitoring.ruf.is_empty());
+        assert!(monitoring.issues.iter().any(|issue| issue.starts_with("p=none")));
+
+        let no_policy = check_dmarc(&records(&["v=DMARC1; rua=mailto:d@example.com"]));
+        assert_eq!(no_policy.policy, None);
+        assert!(no_policy.issues.iter().any(|issue| issue.contains("no p= tag")));
+
+        let missing = check_dmarc(&records(&["some other record"]));
+        assert_eq!(missing.record, None);
+        assert!(missing.issues[0].starts_with("No DMARC record"));
+    }
+
+    #[test]
+    fn check_dmarc_reports_an_invalid_pct() {
+        for pct in ["abc", "150", "300", "-5", "50.5", ""] {
+            let check = check_dmarc(&records(&[&format!("v=DMARC1; p=reject; pct={}; rua=mailto:d@example.com", pct)]));
+            assert_eq!(check.pct, 100, "{}", pct);
+            assert_eq!(
+                check.issues,
+                vec![format!("Invalid pct={}: it must be a whole number from 0 to 100, so receivers ignore it", pct)]
+            );
+        }
+
+        let check = check_dmarc(&records(&["v=DMARC1; p=reject; pct=0; rua=mailto:d@example.com"]));
+        assert_eq!(check.pct, 0);
+        assert!(check.issues[0].starts_with("pct=0:"));
+    }
+}
@@ -1,11 +1,26 @@
 mod dns;
+pub mod engine;
 mod ip;
+mod mail;
 mod ssl;
 mod response;
+mod redirects;
+mod headers;
+mod cache;
+mod compression;
+mod tls_scan;
 mod page_load;
+mod propagation;
 
-pub use dns::dns;
-pub use ip::ip;
-pub use ssl::ssl;
-pub use response::response;
-pub use page_load::page_load;
\ No newline at end of file
+pub use dns::lookup_dns;
+pub use ip::lookup_ip;
+pub use mail::check_mail;
+pub use ssl::{SslReport, inspect_certificate};
+pub use response::{measure_response, sample_response};
+pub use redirects::trace_redirects;
+pub use headers::audit_headers;
+pub use cache::detect_cache;
+pub use compression::analyse_compression;
+pub use tls_scan::scan_tls;
+pub use page_load::analyse_page_load;
+pub use propagation::check_propagation;
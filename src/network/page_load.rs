@@ -1,13 +1,14 @@
 use color_eyre::eyre::{eyre, Result};
 use crossterm::{cursor, execute, terminal};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::io::{self, Write};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
 use crate::config::load_config;
-use crate::utils::extract_hostname;
+use crate::batch::Summary;
 
 // --- Serde structs for PageSpeed Insights API v5 ---
 
@@ -58,7 +59,9 @@ struct AuditEntry {
 
 // --- Rating system ---
 
-enum Rating {
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Rating {
     Good,
     NeedsImprovement,
     Poor,
@@ -94,6 +97,7 @@ impl Rating {
 
 // --- Spinner ---
 
+/// Drawn on stderr so it never mixes with the report on stdout.
 struct Spinner {
     stop: Arc<AtomicBool>,
     handle: Option<std::thread::JoinHandle<()>>,
@@ -109,14 +113,14 @@ impl Spinner {
             let frames = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
             let mut i = 0;
             while !stop_clone.load(Ordering::Relaxed) {
-                print!("\r{} {}", frames[i % frames.len()], msg);
-                let _ = io::stdout().flush();
+                eprint!("\r{} {}", frames[i % frames.len()], msg);
+                let _ = io::stderr().flush();
                 i += 1;
                 std::thread::sleep(Duration::from_millis(80));
             }
             // Clear the spinner line
             let _ = execute!(
-                io::stdout(),
+                io::stderr(),
                 cursor::MoveToColumn(0),
                 terminal::Clear(terminal::ClearType::CurrentLine)
             );
@@ -136,9 +140,36 @@ impl Spinner {
     }
 }
 
+// --- Report ---
+
+#[derive(Serialize)]
+pub struct PageLoadReport {
+    pub hostname: String,
+    pub strategy: String,
+    pub performance_score: u32,
+    pub rating: Rating,
+    pub metrics: Vec<Metric>,
+    pub report_url: String,
+}
+
+#[derive(Serialize)]
+pub struct Metric {
+    pub name: &'static str,
+    pub value: Option<String>,
+    pub score: Option<f64>,
+    pub rating: Rating,
+}
+
 // --- Main function ---
 
-pub fn page_load(site: String, strategy: String, api_key: Option<String>) -> Result<()> {
+/// Runs a PageSpeed Insights analysis. The spinner is only shown for a
+/// single site, as concurrent batch checks would draw over each other.
+pub fn analyse_page_load(
+    hostname: &str,
+    strategy: &str,
+    api_key: Option<String>,
+    show_spinner: bool,
+) -> Result<PageLoadReport> {
     let strategy = strategy.to_lowercase();
     if strategy != "mobile" && strategy != "desktop" {
         return Err(eyre!(
@@ -152,10 +183,9 @@ pub fn page_load(site: String, strategy: String, api_key: Option<String>) -> Res
         load_config().ok().and_then(|c| c.pagespeed_api_key)
     });
 
-    let hostname = extract_hostname(&site)?;
     let url = format!("https://{}", hostname);
 
-    let spinner = Spinner::start(&format!("Analysing {} ({})...", hostname, strategy));
+    let spinner = show_spinner.then(|| Spinner::start(&format!("Analysing {} ({})...", hostname, strategy)));
 
     let client = reqwest::blocking::Client::builder()
         .timeout(Duration::from_secs(120))
@@ -173,7 +203,9 @@ pub fn page_load(site: String, strategy: String, api_key: Option<String>) -> Res
         .query(&params)
         .send();
 
-    spinner.stop();
+    if let Some(spinner) = spinner {
+        spinner.stop();
+    }
 
     let response = response.map_err(|e| eyre!("API request failed: {}", e))?;
 
@@ -213,57 +245,80 @@ pub fn page_load(site: String, strategy: String, api_key: Option<String>) -> Res
         .performance
         .score
         .unwrap_or(0.0);
-    let score_int = (perf_score * 100.0).round() as u32;
-    let rating = Rating::from_score(perf_score);
-
-    println!(
-        "PageSpeed Insights for: {} ({})\n",
-        hostname, strategy
-    );
-    println!(
-        "Performance Score: {} / 100  {}  {}\n",
-        score_int,
-        rating.emoji(),
-        rating.label()
-    );
-    println!("Core Web Vitals:\n");
-
-    // Print each metric
-    let audits = &data.lighthouse_result.audits;
-    let metrics: [(&str, &AuditEntry); 6] = [
-        ("First Contentful Paint (FCP)", &audits.first_contentful_paint),
-        ("Largest Contentful Paint (LCP)", &audits.largest_contentful_paint),
-        ("Total Blocking Time (TBT)", &audits.total_blocking_time),
-        ("Cumulative Layout Shift (CLS)", &audits.cumulative_layout_shift),
-        ("Speed Index", &audits.speed_index),
-        ("Time to Interactive (TTI)", &audits.time_to_interactive),
+
+    let audits = data.lighthouse_result.audits;
+    let metrics: [(&'static str, AuditEntry); 6] = [
+        ("First Contentful Paint (FCP)", audits.first_contentful_paint),
+        ("Largest Contentful Paint (LCP)", audits.largest_contentful_paint),
+        ("Total Blocking Time (TBT)", audits.total_blocking_time),
+        ("Cumulative Layout Shift (CLS)", audits.cumulative_layout_shift),
+        ("Speed Index", audits.speed_index),
+        ("Time to Interactive (TTI)", audits.time_to_interactive),
     ];
 
-    for (name, audit) in &metrics {
-        let value = audit
-            .display_value
-            .as_deref()
-            .unwrap_or("N/A");
-        let metric_rating = audit
-            .score
-            .map(Rating::from_score)
-            .unwrap_or(Rating::Poor);
-
-        println!(
-            "  {:<42} {:>10}  {}  {}",
+    let metrics = metrics
+        .into_iter()
+        .map(|(name, audit)| Metric {
             name,
-            value,
-            metric_rating.emoji(),
-            metric_rating.label()
-        );
-    }
+            rating: audit.score.map(Rating::from_score).unwrap_or(Rating::Poor),
+            value: audit.display_value,
+            score: audit.score,
+        })
+        .collect();
+
+    Ok(PageLoadReport {
+        hostname: hostname.to_string(),
+        report_url: format!(
+            "https://pagespeed.web.dev/analysis?url={}&strategy={}",
+            url, strategy
+        ),
+        strategy,
+        performance_score: (perf_score * 100.0).round() as u32,
+        rating: Rating::from_score(perf_score),
+        metrics,
+    })
+}
 
-    println!();
-    println!(
-        "Full report: https://pagespeed.web.dev/analysis?url={}&strategy={}",
-        url, strategy
-    );
-    println!();
+impl fmt::Display for PageLoadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "PageSpeed Insights for: {} ({})\n",
+            self.hostname, self.strategy
+        )?;
+        writeln!(
+            f,
+            "Performance Score: {} / 100  {}  {}\n",
+            self.performance_score,
+            self.rating.emoji(),
+            self.rating.label()
+        )?;
+        writeln!(f, "Core Web Vitals:\n")?;
+
+        for metric in &self.metrics {
+            writeln!(
+                f,
+                "  {:<42} {:>10}  {}  {}",
+                metric.name,
+                metric.value.as_deref().unwrap_or("N/A"),
+                metric.rating.emoji(),
+                metric.rating.label()
+            )?;
+        }
 
-    Ok(())
+        writeln!(f)?;
+        writeln!(f, "Full report: {}", self.report_url)?;
+        writeln!(f)
+    }
+}
+
+impl Summary for PageLoadReport {
+    fn summary(&self) -> String {
+        format!(
+            "score {} / 100 ({}, {})",
+            self.performance_score,
+            self.rating.label(),
+            self.strategy
+        )
+    }
 }
@@ -0,0 +1,366 @@
+use color_eyre::eyre::{Result, eyre};
+use hickory_resolver::proto::rr::RecordType;
+use serde::Serialize;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+use crate::batch::Summary;
+use crate::config::load_config;
+use crate::network::dns::{Answer, lookup_answer, zone_apex};
+use crate::network::engine;
+use crate::output::Table;
+
+/// Public resolvers every check includes, unless `propagation_resolvers` in
+/// the config lists others.
+const PUBLIC_RESOLVERS: [(&str, &str); 4] = [
+    ("Google", "8.8.8.8"),
+    ("Cloudflare", "1.1.1.1"),
+    ("Quad9", "9.9.9.9"),
+    ("OpenDNS", "208.67.222.222"),
+];
+
+#[derive(Serialize)]
+pub struct PropagationReport {
+    pub hostname: String,
+    pub record_type: String,
+    /// The value every resolver should return once the change has spread.
+    pub expected: Vec<String>,
+    pub expected_from: Option<String>,
+    pub resolvers: Vec<ResolverAnswer>,
+    /// Authoritative name servers that were not queried because they have
+    /// no IPv4 address.
+    pub skipped_name_servers: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct ResolverAnswer {
+    pub name: String,
+    pub address: String,
+    pub kind: ResolverKind,
+    pub status: AnswerStatus,
+    pub values: Vec<String>,
+    pub ttl: Option<u32>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResolverKind {
+    Public,
+    Authoritative,
+    Custom,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AnswerStatus {
+    /// Returns the expected value.
+    Current,
+    /// Returns a different value, usually the old one from its cache.
+    Stale,
+    /// Returns no records of the type.
+    Empty,
+    /// Did not answer.
+    Failed,
+    /// Answered, but there is no expected value to compare with.
+    Answered,
+}
+
+/// Queries the same record on public resolvers, the domain's authoritative
+/// name servers and any `custom` servers, and compares each answer with the
+/// `expected` value, or with the authoritative answer when none is given.
+pub fn check_propagation(
+    hostname: &str,
+    record_type: &str,
+    custom: &[SocketAddr],
+    expected: &[String],
+) -> Result<PropagationReport> {
+    let record_type = RecordType::from_str(&record_type.to_ascii_uppercase())
+        .map_err(|_| eyre!("Unknown record type '{}'", record_type))?;
+
+    let config = load_config()?;
+    let mut servers: Vec<(String, SocketAddr, ResolverKind)> = if config.propagation_resolvers.is_empty() {
+        PUBLIC_RESOLVERS
+            .iter()
+            .filter_map(|(name, ip)| {
+                let ip: IpAddr = ip.parse().ok()?;
+                Some((name.to_string(), SocketAddr::new(ip, 53), ResolverKind::Public))
+            })
+            .collect()
+    } else {
+        config
+            .propagation_resolvers
+            .iter()
+            .map(|resolver| (resolver.name.clone(), SocketAddr::new(resolver.address, 53), ResolverKind::Public))
+            .collect()
+    };
+    let (authoritative, skipped_name_servers) = authoritative_servers(hostname);
+    servers.extend(authoritative);
+    servers.extend(
+        custom
+            .iter()
+            .map(|address| (address.to_string(), *address, ResolverKind::Custom)),
+    );
+
+    let answers = engine::block_on_all(
+        servers
+            .iter()
+            .map(|(_, address, _)| lookup_answer(engine::resolver_for(&[*address]), hostname.to_string(), record_type))
+            .collect(),
+    );
+
+    let (expected, expected_from) = if !expected.is_empty() {
+        let mut expected = expected.to_vec();
+        expected.sort();
+        (expected, Some("--expect".to_string()))
+    } else {
+        servers
+            .iter()
+            .zip(&answers)
+            .find_map(|((name, _, kind), answer)| match (kind, answer) {
+                (ResolverKind::Authoritative, Ok(Answer { values, .. })) if !values.is_empty() => {
+                    Some((values.clone(), Some(format!("authoritative server {}", name))))
+                }
+                _ => None,
+            })
+            .unwrap_or_default()
+    };
+
+    let resolvers = servers
+        .into_iter()
+        .zip(answers)
+        .map(|((name, address, kind), answer)| {
+            let (values, ttl, error) = match answer {
+                Ok(answer) => (answer.values, answer.ttl, None),
+                Err(e) => (Vec::new(), None, Some(e)),
+            };
+            ResolverAnswer {
+                name,
+                address: address.to_string(),
+                kind,
+                status: status(&values, error.as_deref(), &expected),
+                values,
+                ttl,
+                error,
+            }
+        })
+        .collect();
+
+    Ok(PropagationReport {
+        hostname: hostname.to_string(),
+        record_type: record_type.to_string(),
+        expected,
+        expected_from,
+        resolvers,
+        skipped_name_servers,
+    })
+}
+
+/// The name servers of the zone the site is in, each at its first IPv4
+/// address, and the names of those without one.
+fn authoritative_servers(hostname: &str) -> (Vec<(String, SocketAddr, ResolverKind)>, Vec<String>) {
+    let resolver = engine::resolver();
+    let domain = zone_apex(&resolver, hostname);
+
+    let ns = engine::block_on_all(vec![lookup_answer(resolver.clone(), domain.clone(), RecordType::NS)])
+        .pop()
+        .and_then(|answer| answer.ok())
+        .map(|answer| answer.values)
+        .unwrap_or_default();
+    let addresses = engine::block_on_all(
+        ns.iter()
+            .map(|name| lookup_answer(resolver.clone(), name.clone(), RecordType::A))
+            .collect(),
+    );
+
+    let mut servers = Vec::new();
+    let mut skipped = Vec::new();
+    for (name, answer) in ns.into_iter().zip(addresses) {
+        let name = name.trim_end_matches('.').to_string();
+        let ip = answer.ok().and_then(|answer| answer.values.first()?.parse::<IpAddr>().ok());
+        match ip {
+            Some(ip) => servers.push((name, SocketAddr::new(ip, 53), ResolverKind::Authoritative)),
+            None => skipped.push(name),
+        }
+    }
+    (servers, skipped)
+}
+
+/// Classifies one resolver's answer against the `expected` values, which
+/// are empty when there is nothing to compare with.
+fn status(values: &[String], error: Option<&str>, expected: &[String]) -> AnswerStatus {
+    if error.is_some() {
+        AnswerStatus::Failed
+    } else if values.is_empty() {
+        AnswerStatus::Empty
+    } else if expected.is_empty() {
+        AnswerStatus::Answered
+    } else if same_values(values, expected) {
+        AnswerStatus::Current
+    } else {
+        AnswerStatus::Stale
+    }
+}
+
+/// Compares answers ignoring case and trailing dots, so `--expect` values
+/// can be typed the way people write them.
+fn same_values(values: &[String], expected: &[String]) -> bool {
+    let normalise = |values: &[String]| {
+        let mut values: Vec<String> = values
+            .iter()
+            .map(|value| value.trim().trim_end_matches('.').to_ascii_lowercase())
+            .collect();
+        values.sort();
+        values
+    };
+    normalise(values) == normalise(expected)
+}
+
+impl ResolverAnswer {
+    fn label(&self) -> String {
+        match self.kind {
+            ResolverKind::Public => self.name.clone(),
+            ResolverKind::Authoritative => format!("{} (authoritative)", self.name),
+            ResolverKind::Custom => "custom".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for AnswerStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            AnswerStatus::Current => "current",
+            AnswerStatus::Stale => "stale",
+            AnswerStatus::Empty => "no records",
+            AnswerStatus::Failed => "failed",
+            AnswerStatus::Answered => "answered",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+impl fmt::Display for PropagationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "DNS propagation for: {} ({})", self.hostname, self.record_type)?;
+        match &self.expected_from {
+            Some(from) => writeln!(f, "Expected: {} (from {})\n", self.expected.join(", "), from)?,
+            None => writeln!(f, "Expected: unknown, no authoritative answer; pass --expect to compare\n")?,
+        }
+
+        let mut table = Table::new(&["Resolver", "Address", "Status", "TTL", "Answer"]);
+        for resolver in &self.resolvers {
+            table.add_row(vec![
+                resolver.label(),
+                resolver.address.clone(),
+                resolver.status.to_string(),
+                resolver.ttl.map(|ttl| format!("{}s", ttl)).unwrap_or_else(|| "-".to_string()),
+                match &resolver.error {
+                    Some(error) => error.clone(),
+                    None => resolver.values.join(", "),
+                },
+            ]);
+        }
+        write!(f, "{}", table)?;
+        if !self.skipped_name_servers.is_empty() {
+            writeln!(
+                f,
+                "\nNot queried (no IPv4 address): {}",
+                self.skipped_name_servers.join(", ")
+            )?;
+        }
+
+        if self.expected.is_empty() {
+            return Ok(());
+        }
+        let current = self.resolvers.iter().filter(|r| r.status == AnswerStatus::Current).count();
+        writeln!(f, "\n{} of {} resolvers return the expected value", current, self.resolvers.len())?;
+
+        let stale: Vec<String> = self
+            .resolvers
+            .iter()
+            .filter(|r| r.status == AnswerStatus::Stale)
+            .map(|r| format!("{} ({})", r.label(), r.values.join(", ")))
+            .collect();
+        if !stale.is_empty() {
+            writeln!(
+                f,
+                "⚠️  Still returning another value: {}. Cached answers expire within the TTL shown.",
+                stale.join("; ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Summary for PropagationReport {
+    fn summary(&self) -> String {
+        let current = self.resolvers.iter().filter(|r| r.status == AnswerStatus::Current).count();
+        let stale = self.resolvers.iter().filter(|r| r.status == AnswerStatus::Stale).count();
+        if self.expected.is_empty() {
+            return format!("{} resolvers, no expected value", self.resolvers.len());
+        }
+        format!("{}/{} current, {} stale", current, self.resolvers.len(), stale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn failed_lookup_wins() {
+        let values = strings(&["192.0.2.1"]);
+        assert_eq!(status(&values, Some("timed out"), &values), AnswerStatus::Failed);
+        assert_eq!(status(&[], Some("timed out"), &[]), AnswerStatus::Failed);
+    }
+
+    #[test]
+    fn no_records() {
+        assert_eq!(status(&[], None, &strings(&["192.0.2.1"])), AnswerStatus::Empty);
+        assert_eq!(status(&[], None, &[]), AnswerStatus::Empty);
+    }
+
+    #[test]
+    fn nothing_to_compare_with() {
+        assert_eq!(status(&strings(&["192.0.2.1"]), None, &[]), AnswerStatus::Answered);
+    }
+
+    #[test]
+    fn matching_answer_is_current() {
+        let expected = strings(&["192.0.2.1", "192.0.2.2"]);
+        assert_eq!(status(&strings(&["192.0.2.1", "192.0.2.2"]), None, &expected), AnswerStatus::Current);
+        assert_eq!(status(&strings(&["192.0.2.2", "192.0.2.1"]), None, &expected), AnswerStatus::Current);
+    }
+
+    #[test]
+    fn different_answer_is_stale() {
+        let expected = strings(&["192.0.2.1", "192.0.2.2"]);
+        assert_eq!(status(&strings(&["192.0.2.1"]), None, &expected), AnswerStatus::Stale);
+        assert_eq!(status(&strings(&["198.51.100.7"]), None, &expected), AnswerStatus::Stale);
+    }
+
+    #[test]
+    fn comparison_ignores_case_trailing_dots_and_whitespace() {
+        assert!(same_values(&strings(&["Mail.Example.COM."]), &strings(&["mail.example.com"])));
+        assert!(same_values(&strings(&["b.example.com.", "a.example.com."]), &strings(&[" A.example.com", "b.example.com"])));
+        assert!(!same_values(&strings(&["a.example.com."]), &strings(&["a.example.com", "b.example.com"])));
+    }
+
+    #[test]
+    fn name_servers_without_ipv4_are_reported() {
+        let report = PropagationReport {
+            hostname: "example.com".to_string(),
+            record_type: "A".to_string(),
+            expected: Vec::new(),
+            expected_from: None,
+            resolvers: Vec::new(),
+            skipped_name_servers: strings(&["ns1.example.net"]),
+        };
+        assert!(report.to_string().contains("Not queried (no IPv4 address): ns1.example.net"));
+    }
+}
@@ -0,0 +1,477 @@
+use crate::batch::Summary;
+use crate::output;
+use color_eyre::eyre::{Result, eyre};
+use reqwest::blocking::Client;
+use reqwest::header::LOCATION;
+use reqwest::redirect::Policy;
+use serde::Serialize;
+use std::fmt;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use url::Url;
+
+use crate::utils::http_client_with;
+
+/// Give up after this many hops, like browsers do.
+const MAX_HOPS: usize = 20;
+
+/// More redirects than this are flagged as a slow chain.
+const HOP_WARNING: usize = 3;
+
+#[derive(Serialize)]
+pub struct RedirectReport {
+    pub hostname: String,
+    pub start_url: String,
+    pub hops: Vec<Hop>,
+    pub final_url: Option<String>,
+    pub counterpart: Option<CounterpartTrace>,
+    pub issues: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct Hop {
+    pub url: String,
+    pub status: Option<u16>,
+    pub location: Option<String>,
+    #[serde(rename = "duration_ms", serialize_with = "output::serialize_millis")]
+    pub duration: Duration,
+    pub error: Option<String>,
+}
+
+/// Where the www / non-www counterpart of the host ends up, to check both
+/// variants are canonicalised to the same address.
+#[derive(Serialize)]
+pub struct CounterpartTrace {
+    pub start_url: String,
+    pub redirects: usize,
+    pub final_url: Option<String>,
+}
+
+struct Trace {
+    hops: Vec<Hop>,
+    looped: bool,
+    /// Why the last redirect's Location could not be followed.
+    invalid_location: Option<String>,
+}
+
+impl Trace {
+    /// The URL the chain settled on, if it ended in a non-redirect response.
+    fn final_url(&self) -> Option<String> {
+        let last = self.hops.last()?;
+        match last.status {
+            Some(status) if !self.looped && !(300..400).contains(&status) => Some(last.url.clone()),
+            _ => None,
+        }
+    }
+
+    fn redirects(&self) -> usize {
+        self.hops.iter().filter(|hop| hop.status.is_some_and(|s| (300..400).contains(&s))).count()
+    }
+}
+
+/// Follows the redirects for a site one hop at a time, starting from plain
+/// http:// unless `https` is set, and flags loops, protocol downgrades, long
+/// chains and inconsistent www handling.
+pub fn trace_redirects(hostname: &str, https: bool) -> Result<RedirectReport> {
+    let client = http_client_with(Policy::none())?;
+
+    let scheme = if https { "https" } else { "http" };
+    let start_url = format!("{}://{}/", scheme, hostname);
+    let trace = follow(&client, &start_url)?;
+
+    if let Some(Hop { error: Some(error), .. }) = trace.hops.first() {
+        return Err(eyre!("Request to {} failed: {}", start_url, error));
+    }
+
+    let counterpart = counterpart_host(hostname).and_then(|host| {
+        let start_url = format!("{}://{}/", scheme, host);
+        let trace = follow(&client, &start_url).ok()?;
+        // A counterpart that does not resolve or answer is not configured at
+        // all, which is fine.
+        if trace.hops.first()?.error.is_some() {
+            return None;
+        }
+        Some(CounterpartTrace {
+            redirects: trace.redirects(),
+            final_url: trace.final_url(),
+            start_url,
+        })
+    });
+
+    let issues = find_issues(&trace, counterpart.as_ref());
+
+    Ok(RedirectReport {
+        hostname: hostname.to_string(),
+        start_url,
+        final_url: trace.final_url(),
+        hops: trace.hops,
+        counterpart,
+        issues,
+    })
+}
+
+fn follow(client: &Client, start_url: &str) -> Result<Trace> {
+    let mut url = Url::parse(start_url).map_err(|e| eyre!("Invalid URL '{}': {}", start_url, e))?;
+    let mut hops: Vec<Hop> = Vec::new();
+    let mut looped = false;
+    let mut invalid_location = None;
+
+    while hops.len() < MAX_HOPS {
+        let start = Instant::now();
+        let response = match client.get(url.clone()).send() {
+            Ok(response) => response,
+            Err(e) => {
+                hops.push(Hop {
+                    url: url.to_string(),
+                    status: None,
+                    location: None,
+                    duration: start.elapsed(),
+                    error: Some(e.to_string()),
+                });
+                break;
+            }
+        };
+        let duration = start.elapsed();
+
+        let status = response.status();
+        let location = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+
+        hops.push(Hop {
+            url: url.to_string(),
+            status: Some(status.as_u16()),
+            location: location.clone(),
+            duration,
+            error: None,
+        });
+
+        let Some(location) = location.filter(|_| status.is_redirection()) else {
+            break;
+        };
+        // Location may be relative to the current URL. One that cannot be
+        // parsed ends the chain, keeping the hops traced so far.
+        let next = match url.join(&location) {
+            Ok(next) => next,
+            Err(e) => {
+                let error = format!("invalid Location header: {}", e);
+                invalid_location = Some(format!("Invalid Location header '{}' at {}: {}", location, url, e));
+                hops.push(Hop {
+                    url: location,
+                    status: None,
+                    location: None,
+                    duration: Duration::ZERO,
+                    error: Some(error),
+                });
+                break;
+            }
+        };
+        if hops.iter().any(|hop| hop.url == next.as_str()) {
+            looped = true;
+            break;
+        }
+        url = next;
+    }
+
+    Ok(Trace { hops, looped, invalid_location })
+}
+
+fn find_issues(trace: &Trace, counterpart: Option<&CounterpartTrace>) -> Vec<String> {
+    let mut issues = Vec::new();
+    let urls: Vec<Url> = trace.hops.iter().filter_map(|hop| Url::parse(&hop.url).ok()).collect();
+
+    if let Some(invalid_location) = &trace.invalid_location {
+        issues.push(invalid_location.clone());
+    } else if let Some(Hop { url, error: Some(error), .. }) = trace.hops.last() {
+        issues.push(format!("The chain did not reach a final page: {} failed ({})", url, error));
+    } else if trace.looped {
+        issues.push("Redirect loop: the chain returns to a URL it already visited".to_string());
+    } else if trace.hops.len() == MAX_HOPS && trace.final_url().is_none() {
+        issues.push(format!("Gave up after {} hops", MAX_HOPS));
+    }
+
+    let redirects = trace.redirects();
+    if redirects > HOP_WARNING {
+        issues.push(format!(
+            "{} redirects before the final page (more than {}); point links and redirects straight at the final URL",
+            redirects, HOP_WARNING
+        ));
+    }
+
+    let downgrade = urls
+        .iter()
+        .skip_while(|url| url.scheme() != "https")
+        .any(|url| url.scheme() == "http");
+    if downgrade {
+        issues.push("Redirects from https:// back to http:// (protocol flip-flop)".to_string());
+    }
+
+    let www_switches = urls
+        .windows(2)
+        .filter(|pair| has_www(&pair[0]) != has_www(&pair[1]))
+        .count();
+    if www_switches > 1 {
+        issues.push("The chain switches between www and non-www more than once".to_string());
+    }
+
+    if let (Some(counterpart), Some(final_url)) = (counterpart, trace.final_url()) {
+        match &counterpart.final_url {
+            Some(other) if !same_origin(other, &final_url) => issues.push(format!(
+                "Inconsistent canonical host: {} ends at {}, but {} ends at {}",
+                trace.hops[0].url, final_url, counterpart.start_url, other
+            )),
+            _ => {}
+        }
+    }
+
+    let temporary: Vec<&str> = trace
+        .hops
+        .iter()
+        .filter(|hop| matches!(hop.status, Some(302 | 303 | 307)))
+        .map(|hop| hop.url.as_str())
+        .collect();
+    if !temporary.is_empty() {
+        issues.push(format!(
+            "Temporary redirect at {}; use 301 or 308 for permanent moves so search engines follow them",
+            temporary.join(", ")
+        ));
+    }
+
+    issues
+}
+
+fn has_www(url: &Url) -> bool {
+    url.host_str().is_some_and(|host| host.starts_with("www."))
+}
+
+fn same_origin(a: &str, b: &str) -> bool {
+    match (Url::parse(a), Url::parse(b)) {
+        (Ok(a), Ok(b)) => a.origin() == b.origin(),
+        _ => a == b,
+    }
+}
+
+/// The www / non-www counterpart of a hostname. IP addresses and single
+/// label names have none.
+fn counterpart_host(hostname: &str) -> Option<String> {
+    if hostname.parse::<IpAddr>().is_ok() || !hostname.contains('.') {
+        return None;
+    }
+    match hostname.strip_prefix("www.") {
+        Some(bare) => Some(bare.to_string()),
+        None => Some(format!("www.{}", hostname)),
+    }
+}
+
+impl fmt::Display for RedirectReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Redirect chain for: {}\n", self.hostname)?;
+
+        for (index, hop) in self.hops.iter().enumerate() {
+            match (&hop.status, &hop.error) {
+                (Some(status), _) => writeln!(f, "  {}. [{}] {}  ({:.2?})", index + 1, status, hop.url, hop.duration)?,
+                (None, Some(error)) => writeln!(f, "  {}. [error] {}  ({})", index + 1, hop.url, error)?,
+                (None, None) => writeln!(f, "  {}. [?] {}", index + 1, hop.url)?,
+            }
+            if let Some(location) = &hop.location {
+                writeln!(f, "       Location: {}", location)?;
+            }
+        }
+
+        match &self.final_url {
+            Some(final_url) => writeln!(f, "\nFinal URL: {}", final_url)?,
+            None => writeln!(f, "\nFinal URL: (none, the chain did not finish)")?,
+        }
+
+        if let Some(counterpart) = &self.counterpart {
+            writeln!(
+                f,
+                "Counterpart: {} → {} ({} redirects)",
+                counterpart.start_url,
+                counterpart.final_url.as_deref().unwrap_or("(did not finish)"),
+                counterpart.redirects
+            )?;
+        }
+
+        if self.issues.is_empty() {
+            writeln!(f, "\n✓ No redirect problems found")
+        } else {
+            writeln!(f, "\nIssues:")?;
+            for issue in &self.issues {
+                writeln!(f, "  ⚠️  {}", issue)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+impl Summary for RedirectReport {
+    fn summary(&self) -> String {
+        let redirects = self
+            .hops
+            .iter()
+            .filter(|hop| hop.status.is_some_and(|s| (300..400).contains(&s)))
+            .count();
+        format!(
+            "{} redirects → {}, {} issues",
+            redirects,
+            self.final_url.as_deref().unwrap_or("unfinished"),
+            self.issues.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A chain visiting `hops` in order, each `(url, status)`; every hop
+    /// but the last redirects to the next.
+    fn trace(hops: &[(&str, u16)]) -> Trace {
+        Trace {
+            hops: hops
+                .iter()
+                .enumerate()
+                .map(|(index, (url, status))| Hop {
+                    url: url.to_string(),
+                    status: Some(*status),
+                    location: hops.get(index + 1).map(|(next, _)| next.to_string()),
+                    duration: Duration::ZERO,
+                    error: None,
+                })
+                .collect(),
+            looped: false,
+            invalid_location: None,
+        }
+    }
+
+    #[test]
+    fn a_single_permanent_redirect_is_clean() {
+        let trace = trace(&[("http://example.com/", 301), ("https://example.com/", 200)]);
+        assert_eq!(trace.redirects(), 1);
+        assert_eq!(trace.final_url().as_deref(), Some("https://example.com/"));
+        assert!(find_issues(&trace, None).is_empty());
+    }
+
+    #[test]
+    fn flags_loops() {
+        let mut trace = trace(&[("http://example.com/", 301), ("https://example.com/", 301)]);
+        trace.looped = true;
+        assert_eq!(trace.final_url(), None);
+        assert!(find_issues(&trace, None)[0].starts_with("Redirect loop"));
+    }
+
+    #[test]
+    fn flags_long_chains() {
+        let trace = trace(&[
+            ("http://example.com/", 301),
+            ("https://example.com/", 301),
+            ("https://example.com/home", 301),
+            ("https://example.com/home/", 301),
+            ("https://example.com/en/", 200),
+        ]);
+        let issues = find_issues(&trace, None);
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].starts_with("4 redirects before the final page"));
+    }
+
+    #[test]
+    fn flags_protocol_downgrades() {
+        let trace = trace(&[
+            ("https://example.com/", 301),
+            ("http://www.example.com/", 301),
+            ("https://www.example.com/", 200),
+        ]);
+        let issues = find_issues(&trace, None);
+        assert!(issues.iter().any(|issue| issue.contains("protocol flip-flop")), "{:?}", issues);
+    }
+
+    #[test]
+    fn flags_www_flip_flops() {
+        let trace = trace(&[
+            ("https://example.com/", 301),
+            ("https://www.example.com/", 301),
+            ("https://example.com/home", 200),
+        ]);
+        let issues = find_issues(&trace, None);
+        assert_eq!(issues, vec!["The chain switches between www and non-www more than once"]);
+    }
+
+    #[test]
+    fn flags_temporary_redirects() {
+        let trace = trace(&[("http://example.com/", 302), ("https://example.com/", 200)]);
+        let issues = find_issues(&trace, None);
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].starts_with("Temporary redirect at http://example.com/"));
+    }
+
+    #[test]
+    fn an_invalid_location_keeps_the_hops_traced_so_far() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request);
+            stream
+                .write_all(b"HTTP/1.1 301 Moved Permanently\r\nLocation: http://[::1\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+        });
+
+        let client = http_client_with(Policy::none()).unwrap();
+        let start_url = format!("http://{}/", address);
+        let trace = follow(&client, &start_url).unwrap();
+        server.join().unwrap();
+
+        assert_eq!(trace.hops.len(), 2);
+        assert_eq!(trace.hops[0].status, Some(301));
+        assert_eq!(trace.hops[1].url, "http://[::1");
+        assert_eq!(trace.hops[1].status, None);
+        assert!(trace.hops[1].error.as_deref().is_some_and(|e| e.starts_with("invalid Location header")));
+        assert_eq!(trace.final_url(), None);
+
+        let issues = find_issues(&trace, None);
+        assert!(issues[0].starts_with(&format!("Invalid Location header 'http://[::1' at {}", start_url)), "{:?}", issues);
+    }
+
+    #[test]
+    fn flags_a_failed_hop() {
+        let mut trace = trace(&[("http://example.com/", 301), ("https://example.com/", 200)]);
+        let last = trace.hops.last_mut().unwrap();
+        last.status = None;
+        last.error = Some("connection timed out".to_string());
+
+        assert_eq!(trace.final_url(), None);
+        assert_eq!(
+            find_issues(&trace, None),
+            vec!["The chain did not reach a final page: https://example.com/ failed (connection timed out)"]
+        );
+    }
+
+    #[test]
+    fn compares_the_counterpart_by_origin() {
+        let trace = trace(&[("http://example.com/", 301), ("https://example.com/", 200)]);
+        let counterpart = |final_url: &str| CounterpartTrace {
+            start_url: "http://www.example.com/".to_string(),
+            redirects: 1,
+            final_url: Some(final_url.to_string()),
+        };
+
+        assert!(find_issues(&trace, Some(&counterpart("https://example.com/home"))).is_empty());
+        let issues = find_issues(&trace, Some(&counterpart("https://www.example.com/")));
+        assert!(issues[0].starts_with("Inconsistent canonical host"), "{:?}", issues);
+    }
+
+    #[test]
+    fn counterpart_hosts() {
+        assert_eq!(counterpart_host("example.com").as_deref(), Some("www.example.com"));
+        assert_eq!(counterpart_host("www.example.com").as_deref(), Some("example.com"));
+        assert_eq!(counterpart_host("192.0.2.1"), None);
+        assert_eq!(counterpart_host("localhost"), None);
+    }
+}
@@ -1,31 +1,789 @@
-use crate::utils::extract_hostname;
-use color_eyre::eyre::Result;
-use std::time::Instant;
+use crate::batch::Summary;
+use crate::output::{self, Table};
+use color_eyre::eyre::{Result, eyre};
+use crate::network::ssl::load_trust_store;
+use openssl::ssl::{SslConnector, SslConnectorBuilder, SslMethod, SslOptions};
+use serde::Serialize;
+use std::fmt;
+use std::io::{Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+use url::{Host, Url};
 
-pub fn response(site: String) -> Result<()> {
-    let hostname = extract_hostname(&site)?;
+const TIMEOUT: Duration = Duration::from_secs(30);
+/// How long to wait for each of the host's addresses before trying the next.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_REDIRECTS: usize = 10;
+/// ALPN offers in wire format: what a browser offers, and HTTP/1.1 alone.
+const ALPN_BROWSER: &[u8] = b"\x02h2\x08http/1.1";
+const ALPN_HTTP1: &[u8] = b"\x08http/1.1";
+/// OpenSSL 3's `SSL_OP_IGNORE_UNEXPECTED_EOF`, which the openssl crate does
+/// not name.
+const IGNORE_UNEXPECTED_EOF: SslOptions = SslOptions::from_bits_retain(1 << 7);
 
-    let start_time_headers = Instant::now();
+#[derive(Serialize)]
+pub struct ResponseReport {
+    pub hostname: String,
+    /// The URL of the final request, after redirects.
+    pub url: String,
+    pub status: u16,
+    /// The protocol the server picks when offered HTTP/2 and HTTP/1.1, as a
+    /// browser would offer them. `None` for plain HTTP or without ALPN.
+    pub negotiated_protocol: Option<String>,
+    /// The version of the timed request, which is always sent as HTTP/1.1.
+    pub http_version: String,
+    pub remote_ip: IpAddr,
+    /// Set when the final response is a redirect that was not followed.
+    pub location: Option<String>,
+    pub size_bytes: usize,
+    /// Redirects followed before the final request, in order.
+    pub redirects: Vec<RedirectHop>,
+    /// Timing of the final request.
+    pub timing: PhaseTiming,
+}
+
+#[derive(Serialize)]
+pub struct RedirectHop {
+    pub url: String,
+    pub status: u16,
+    pub location: String,
+    #[serde(rename = "total_ms", serialize_with = "output::serialize_millis")]
+    pub total: Duration,
+}
+
+/// How long each phase of the request took, in the order they happen.
+#[derive(Serialize)]
+pub struct PhaseTiming {
+    #[serde(rename = "dns_ms", serialize_with = "output::serialize_millis")]
+    pub dns: Duration,
+    #[serde(rename = "connect_ms", serialize_with = "output::serialize_millis")]
+    pub connect: Duration,
+    #[serde(rename = "tls_ms", serialize_with = "output::serialize_millis")]
+    pub tls: Duration,
+    /// From sending the request until the first byte of the response, which
+    /// is mostly the time the server (PHP, database) spends generating it.
+    #[serde(rename = "ttfb_ms", serialize_with = "output::serialize_millis")]
+    pub ttfb: Duration,
+    #[serde(rename = "transfer_ms", serialize_with = "output::serialize_millis")]
+    pub transfer: Duration,
+    #[serde(rename = "total_ms", serialize_with = "output::serialize_millis")]
+    pub total: Duration,
+}
+
+#[derive(Serialize)]
+pub struct SampledResponseReport {
+    pub hostname: String,
+    pub samples: u32,
+    #[serde(rename = "interval_ms", serialize_with = "output::serialize_millis")]
+    pub interval: Duration,
+    pub failures: u32,
+    pub failure_rate: f64,
+    /// The first request, which usually misses server-side caches.
+    pub cold: Option<ResponseReport>,
+    pub warm_total: Option<LatencyStats>,
+    pub warm_ttfb: Option<LatencyStats>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct LatencyStats {
+    pub count: usize,
+    #[serde(rename = "min_ms", serialize_with = "output::serialize_millis")]
+    pub min: Duration,
+    #[serde(rename = "median_ms", serialize_with = "output::serialize_millis")]
+    pub median: Duration,
+    #[serde(rename = "p95_ms", serialize_with = "output::serialize_millis")]
+    pub p95: Duration,
+    #[serde(rename = "max_ms", serialize_with = "output::serialize_millis")]
+    pub max: Duration,
+    #[serde(rename = "stddev_ms", serialize_with = "output::serialize_millis")]
+    pub stddev: Duration,
+}
+
+/// Requests the site's front page over hand-made HTTP/1.1 connections, so
+/// every phase (DNS, TCP, TLS, server wait, transfer) can be timed on its
+/// own. Redirects are followed and each hop is timed; the phase breakdown
+/// is for the final request.
+pub fn measure_response(hostname: &str) -> Result<ResponseReport> {
+    let mut url = Url::parse(&format!("https://{}", hostname))
+        .map_err(|e| eyre!("Invalid site '{}': {}", hostname, e))?;
+    let mut redirects = Vec::new();
+
+    loop {
+        let exchange = send_request(&url)?;
+        let next = match (exchange.status, &exchange.location) {
+            (301 | 302 | 303 | 307 | 308, Some(location)) => url
+                .join(location)
+                .ok()
+                .filter(|next| matches!(next.scheme(), "http" | "https")),
+            _ => None,
+        };
+
+        let Some(next) = next else {
+            return Ok(ResponseReport {
+                hostname: hostname.to_string(),
+                url: url.to_string(),
+                status: exchange.status,
+                negotiated_protocol: exchange.negotiated_protocol,
+                http_version: exchange.http_version,
+                remote_ip: exchange.remote_ip,
+                location: exchange.location,
+                size_bytes: exchange.size_bytes,
+                redirects,
+                timing: exchange.timing,
+            });
+        };
+        if redirects.len() == MAX_REDIRECTS {
+            return Err(eyre!("Too many redirects (more than {}) from {}", MAX_REDIRECTS, hostname));
+        }
+        redirects.push(RedirectHop {
+            url: url.to_string(),
+            status: exchange.status,
+            location: next.to_string(),
+            total: exchange.timing.total,
+        });
+        url = next;
+    }
+}
+
+/// One timed request and its response.
+struct Exchange {
+    status: u16,
+    negotiated_protocol: Option<String>,
+    http_version: String,
+    remote_ip: IpAddr,
+    location: Option<String>,
+    size_bytes: usize,
+    timing: PhaseTiming,
+}
+
+/// A connected, possibly encrypted, stream.
+trait Connection: Read + Write {}
+
+impl<T: Read + Write> Connection for T {}
+
+/// An open connection and how long it took to set up.
+struct Opened {
+    stream: Box<dyn Connection>,
+    address: SocketAddr,
+    connect: Duration,
+    tls: Duration,
+    alpn: Option<String>,
+}
+
+/// The parts of a raw HTTP/1.1 response the report uses.
+struct ParsedResponse {
+    http_version: String,
+    status: u16,
+    location: Option<String>,
+    /// The size of the body as sent, without chunked encoding.
+    size_bytes: usize,
+}
+
+/// The host to connect to and verify the certificate for, which is a bare
+/// address for an IP literal, and the `Host` header value, which keeps
+/// IPv6 brackets and adds a non-default port.
+fn host_parts(url: &Url) -> Option<(String, String)> {
+    let host = match url.host()? {
+        Host::Ipv6(ip) => ip.to_string(),
+        host => host.to_string(),
+    };
+    let header = match url.port() {
+        Some(port) => format!("{}:{}", url.host_str()?, port),
+        None => url.host_str()?.to_string(),
+    };
+    Some((host, header))
+}
+
+fn send_request(url: &Url) -> Result<Exchange> {
+    let (host, host_header) = host_parts(url).ok_or_else(|| eyre!("No host in '{}'", url))?;
+    let port = url.port_or_known_default().unwrap_or(443);
+    let https = url.scheme() == "https";
+    let path = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+
+    let start = Instant::now();
+    let addresses: Vec<SocketAddr> = (host.as_str(), port)
+        .to_socket_addrs()
+        .map_err(|e| eyre!("Failed to resolve {}: {}", host, e))?
+        .collect();
+    if addresses.is_empty() {
+        return Err(eyre!("No address found for {}", host));
+    }
+    let dns = start.elapsed();
+
+    let mut opened = open(&host, &addresses, https, ALPN_BROWSER)?;
+    let negotiated_protocol = opened.alpn.take();
+    if negotiated_protocol.as_deref() == Some("h2") {
+        // An HTTP/2 connection cannot carry the HTTP/1.1 request below, so
+        // connect again offering HTTP/1.1 only and time that connection.
+        opened = open(&host, &[opened.address], https, ALPN_HTTP1)?;
+    }
+    let mut stream = opened.stream;
+
+    let request_start = Instant::now();
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: wpust/{}\r\nAccept: */*\r\nConnection: close\r\n\r\n",
+        path,
+        host_header,
+        env!("CARGO_PKG_VERSION")
+    );
+    stream.write_all(request.as_bytes())
+        .map_err(|e| eyre!("Failed to send request: {}", e))?;
+    let sent = request_start.elapsed();
+
+    let (response, first_byte) =
+        read_to_close(&mut stream, request_start).map_err(|e| eyre!("Failed to read response: {}", e))?;
+    let finished = request_start.elapsed();
+    let first_byte = first_byte.ok_or_else(|| eyre!("{} closed the connection without a response", host))?;
+
+    let parsed = parse_response(&response).map_err(|e| eyre!("{} from {}", e, host))?;
+
+    Ok(Exchange {
+        status: parsed.status,
+        negotiated_protocol,
+        http_version: parsed.http_version,
+        remote_ip: opened.address.ip(),
+        location: parsed.location,
+        size_bytes: parsed.size_bytes,
+        timing: PhaseTiming {
+            dns,
+            connect: opened.connect,
+            tls: opened.tls,
+            ttfb: first_byte - sent,
+            transfer: finished - first_byte,
+            total: dns + opened.connect + opened.tls + finished,
+        },
+    })
+}
+
+/// Reads until the server closes the connection, returning what arrived
+/// and when its first byte did, relative to `start`.
+fn read_to_close(stream: &mut impl Read, start: Instant) -> std::io::Result<(Vec<u8>, Option<Duration>)> {
+    let mut response = Vec::new();
+    let mut buffer = [0u8; 16 * 1024];
+    let mut first_byte = None;
+    loop {
+        match stream.read(&mut buffer)? {
+            0 => return Ok((response, first_byte)),
+            read => {
+                first_byte.get_or_insert_with(|| start.elapsed());
+                response.extend_from_slice(&buffer[..read]);
+            }
+        }
+    }
+}
+
+fn parse_response(response: &[u8]) -> std::result::Result<ParsedResponse, String> {
+    let head_end = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or("Malformed HTTP response")?;
+    let head = String::from_utf8_lossy(&response[..head_end]);
+    let mut lines = head.lines();
+    let status_line = lines.next().unwrap_or_default();
+    let mut parts = status_line.split_whitespace();
+    let http_version = parts.next().unwrap_or_default().to_string();
+    let status = parts
+        .next()
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| format!("Malformed status line: {}", status_line))?;
+
+    let headers: Vec<(&str, &str)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .collect();
+    let header = |wanted: &str| {
+        headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(wanted))
+            .map(|(_, value)| *value)
+    };
+    let chunked = header("transfer-encoding").is_some_and(|value| value.to_ascii_lowercase().contains("chunked"));
+
+    let body = &response[head_end + 4..];
+    Ok(ParsedResponse {
+        http_version,
+        status,
+        location: header("location").map(str::to_string),
+        size_bytes: if chunked { chunked_size(body) } else { body.len() },
+    })
+}
+
+/// The size of a chunked body once decoded: the chunk data without the
+/// chunk-size lines and trailers. A body cut short counts the data that
+/// arrived.
+fn chunked_size(mut body: &[u8]) -> usize {
+    let mut size = 0;
+    while let Some(line_end) = body.windows(2).position(|window| window == b"\r\n") {
+        let line = String::from_utf8_lossy(&body[..line_end]);
+        // Chunk extensions follow the size after a semicolon.
+        let Ok(chunk) = usize::from_str_radix(line.split(';').next().unwrap_or_default().trim(), 16) else {
+            break;
+        };
+        if chunk == 0 {
+            break;
+        }
+        let data = &body[line_end + 2..];
+        size += chunk.min(data.len());
+        body = data.get(chunk + 2..).unwrap_or_default();
+    }
+    size
+}
+
+/// Connects to the first of `addresses` that accepts, so a host whose IPv6
+/// address is unreachable from here is still measured over IPv4, and for
+/// HTTPS completes a verified TLS handshake offering `alpn`.
+fn open(host: &str, addresses: &[SocketAddr], https: bool, alpn: &[u8]) -> Result<Opened> {
+    let start = Instant::now();
+    let mut failures = Vec::new();
+    let mut connected = None;
+    for address in addresses {
+        match TcpStream::connect_timeout(address, CONNECT_TIMEOUT) {
+            Ok(stream) => {
+                connected = Some((stream, *address));
+                break;
+            }
+            Err(e) => failures.push(format!("{}: {}", address, e)),
+        }
+    }
+    let (stream, address) =
+        connected.ok_or_else(|| eyre!("Failed to connect to {} ({})", host, failures.join("; ")))?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let connect = start.elapsed();
+
+    if !https {
+        return Ok(Opened {
+            stream: Box::new(stream),
+            address,
+            connect,
+            tls: Duration::ZERO,
+            alpn: None,
+        });
+    }
+
+    let tls = tls_connector(alpn)?
+        .build()
+        .connect(host, stream)
+        .map_err(|e| eyre!("TLS handshake with {} failed: {} (run `wpust ssl {}` for details)", host, e, host))?;
+    let alpn = tls
+        .ssl()
+        .selected_alpn_protocol()
+        .map(|protocol| String::from_utf8_lossy(protocol).into_owned());
+
+    Ok(Opened {
+        stream: Box::new(tls),
+        address,
+        connect,
+        tls: start.elapsed() - connect,
+        alpn,
+    })
+}
+
+/// A verifying TLS client offering `alpn`.
+fn tls_connector(alpn: &[u8]) -> Result<SslConnectorBuilder> {
+    let mut connector = SslConnector::builder(SslMethod::tls_client())?;
+    load_trust_store(&mut connector, None)?;
+    connector.set_alpn_protos(alpn)?;
+    // Many servers close the connection without a TLS close_notify, which
+    // OpenSSL 3 can report as an "unexpected eof while reading" error
+    // instead of the end of the response.
+    connector.set_options(IGNORE_UNEXPECTED_EOF);
+    Ok(connector)
+}
+
+impl fmt::Display for ResponseReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Response for: {}\n", self.hostname)?;
+
+        match reqwest::StatusCode::from_u16(self.status) {
+            Ok(status) => writeln!(f, "Status:       {}", status)?,
+            Err(_) => writeln!(f, "Status:       {}", self.status)?,
+        }
+        if !self.redirects.is_empty() {
+            writeln!(f, "Final URL:    {}", self.url)?;
+        }
+        if let Some(location) = &self.location {
+            writeln!(f, "Redirects to: {} (not followed)", location)?;
+        }
+        match &self.negotiated_protocol {
+            Some(protocol) => writeln!(f, "Negotiated:   {} (ALPN, offering h2 and http/1.1)", protocol)?,
+            None => writeln!(f, "Negotiated:   no ALPN")?,
+        }
+        writeln!(f, "Timed as:     {} request", self.http_version)?;
+        writeln!(f, "Remote IP:    {}", self.remote_ip)?;
+        writeln!(f, "Body size:    {} bytes\n", self.size_bytes)?;
+
+        if !self.redirects.is_empty() {
+            writeln!(f, "Redirects:")?;
+            for hop in &self.redirects {
+                writeln!(f, "  {} {} -> {} ({:.2?})", hop.status, hop.url, hop.location, hop.total)?;
+            }
+            writeln!(f)?;
+        }
+
+        let timing = &self.timing;
+        let phases = [
+            ("DNS lookup", timing.dns),
+            ("TCP connect", timing.connect),
+            ("TLS handshake", timing.tls),
+            ("Time to first byte", timing.ttfb),
+            ("Content transfer", timing.transfer),
+        ];
+
+        writeln!(f, "Timing of the final request:")?;
+        let mut elapsed = Duration::ZERO;
+        for (phase, duration) in phases {
+            elapsed += duration;
+            writeln!(f, "  {:<20} {:>10.2?}   (at {:.2?})", phase, duration, elapsed)?;
+        }
+        writeln!(f, "  {:<20} {:>10.2?}", "Total", timing.total)
+    }
+}
+
+impl Summary for ResponseReport {
+    fn summary(&self) -> String {
+        let summary = format!(
+            "HTTP {} in {:.2?} (TTFB {:.2?})",
+            self.status, self.timing.total, self.timing.ttfb
+        );
+        match self.redirects.len() {
+            0 => summary,
+            count => format!("{} after {} redirect(s)", summary, count),
+        }
+    }
+}
+
+/// Sends `samples` requests one after another, pausing `interval` between
+/// them. The first request is reported on its own as the cold-cache run and
+/// the statistics only cover the warm runs after it.
+pub fn sample_response(hostname: &str, samples: u32, interval: Duration) -> Result<SampledResponseReport> {
+    let mut cold = None;
+    let mut warm = Vec::new();
+    let mut errors = Vec::new();
+
+    for sample in 0..samples {
+        if sample > 0 {
+            std::thread::sleep(interval);
+        }
+        match measure_response(hostname) {
+            Ok(report) if sample == 0 => cold = Some(report),
+            Ok(report) => warm.push(report),
+            Err(e) => errors.push(format!("request {}: {}", sample + 1, e)),
+        }
+    }
+
+    let failures = errors.len() as u32;
+    if failures == samples {
+        return Err(eyre!("All {} requests failed, last error: {}", samples, errors.last().map(String::as_str).unwrap_or_default()));
+    }
+
+    let totals: Vec<Duration> = warm.iter().map(|report| report.timing.total).collect();
+    let ttfbs: Vec<Duration> = warm.iter().map(|report| report.timing.ttfb).collect();
+
+    Ok(SampledResponseReport {
+        hostname: hostname.to_string(),
+        samples,
+        interval,
+        failures,
+        failure_rate: f64::from(failures) / f64::from(samples),
+        cold,
+        warm_total: LatencyStats::from_samples(&totals),
+        warm_ttfb: LatencyStats::from_samples(&ttfbs),
+        errors,
+    })
+}
+
+impl LatencyStats {
+    /// Min, max, nearest-rank median and p95, and the population standard
+    /// deviation of `samples`, or `None` without any.
+    fn from_samples(samples: &[Duration]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+        let count = sorted.len();
+
+        // Nearest-rank percentiles, so they are always an observed value;
+        // for an even count the median is the lower of the two middle ones.
+        let percentile = |p: f64| sorted[((p * count as f64).ceil() as usize).clamp(1, count) - 1];
+
+        let mean = sorted.iter().map(Duration::as_secs_f64).sum::<f64>() / count as f64;
+        let variance = sorted
+            .iter()
+            .map(|d| (d.as_secs_f64() - mean).powi(2))
+            .sum::<f64>()
+            / count as f64;
+
+        Some(LatencyStats {
+            count,
+            min: sorted[0],
+            median: percentile(0.5),
+            p95: percentile(0.95),
+            max: sorted[count - 1],
+            stddev: Duration::from_secs_f64(variance.sqrt()),
+        })
+    }
+}
+
+impl fmt::Display for SampledResponseReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Response sampling for: {} ({} requests, {:.2?} apart)\n",
+            self.hostname, self.samples, self.interval
+        )?;
+
+        match &self.cold {
+            Some(cold) => writeln!(
+                f,
+                "Cold (first) request: HTTP {} in {:.2?} (TTFB {:.2?})\n",
+                cold.status, cold.timing.total, cold.timing.ttfb
+            )?,
+            None => writeln!(f, "Cold (first) request: failed\n")?,
+        }
+
+        match (&self.warm_total, &self.warm_ttfb) {
+            (Some(total), Some(ttfb)) => {
+                writeln!(f, "Warm requests ({}):", total.count)?;
+                let mut table = Table::new(&["", "Min", "Median", "p95", "Max", "Std dev"]);
+                for (label, stats) in [("Total", total), ("TTFB", ttfb)] {
+                    table.add_row(vec![
+                        label.to_string(),
+                        format!("{:.2?}", stats.min),
+                        format!("{:.2?}", stats.median),
+                        format!("{:.2?}", stats.p95),
+                        format!("{:.2?}", stats.max),
+                        format!("{:.2?}", stats.stddev),
+                    ]);
+                }
+                write!(f, "{}", table)?;
+            }
+            _ => writeln!(f, "No successful warm requests.")?,
+        }
+
+        writeln!(
+            f,
+            "\nFailures: {} of {} ({:.0}%)",
+            self.failures,
+            self.samples,
+            self.failure_rate * 100.0
+        )?;
+        for error in &self.errors {
+            writeln!(f, "  - {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl Summary for SampledResponseReport {
+    fn summary(&self) -> String {
+        match &self.warm_total {
+            Some(total) => format!(
+                "median {:.2?}, p95 {:.2?}, {:.0}% failed",
+                total.median,
+                total.p95,
+                self.failure_rate * 100.0
+            ),
+            None => format!("no warm samples, {:.0}% failed", self.failure_rate * 100.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_response_with_content_length() {
+        let parsed = parse_response(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 13\r\n\r\n<p>Hello</p>\n",
+        )
+        .unwrap();
+        assert_eq!(parsed.http_version, "HTTP/1.1");
+        assert_eq!(parsed.status, 200);
+        assert_eq!(parsed.location, None);
+        assert_eq!(parsed.size_bytes, 13);
+    }
+
+    #[test]
+    fn parses_a_redirect() {
+        let parsed = parse_response(
+            b"HTTP/1.1 301 Moved Permanently\r\nlocation:  https://www.example.com/ \r\ncontent-length: 0\r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!(parsed.status, 301);
+        assert_eq!(parsed.location.as_deref(), Some("https://www.example.com/"));
+        assert_eq!(parsed.size_bytes, 0);
+    }
+
+    #[test]
+    fn chunked_bodies_are_measured_decoded() {
+        let parsed = parse_response(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+              7\r\n<html>\n\r\n19;name=value\r\n<body>Hello world</body>\n\r\n\
+              0\r\nX-Trailer: yes\r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!(parsed.size_bytes, 7 + 25);
+    }
+
+    #[test]
+    fn truncated_chunked_bodies_count_what_arrived() {
+        assert_eq!(chunked_size(b"a\r\n01234"), 5);
+        assert_eq!(chunked_size(b"5\r\n01234\r\nzz\r\n"), 5);
+        assert_eq!(chunked_size(b""), 0);
+    }
+
+    #[test]
+    fn malformed_responses_are_errors() {
+        assert_eq!(parse_response(b"HTTP/1.1 200 OK\r\n").err().as_deref(), Some("Malformed HTTP response"));
+        assert_eq!(
+            parse_response(b"garbage\r\n\r\n").err().as_deref(),
+            Some("Malformed status line: garbage")
+        );
+    }
+
+    #[test]
+    fn host_parts_handle_ipv6_literals_and_ports() {
+        let parts = |url: &str| host_parts(&Url::parse(url).unwrap()).unwrap();
+        assert_eq!(parts("https://example.com/"), ("example.com".to_string(), "example.com".to_string()));
+        assert_eq!(parts("http://example.com:8080/"), ("example.com".to_string(), "example.com:8080".to_string()));
+        assert_eq!(parts("https://[2001:db8::1]/"), ("2001:db8::1".to_string(), "[2001:db8::1]".to_string()));
+        assert_eq!(parts("https://[::1]:8443/"), ("::1".to_string(), "[::1]:8443".to_string()));
+        assert_eq!(parts("https://127.0.0.1/"), ("127.0.0.1".to_string(), "127.0.0.1".to_string()));
+    }
+
+    fn millis(values: &[u64]) -> Vec<Duration> {
+        values.iter().map(|&ms| Duration::from_millis(ms)).collect()
+    }
+
+    #[test]
+    fn no_samples_have_no_stats() {
+        assert!(LatencyStats::from_samples(&[]).is_none());
+    }
+
+    #[test]
+    fn single_sample() {
+        let stats = LatencyStats::from_samples(&millis(&[120])).unwrap();
+        assert_eq!(stats.count, 1);
+        assert_eq!(stats.min, Duration::from_millis(120));
+        assert_eq!(stats.median, Duration::from_millis(120));
+        assert_eq!(stats.p95, Duration::from_millis(120));
+        assert_eq!(stats.max, Duration::from_millis(120));
+        assert_eq!(stats.stddev, Duration::ZERO);
+    }
+
+    #[test]
+    fn even_count_median_is_the_lower_middle_value() {
+        let stats = LatencyStats::from_samples(&millis(&[40, 10, 30, 20])).unwrap();
+        assert_eq!(stats.min, Duration::from_millis(10));
+        assert_eq!(stats.median, Duration::from_millis(20));
+        assert_eq!(stats.max, Duration::from_millis(40));
+    }
+
+    #[test]
+    fn odd_count_median_is_the_middle_value() {
+        let stats = LatencyStats::from_samples(&millis(&[30, 10, 20])).unwrap();
+        assert_eq!(stats.median, Duration::from_millis(20));
+    }
+
+    #[test]
+    fn p95_of_twenty_samples_is_the_nineteenth() {
+        let samples: Vec<u64> = (1..=20).rev().collect();
+        let stats = LatencyStats::from_samples(&millis(&samples)).unwrap();
+        assert_eq!(stats.p95, Duration::from_millis(19));
+        assert_eq!(stats.median, Duration::from_millis(10));
+    }
+
+    #[test]
+    fn p95_of_twenty_one_samples_is_the_twentieth() {
+        let samples: Vec<u64> = (1..=21).collect();
+        let stats = LatencyStats::from_samples(&millis(&samples)).unwrap();
+        assert_eq!(stats.p95, Duration::from_millis(20));
+        assert_eq!(stats.median, Duration::from_millis(11));
+        assert_eq!(stats.max, Duration::from_millis(21));
+    }
 
-    let headers_response = reqwest::blocking::get(format!("https://{}", hostname))
-        .map_err(|e| color_eyre::eyre::eyre!("HTTP request failed: {}", e))?;
+    #[test]
+    fn population_standard_deviation() {
+        // Mean 5 ms, squared deviations sum to 32, over 8 samples: 2 ms.
+        let stats = LatencyStats::from_samples(&millis(&[2, 4, 4, 4, 5, 5, 7, 9])).unwrap();
+        assert!((stats.stddev.as_secs_f64() - 0.002).abs() < 1e-9);
+    }
 
-    let status = headers_response.status();
+    /// A self-signed certificate and key for `localhost`.
+    fn localhost_certificate() -> (openssl::x509::X509, openssl::pkey::PKey<openssl::pkey::Private>) {
+        use openssl::asn1::Asn1Time;
+        use openssl::ec::{EcGroup, EcKey};
+        use openssl::hash::MessageDigest;
+        use openssl::nid::Nid;
+        use openssl::pkey::PKey;
+        use openssl::x509::extension::SubjectAlternativeName;
+        use openssl::x509::{X509, X509Name};
 
-    let duration_headers = start_time_headers.elapsed();
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let mut name = X509Name::builder().unwrap();
+        name.append_entry_by_text("CN", "localhost").unwrap();
+        let name = name.build();
 
-    let start_time_text = Instant::now();
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+        builder.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+        let alt_names = SubjectAlternativeName::new()
+            .dns("localhost")
+            .build(&builder.x509v3_context(None, None))
+            .unwrap();
+        builder.append_extension(alt_names).unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+        (builder.build(), key)
+    }
 
-    let _text_response = headers_response.text().map_err(|e| color_eyre::eyre::eyre!("Failed to get text: {}", e))?;
+    #[test]
+    fn a_tls_close_without_close_notify_ends_the_response() {
+        use openssl::ssl::SslAcceptor;
+        use std::net::TcpListener;
 
-    let duration_text = start_time_text.elapsed();
+        let (certificate, key) = localhost_certificate();
+        let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls_server()).unwrap();
+        acceptor.set_certificate(&certificate).unwrap();
+        acceptor.set_private_key(&key).unwrap();
+        let acceptor = acceptor.build();
 
-    println!("Response for: {}\n", hostname);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut tls = acceptor.accept(stream).unwrap();
+            let mut request = [0; 1024];
+            let _ = tls.read(&mut request);
+            tls.write_all(b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\r\nHello").unwrap();
+            // Dropping the stream closes the socket without a close_notify.
+            drop(tls);
+        });
 
-    println!("Status: {}\n", status);
+        let mut connector = tls_connector(ALPN_HTTP1).unwrap();
+        connector.cert_store_mut().add_cert(certificate).unwrap();
+        let stream = TcpStream::connect(address).unwrap();
+        let mut tls = connector.build().connect("localhost", stream).unwrap();
+        tls.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
 
-    println!("Duration for headers response: {:.2?}\n", duration_headers);
-    println!("Duration for text response: {:.2?}\n", duration_text);
+        let (response, first_byte) = read_to_close(&mut tls, Instant::now()).unwrap();
+        server.join().unwrap();
 
-    Ok(())
+        assert!(first_byte.is_some());
+        let parsed = parse_response(&response).unwrap();
+        assert_eq!(parsed.status, 200);
+        assert_eq!(parsed.size_bytes, 5);
+    }
 }
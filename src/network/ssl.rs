@@ -1,68 +1,173 @@
 use color_eyre::eyre::Result;
-use openssl::ssl::{SslConnector, SslMethod, SslVerifyMode};
-use std::net::TcpStream;
-use crate::utils::extract_hostname;
+use openssl::nid::Nid;
+use openssl::pkey::Id;
+use openssl::ssl::{SslConnector, SslConnectorBuilder, SslFiletype, SslMethod, SslVerifyMode};
+use openssl::x509::store::X509Lookup;
+use openssl::x509::{X509, X509NameRef, X509Ref, X509VerifyResult};
+use serde::Serialize;
+use std::fmt;
+use std::net::{IpAddr, TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::time::Duration;
+use crate::batch::Summary;
+use crate::utils::{UtcDateTime, extract_root_domain, unix_now};
 
-pub fn ssl(site: String) -> Result<()> {
-    let hostname = extract_hostname(&site)?;
+const TIMEOUT: Duration = Duration::from_secs(10);
 
-    println!("SSL Certificate information for: {}\n", hostname);
+#[derive(Serialize)]
+pub struct SslReport {
+    pub hostname: String,
+    pub subject: Vec<NameEntry>,
+    pub issuer: Vec<NameEntry>,
+    pub subject_alt_names: Vec<String>,
+    pub coverage: Vec<NameCoverage>,
+    pub public_key: PublicKeyInfo,
+    pub signature_algorithm: String,
+    pub certificate_type: CertificateType,
+    pub ocsp_urls: Vec<String>,
+    pub crl_urls: Vec<String>,
+    pub not_before: String,
+    pub not_after: String,
+    /// Whole days left until expiry, negative once the certificate expired.
+    pub days_until_expiry: i64,
+    pub expires_at: String,
+    #[serde(skip)]
+    pub expires_at_unix: i64,
+    pub serial_number: String,
+    pub fingerprint_sha256: String,
+    pub validation: ChainValidation,
+}
+
+#[derive(Serialize)]
+pub struct NameCoverage {
+    pub name: String,
+    pub covered: bool,
+}
+
+#[derive(Serialize)]
+pub struct PublicKeyInfo {
+    pub algorithm: String,
+    pub bits: u32,
+}
+
+#[derive(Serialize)]
+pub struct CertificateType {
+    pub level: ValidationLevel,
+    /// False when the level was read from the certificate policies, true
+    /// when it had to be guessed from the subject.
+    pub inferred: bool,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ValidationLevel {
+    Ev,
+    Ov,
+    Iv,
+    Dv,
+}
+
+#[derive(Serialize)]
+pub struct ChainValidation {
+    pub trust_store: String,
+    pub trusted: bool,
+    pub verify_code: i32,
+    pub verify_message: String,
+    pub hostname_matches: bool,
+    pub matched_name: Option<String>,
+    /// Why the hostname does not match, when it does not.
+    pub hostname_mismatch: Option<String>,
+    pub missing_intermediate: bool,
+    /// Where clients that fetch missing intermediates themselves (browsers)
+    /// download the leaf's issuer from.
+    pub ca_issuers_url: Option<String>,
+    pub chain: Vec<ChainCertificate>,
+}
+
+#[derive(Serialize)]
+pub struct ChainCertificate {
+    pub subject: String,
+    pub issuer: String,
+    pub not_after: String,
+    pub self_signed: bool,
+}
+
+#[derive(Serialize)]
+pub struct NameEntry {
+    pub field: String,
+    pub value: String,
+}
 
+pub fn inspect_certificate(hostname: &str, ca_file: Option<&Path>) -> Result<SslReport> {
+    // Verification failures are recorded instead of aborting the handshake,
+    // so broken certificates can still be inspected.
     let mut connector = SslConnector::builder(SslMethod::tls())?;
     connector.set_verify(SslVerifyMode::NONE);
+    let trust_store = load_trust_store(&mut connector, ca_file)?;
     let connector = connector.build();
 
-    let stream = TcpStream::connect(format!("{}:443", hostname))
-        .map_err(|e| color_eyre::eyre::eyre!("Failed to connect to {}:443: {}", hostname, e))?;
+    // A host that accepts the connection and then stalls the handshake must
+    // not hold up a whole dashboard or batch. Every address is tried, so an
+    // unreachable IPv6 address does not hide a working IPv4 one.
+    let mut last_error = None;
+    let mut connected = None;
+    for address in (hostname, 443)
+        .to_socket_addrs()
+        .map_err(|e| color_eyre::eyre::eyre!("Failed to resolve {}: {}", hostname, e))?
+    {
+        match TcpStream::connect_timeout(&address, TIMEOUT) {
+            Ok(stream) => {
+                connected = Some(stream);
+                break;
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    let stream = match (connected, last_error) {
+        (Some(stream), _) => stream,
+        (None, Some(e)) => return Err(color_eyre::eyre::eyre!("Failed to connect to {}:443: {}", hostname, e)),
+        (None, None) => return Err(color_eyre::eyre::eyre!("No address found for {}", hostname)),
+    };
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
 
-    let ssl_stream = connector.connect(&hostname, stream)
+    // The hostname is checked separately so the verify result only reflects
+    // whether the chain is trusted.
+    let ssl_stream = connector.configure()?
+        .verify_hostname(false)
+        .connect(hostname, stream)
         .map_err(|e| color_eyre::eyre::eyre!("SSL handshake failed: {}", e))?;
 
     let certificate = ssl_stream.ssl()
         .peer_certificate()
         .ok_or_else(|| color_eyre::eyre::eyre!("No certificate found"))?;
 
-    let subject = certificate.subject_name();
-    let issuer = certificate.issuer_name();
+    let sent_chain: Vec<X509> = ssl_stream.ssl()
+        .peer_cert_chain()
+        .map(|chain| chain.iter().map(|cert| cert.to_owned()).collect())
+        .unwrap_or_default();
 
-    println!("Subject:");
-    for entry in subject.entries() {
-        let object_name = entry.object().nid().short_name().unwrap_or("UNKNOWN");
-        let value = entry.data().as_utf8()
-            .map_err(|e| color_eyre::eyre::eyre!("Failed to decode subject entry: {}", e))?
-            .to_string();
-        println!("  {}: {}", object_name, value);
-    }
+    let validation = validate_chain(
+        hostname,
+        &certificate,
+        &sent_chain,
+        ssl_stream.ssl().verify_result(),
+        trust_store,
+    )?;
 
-    println!("\nIssuer:");
-    for entry in issuer.entries() {
-        let object_name = entry.object().nid().short_name().unwrap_or("UNKNOWN");
-        let value = entry.data().as_utf8()
-            .map_err(|e| color_eyre::eyre::eyre!("Failed to decode issuer entry: {}", e))?
-            .to_string();
-        println!("  {}: {}", object_name, value);
-    }
+    let subject = name_entries(certificate.subject_name(), "subject")?;
+    let issuer = name_entries(certificate.issuer_name(), "issuer")?;
 
     let not_before = certificate.not_before();
     let not_after = certificate.not_after();
 
-    println!("\nValidity:");
-    println!("  Not Before: {}", not_before);
-    println!("  Not After:  {}", not_after);
-
     let now = openssl::asn1::Asn1Time::days_from_now(0)
         .map_err(|e| color_eyre::eyre::eyre!("Failed to get current time: {}", e))?;
 
-    let diff_result = now.diff(&not_after)?;
-    let days_until_expiry = (diff_result.days as i64).max(0);
-
-    if days_until_expiry == 0 {
-        println!("  ⚠️  Certificate has expired!");
-    } else if days_until_expiry <= 30 {
-        println!("  ⚠️  Certificate expires in {} days", days_until_expiry);
-    } else {
-        println!("  ✓ Certificate is valid for {} more days", days_until_expiry);
-    }
+    let diff_result = now.diff(not_after)?;
+    let seconds_until_expiry = diff_result.days as i64 * 86_400 + diff_result.secs as i64;
+    let days_until_expiry = seconds_until_expiry.div_euclid(86_400);
+    let expires_at_unix = unix_now() + seconds_until_expiry;
 
     let serial_number = certificate.serial_number();
     let serial_bytes = serial_number.to_bn()
@@ -75,14 +180,644 @@ pub fn ssl(site: String) -> Result<()> {
         .collect::<Vec<_>>()
         .join(":");
 
-    println!("\nSerial Number:");
-    println!("  {}", serial_hex);
-
     let fingerprint_sha256 = certificate.digest(openssl::hash::MessageDigest::sha256())
         .map_err(|e| color_eyre::eyre::eyre!("Failed to calculate fingerprint: {}", e))?;
 
-    println!("\nFingerprint (SHA-256):");
-    println!("  {}", hex::encode(fingerprint_sha256.as_ref()));
+    // Clients only match against the Subject Alternative Names; the Common
+    // Name is ignored (RFC 6125).
+    let names = subject_alt_names(&certificate);
+    let coverage = coverage_candidates(hostname)
+        .into_iter()
+        .map(|name| NameCoverage {
+            covered: names.iter().any(|pattern| name_matches(pattern, &name)),
+            name,
+        })
+        .collect();
+
+    let ocsp_urls = certificate.ocsp_responders()
+        .map(|responders| responders.iter().map(|url| url.to_string()).collect())
+        .unwrap_or_default();
+
+    Ok(SslReport {
+        hostname: hostname.to_string(),
+        certificate_type: certificate_type(&certificate, &subject)?,
+        subject,
+        issuer,
+        subject_alt_names: subject_alt_names(&certificate),
+        coverage,
+        public_key: public_key_info(&certificate)?,
+        signature_algorithm: certificate.signature_algorithm().object().nid().long_name()
+            .unwrap_or("unknown")
+            .to_string(),
+        ocsp_urls,
+        crl_urls: crl_urls(&certificate),
+        not_before: not_before.to_string(),
+        not_after: not_after.to_string(),
+        days_until_expiry,
+        expires_at: UtcDateTime::from_unix(expires_at_unix).iso8601(),
+        expires_at_unix,
+        serial_number: serial_hex,
+        fingerprint_sha256: hex::encode(fingerprint_sha256.as_ref()),
+        validation,
+    })
+}
+
+pub(super) fn load_trust_store(builder: &mut SslConnectorBuilder, ca_file: Option<&Path>) -> Result<String> {
+    if let Some(path) = ca_file {
+        builder.set_ca_file(path)
+            .map_err(|e| color_eyre::eyre::eyre!("Failed to load CA file {}: {}", path.display(), e))?;
+        return Ok(path.display().to_string());
+    }
+
+    // The vendored OpenSSL does not know where this system keeps its trusted
+    // certificates, so look for them in the usual locations.
+    let probe = openssl_probe::probe();
+    let mut sources = Vec::new();
+
+    if let Some(file) = &probe.cert_file {
+        if builder.set_ca_file(file).is_ok() {
+            sources.push(file.display().to_string());
+        }
+    }
+    if let Some(dir) = probe.cert_dir.as_deref().and_then(Path::to_str) {
+        let lookup = builder.cert_store_mut().add_lookup(X509Lookup::hash_dir())?;
+        if lookup.add_dir(dir, SslFiletype::PEM).is_ok() {
+            sources.push(dir.to_string());
+        }
+    }
+
+    if sources.is_empty() {
+        Ok("system (not found)".to_string())
+    } else {
+        Ok(format!("system ({})", sources.join(", ")))
+    }
+}
+
+fn validate_chain(
+    hostname: &str,
+    leaf: &X509Ref,
+    sent_chain: &[X509],
+    verify_result: X509VerifyResult,
+    trust_store: String,
+) -> Result<ChainValidation> {
+    let trusted = verify_result == X509VerifyResult::OK;
+    let names = subject_alt_names(leaf);
+    let matched_name = names.iter().find(|name| name_matches(name, hostname)).cloned();
+    let hostname_mismatch = match (&matched_name, names.is_empty()) {
+        (Some(_), _) => None,
+        (None, true) => Some(
+            "the certificate has no subjectAltName, and clients ignore the Common Name".to_string(),
+        ),
+        (None, false) => Some("the hostname is not among the certificate's names".to_string()),
+    };
+
+    // The classic "works in Chrome, broken in curl" case: the chain is not
+    // trusted and the server sends nothing that issued the leaf. Browsers
+    // fetch or cache the intermediate; curl, PHP and wget cannot.
+    let issuer_sent = sent_chain.iter().any(|candidate| {
+        candidate.as_ref() != leaf && candidate.issued(leaf) == X509VerifyResult::OK
+    });
+    let missing_intermediate = !trusted && !issuer_sent && !is_self_signed(leaf);
+    let ca_issuers_url = if missing_intermediate { ca_issuers_url(leaf) } else { None };
+
+    let mut chain = Vec::new();
+    for cert in sent_chain {
+        chain.push(ChainCertificate {
+            subject: one_line_name(cert.subject_name(), "subject")?,
+            issuer: one_line_name(cert.issuer_name(), "issuer")?,
+            not_after: cert.not_after().to_string(),
+            self_signed: is_self_signed(cert),
+        });
+    }
+
+    Ok(ChainValidation {
+        trust_store,
+        trusted,
+        verify_code: verify_result.as_raw(),
+        verify_message: verify_result.error_string().to_string(),
+        hostname_matches: matched_name.is_some(),
+        matched_name,
+        hostname_mismatch,
+        missing_intermediate,
+        ca_issuers_url,
+        chain,
+    })
+}
+
+/// DNS names and IP addresses listed in the certificate's Subject
+/// Alternative Name extension.
+fn subject_alt_names(cert: &X509Ref) -> Vec<String> {
+    cert.subject_alt_names()
+        .map(|sans| {
+            sans.iter()
+                .filter_map(|san| {
+                    san.dnsname().map(str::to_string).or_else(|| san.ipaddress().and_then(ip_to_string))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The hostname plus its www / non-www counterpart, the names most often
+/// expected to share a certificate.
+fn coverage_candidates(hostname: &str) -> Vec<String> {
+    let mut candidates = vec![hostname.to_string()];
+
+    match hostname.strip_prefix("www.") {
+        Some(bare) => candidates.push(bare.to_string()),
+        None if hostname.parse::<IpAddr>().is_err()
+            && extract_root_domain(hostname).is_ok_and(|root| root == hostname) =>
+        {
+            candidates.push(format!("www.{}", hostname));
+        }
+        None => {}
+    }
+
+    candidates
+}
+
+fn public_key_info(cert: &X509Ref) -> Result<PublicKeyInfo> {
+    let key = cert.public_key()
+        .map_err(|e| color_eyre::eyre::eyre!("Failed to read public key: {}", e))?;
+
+    let algorithm = match key.id() {
+        Id::RSA => "RSA".to_string(),
+        Id::EC => {
+            let curve = key.ec_key().ok()
+                .and_then(|ec| ec.group().curve_name())
+                .and_then(|nid| nid.short_name().ok())
+                .unwrap_or("unknown curve");
+            format!("EC {}", curve)
+        }
+        Id::ED25519 => "Ed25519".to_string(),
+        Id::ED448 => "Ed448".to_string(),
+        Id::DSA => "DSA".to_string(),
+        other => format!("unknown (id {})", other.as_raw()),
+    };
+
+    Ok(PublicKeyInfo {
+        algorithm,
+        bits: key.bits(),
+    })
+}
+
+fn crl_urls(cert: &X509Ref) -> Vec<String> {
+    let Some(points) = cert.crl_distribution_points() else {
+        return Vec::new();
+    };
+
+    points.iter()
+        .filter_map(|point| point.distpoint())
+        .filter_map(|name| name.fullname())
+        .flat_map(|names| names.iter().filter_map(|n| n.uri().map(str::to_string)))
+        .collect()
+}
+
+/// DER encodings of the CA/Browser Forum certificate policy OIDs
+/// (2.23.140.1.1 and 2.23.140.1.2.x), including the OID tag and length.
+const POLICY_OIDS: [(&[u8], ValidationLevel); 4] = [
+    (&[0x06, 0x05, 0x67, 0x81, 0x0C, 0x01, 0x01], ValidationLevel::Ev),
+    (&[0x06, 0x06, 0x67, 0x81, 0x0C, 0x01, 0x02, 0x02], ValidationLevel::Ov),
+    (&[0x06, 0x06, 0x67, 0x81, 0x0C, 0x01, 0x02, 0x03], ValidationLevel::Iv),
+    (&[0x06, 0x06, 0x67, 0x81, 0x0C, 0x01, 0x02, 0x01], ValidationLevel::Dv),
+];
+
+/// Determines EV/OV/IV/DV from the certificate policies. The openssl crate
+/// does not expose the policies extension, so the DER is searched for the
+/// CA/Browser Forum policy OIDs. Without one, a certificate naming an
+/// organization is assumed to be OV.
+fn certificate_type(cert: &X509Ref, subject: &[NameEntry]) -> Result<CertificateType> {
+    let der = cert.to_der()
+        .map_err(|e| color_eyre::eyre::eyre!("Failed to encode certificate: {}", e))?;
+
+    for (oid, level) in POLICY_OIDS {
+        if der.windows(oid.len()).any(|window| window == oid) {
+            return Ok(CertificateType { level, inferred: false });
+        }
+    }
+
+    let level = if subject.iter().any(|entry| entry.field == "O") {
+        ValidationLevel::Ov
+    } else {
+        ValidationLevel::Dv
+    };
+    Ok(CertificateType { level, inferred: true })
+}
+
+fn ip_to_string(bytes: &[u8]) -> Option<String> {
+    match bytes.len() {
+        4 => <[u8; 4]>::try_from(bytes).ok().map(|b| IpAddr::from(b).to_string()),
+        16 => <[u8; 16]>::try_from(bytes).ok().map(|b| IpAddr::from(b).to_string()),
+        _ => None,
+    }
+}
+
+/// Matches a certificate name against a hostname, allowing a wildcard for
+/// exactly one leftmost label (`*.example.com`).
+fn name_matches(pattern: &str, hostname: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+    let hostname = hostname.to_ascii_lowercase();
+
+    match pattern.strip_prefix("*.") {
+        Some(suffix) => hostname
+            .split_once('.')
+            .is_some_and(|(label, rest)| !label.is_empty() && rest == suffix),
+        None => pattern == hostname,
+    }
+}
+
+fn is_self_signed(cert: &X509Ref) -> bool {
+    cert.issued(cert) == X509VerifyResult::OK
+}
+
+fn ca_issuers_url(cert: &X509Ref) -> Option<String> {
+    cert.authority_info()?
+        .iter()
+        .filter(|access| access.method().nid() == Nid::AD_CA_ISSUERS)
+        .find_map(|access| access.location().uri().map(str::to_string))
+}
+
+fn one_line_name(name: &X509NameRef, kind: &str) -> Result<String> {
+    Ok(name_entries(name, kind)?
+        .iter()
+        .map(|entry| format!("{}={}", entry.field, entry.value))
+        .collect::<Vec<_>>()
+        .join(", "))
+}
+
+fn name_entries(name: &X509NameRef, kind: &str) -> Result<Vec<NameEntry>> {
+    name.entries()
+        .map(|entry| {
+            let field = entry.object().nid().short_name().unwrap_or("UNKNOWN").to_string();
+            let value = entry.data().as_utf8()
+                .map_err(|e| color_eyre::eyre::eyre!("Failed to decode {} entry: {}", kind, e))?
+                .to_string();
+            Ok(NameEntry { field, value })
+        })
+        .collect()
+}
+
+impl fmt::Display for SslReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "SSL Certificate information for: {}\n", self.hostname)?;
+
+        writeln!(f, "Subject:")?;
+        for entry in &self.subject {
+            writeln!(f, "  {}: {}", entry.field, entry.value)?;
+        }
+
+        writeln!(f, "\nIssuer:")?;
+        for entry in &self.issuer {
+            writeln!(f, "  {}: {}", entry.field, entry.value)?;
+        }
+
+        writeln!(f, "\nSubject Alternative Names:")?;
+        if self.subject_alt_names.is_empty() {
+            writeln!(f, "  (none)")?;
+        }
+        for name in &self.subject_alt_names {
+            writeln!(f, "  - {}", name)?;
+        }
+
+        writeln!(f, "\nCoverage:")?;
+        for name in &self.coverage {
+            if name.covered {
+                writeln!(f, "  ✓ {} is covered", name.name)?;
+            } else {
+                writeln!(f, "  ❌ {} is NOT covered", name.name)?;
+            }
+        }
+
+        writeln!(f, "\nKey:")?;
+        writeln!(f, "  Public key:          {} ({} bits)", self.public_key.algorithm, self.public_key.bits)?;
+        writeln!(f, "  Signature algorithm: {}", self.signature_algorithm)?;
+
+        writeln!(f, "\nCertificate type:")?;
+        writeln!(
+            f,
+            "  {}{}",
+            self.certificate_type.level,
+            if self.certificate_type.inferred { " (inferred from subject, no CA/B policy found)" } else { "" }
+        )?;
+
+        writeln!(f, "\nRevocation:")?;
+        if self.ocsp_urls.is_empty() && self.crl_urls.is_empty() {
+            writeln!(f, "  (no OCSP or CRL URLs)")?;
+        }
+        for url in &self.ocsp_urls {
+            writeln!(f, "  OCSP: {}", url)?;
+        }
+        for url in &self.crl_urls {
+            writeln!(f, "  CRL:  {}", url)?;
+        }
+
+        writeln!(f, "\nValidity:")?;
+        writeln!(f, "  Not Before: {}", self.not_before)?;
+        writeln!(f, "  Not After:  {}", self.not_after)?;
+
+        if self.days_until_expiry < 0 {
+            writeln!(f, "  ⚠️  Certificate has expired! ({} days ago)", -self.days_until_expiry)?;
+        } else if self.days_until_expiry <= 30 {
+            writeln!(f, "  ⚠️  Certificate expires in {} days", self.days_until_expiry)?;
+        } else {
+            writeln!(f, "  ✓ Certificate is valid for {} more days", self.days_until_expiry)?;
+        }
+
+        writeln!(f, "\nSerial Number:")?;
+        writeln!(f, "  {}", self.serial_number)?;
+
+        writeln!(f, "\nFingerprint (SHA-256):")?;
+        writeln!(f, "  {}", self.fingerprint_sha256)?;
+
+        write!(f, "\n{}", self.validation)
+    }
+}
+
+impl fmt::Display for ValidationLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationLevel::Ev => write!(f, "EV (extended validation)"),
+            ValidationLevel::Ov => write!(f, "OV (organization validated)"),
+            ValidationLevel::Iv => write!(f, "IV (individual validated)"),
+            ValidationLevel::Dv => write!(f, "DV (domain validated)"),
+        }
+    }
+}
+
+impl fmt::Display for ChainValidation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Validation:")?;
+        writeln!(f, "  Trust store: {}", self.trust_store)?;
+
+        if self.trusted {
+            writeln!(f, "  ✓ Certificate chain is trusted")?;
+        } else {
+            writeln!(
+                f,
+                "  ❌ Certificate chain is NOT trusted: {} (code {})",
+                self.verify_message, self.verify_code
+            )?;
+        }
+
+        match (&self.matched_name, &self.hostname_mismatch) {
+            (Some(name), _) => writeln!(f, "  ✓ Hostname matches certificate name {}", name)?,
+            (None, Some(reason)) => writeln!(f, "  ❌ Hostname does not match: {}", reason)?,
+            (None, None) => writeln!(f, "  ❌ Hostname does not match any name on the certificate")?,
+        }
+
+        if self.missing_intermediate {
+            writeln!(f, "  ⚠️  Missing intermediate certificate: the server does not send the full chain.")?;
+            writeln!(f, "     Browsers may still work by fetching or caching the intermediate, but curl,")?;
+            writeln!(f, "     PHP and other clients will fail. Configure the server with the full chain")?;
+            writeln!(f, "     (e.g. fullchain.pem instead of cert.pem).")?;
+            if let Some(url) = &self.ca_issuers_url {
+                writeln!(f, "     Missing issuer can be downloaded from: {}", url)?;
+            }
+        }
+
+        writeln!(f, "\nCertificate chain (as sent by the server):")?;
+        for (i, cert) in self.chain.iter().enumerate() {
+            writeln!(f, "  {}: {}", i, cert.subject)?;
+            if cert.self_signed {
+                writeln!(f, "     Issuer:  (self-signed)")?;
+            } else {
+                writeln!(f, "     Issuer:  {}", cert.issuer)?;
+            }
+            writeln!(f, "     Expires: {}", cert.not_after)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Summary for SslReport {
+    fn summary(&self) -> String {
+        let mut summary = if self.days_until_expiry < 0 {
+            format!("expired {} days ago ({})", -self.days_until_expiry, self.not_after)
+        } else {
+            format!("expires in {} days ({})", self.days_until_expiry, self.not_after)
+        };
+
+        if self.validation.missing_intermediate {
+            summary.push_str(", missing intermediate");
+        } else if !self.validation.trusted {
+            summary.push_str(", untrusted");
+        }
+        if !self.validation.hostname_matches {
+            summary.push_str(", hostname mismatch");
+        }
+
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::asn1::{Asn1Object, Asn1OctetString, Asn1Time};
+    use openssl::bn::BigNum;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::pkey::{PKey, Private};
+    use openssl::x509::extension::SubjectAlternativeName;
+    use openssl::x509::{X509Builder, X509Extension, X509Name};
+
+    fn key() -> PKey<Private> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap()
+    }
+
+    fn name(entries: &[(&str, &str)]) -> X509Name {
+        let mut name = X509Name::builder().unwrap();
+        for (field, value) in entries {
+            name.append_entry_by_text(field, value).unwrap();
+        }
+        name.build()
+    }
+
+    /// A certificate for `subject` with `sans`, signed by `issuer` (itself
+    /// when `None`).
+    fn certificate(
+        subject: &[(&str, &str)],
+        sans: &[&str],
+        extensions: Vec<X509Extension>,
+        issuer: Option<(&X509Name, &PKey<Private>)>,
+    ) -> (X509, PKey<Private>) {
+        let key = key();
+        let subject = name(subject);
+        let (issuer_name, signer) = issuer.unwrap_or((&subject, &key));
 
-    Ok(())
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_serial_number(&BigNum::from_u32(1).unwrap().to_asn1_integer().unwrap()).unwrap();
+        builder.set_subject_name(&subject).unwrap();
+        builder.set_issuer_name(issuer_name).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+        builder.set_not_after(&Asn1Time::days_from_now(90).unwrap()).unwrap();
+        if !sans.is_empty() {
+            let mut alt_names = SubjectAlternativeName::new();
+            for san in sans {
+                alt_names.dns(san);
+            }
+            let extension = alt_names.build(&builder.x509v3_context(None, None)).unwrap();
+            builder.append_extension(extension).unwrap();
+        }
+        for extension in extensions {
+            builder.append_extension(extension).unwrap();
+        }
+        builder.sign(signer, MessageDigest::sha256()).unwrap();
+        (builder.build(), key)
+    }
+
+    /// A certificatePolicies extension holding the single policy `oid`,
+    /// given as its DER content bytes.
+    fn policies(oid: &[u8]) -> X509Extension {
+        let mut policy_information = vec![0x30, oid.len() as u8 + 2, 0x06, oid.len() as u8];
+        policy_information.extend_from_slice(oid);
+        let mut policies = vec![0x30, policy_information.len() as u8];
+        policies.extend(policy_information);
+
+        let certificate_policies = Asn1Object::from_str("2.5.29.32").unwrap();
+        let value = Asn1OctetString::new_from_bytes(&policies).unwrap();
+        X509Extension::new_from_der(&certificate_policies, false, &value).unwrap()
+    }
+
+    fn certificate_type_of(subject: &[(&str, &str)], extensions: Vec<X509Extension>) -> CertificateType {
+        let (cert, _) = certificate(subject, &["example.com"], extensions, None);
+        let entries = name_entries(cert.subject_name(), "subject").unwrap();
+        certificate_type(&cert, &entries).unwrap()
+    }
+
+    fn validate(hostname: &str, leaf: &X509, sent_chain: &[X509], verify_result: X509VerifyResult) -> ChainValidation {
+        validate_chain(hostname, leaf, sent_chain, verify_result, "test".to_string()).unwrap()
+    }
+
+    #[test]
+    fn exact_names_match_case_insensitively() {
+        assert!(name_matches("example.com", "example.com"));
+        assert!(name_matches("Example.COM", "example.com"));
+        assert!(name_matches("example.com", "EXAMPLE.com"));
+        assert!(!name_matches("example.com", "www.example.com"));
+        assert!(!name_matches("example.com", "example.org"));
+    }
+
+    #[test]
+    fn wildcard_covers_exactly_one_label() {
+        assert!(name_matches("*.example.com", "www.example.com"));
+        assert!(name_matches("*.EXAMPLE.com", "Shop.example.COM"));
+        assert!(!name_matches("*.example.com", "a.b.example.com"));
+        assert!(!name_matches("*.example.com", "example.com"));
+        assert!(!name_matches("*.example.com", ".example.com"));
+    }
+
+    #[test]
+    fn no_partial_label_wildcards() {
+        assert!(!name_matches("w*.example.com", "www.example.com"));
+        assert!(!name_matches("*w.example.com", "www.example.com"));
+        assert!(!name_matches("www.*.com", "www.example.com"));
+    }
+
+    #[test]
+    fn coverage_adds_the_www_counterpart() {
+        assert_eq!(coverage_candidates("example.com"), vec!["example.com", "www.example.com"]);
+        assert_eq!(coverage_candidates("www.example.com"), vec!["www.example.com", "example.com"]);
+    }
+
+    #[test]
+    fn coverage_leaves_other_names_alone() {
+        assert_eq!(coverage_candidates("blog.example.com"), vec!["blog.example.com"]);
+        assert_eq!(coverage_candidates("192.0.2.1"), vec!["192.0.2.1"]);
+    }
+
+    #[test]
+    fn self_signed_certificate_is_not_a_missing_intermediate() {
+        let (leaf, _) = certificate(&[("CN", "example.com")], &["example.com", "*.example.com"], Vec::new(), None);
+        let validation = validate("www.example.com", &leaf, std::slice::from_ref(&leaf), X509VerifyResult::APPLICATION_VERIFICATION);
+
+        assert!(!validation.trusted);
+        assert!(validation.hostname_matches);
+        assert_eq!(validation.matched_name.as_deref(), Some("*.example.com"));
+        assert!(!validation.missing_intermediate);
+        assert_eq!(validation.chain.len(), 1);
+        assert!(validation.chain[0].self_signed);
+        assert_eq!(validation.chain[0].subject, "CN=example.com");
+    }
+
+    #[test]
+    fn hostname_mismatch() {
+        let (leaf, _) = certificate(&[("CN", "example.com")], &["example.com"], Vec::new(), None);
+        let validation = validate("example.org", &leaf, &[], X509VerifyResult::OK);
+
+        assert!(validation.trusted);
+        assert!(!validation.hostname_matches);
+        assert_eq!(validation.matched_name, None);
+        assert_eq!(
+            validation.hostname_mismatch.as_deref(),
+            Some("the hostname is not among the certificate's names")
+        );
+    }
+
+    #[test]
+    fn common_name_is_ignored_without_sans() {
+        let (leaf, _) = certificate(&[("CN", "example.com")], &[], Vec::new(), None);
+        let validation = validate("example.com", &leaf, &[], X509VerifyResult::OK);
+
+        assert!(!validation.hostname_matches);
+        assert_eq!(validation.matched_name, None);
+        assert!(validation.hostname_mismatch.as_deref().is_some_and(|reason| reason.contains("no subjectAltName")));
+    }
+
+    #[test]
+    fn untrusted_chain_without_the_issuer_is_a_missing_intermediate() {
+        let (ca, ca_key) = certificate(&[("CN", "Test Intermediate")], &[], Vec::new(), None);
+        let ca_name = ca.subject_name().to_owned().unwrap();
+        let (leaf, _) = certificate(&[("CN", "example.com")], &["example.com"], Vec::new(), Some((&ca_name, &ca_key)));
+
+        let validation = validate("example.com", &leaf, std::slice::from_ref(&leaf), X509VerifyResult::APPLICATION_VERIFICATION);
+        assert!(validation.missing_intermediate);
+        assert!(!validation.chain[0].self_signed);
+        assert_eq!(validation.chain[0].issuer, "CN=Test Intermediate");
+
+        let sent = [leaf.clone(), ca.clone()];
+        let validation = validate("example.com", &leaf, &sent, X509VerifyResult::APPLICATION_VERIFICATION);
+        assert!(!validation.missing_intermediate);
+
+        let validation = validate("example.com", &leaf, std::slice::from_ref(&leaf), X509VerifyResult::OK);
+        assert!(!validation.missing_intermediate);
+    }
+
+    #[test]
+    fn validation_level_from_the_policy_oid() {
+        // 2.23.140.1.1, 2.23.140.1.2.2, 2.23.140.1.2.3 and 2.23.140.1.2.1.
+        let cases = [
+            (&[0x67, 0x81, 0x0C, 0x01, 0x01][..], ValidationLevel::Ev),
+            (&[0x67, 0x81, 0x0C, 0x01, 0x02, 0x02][..], ValidationLevel::Ov),
+            (&[0x67, 0x81, 0x0C, 0x01, 0x02, 0x03][..], ValidationLevel::Iv),
+            (&[0x67, 0x81, 0x0C, 0x01, 0x02, 0x01][..], ValidationLevel::Dv),
+        ];
+        for (oid, level) in cases {
+            // An organization in the subject must not override the policy.
+            let certificate_type = certificate_type_of(&[("O", "Example Inc"), ("CN", "example.com")], vec![policies(oid)]);
+            assert_eq!(certificate_type.level, level);
+            assert!(!certificate_type.inferred);
+        }
+    }
+
+    #[test]
+    fn validation_level_is_inferred_without_a_cab_forum_policy() {
+        // 1.3.6.1.4.1.44947.1.1.1, a CA's own policy.
+        let other_policy = policies(&[0x2B, 0x06, 0x01, 0x04, 0x01, 0x82, 0xDF, 0x13, 0x01, 0x01, 0x01]);
+        let certificate_type = certificate_type_of(&[("CN", "example.com")], vec![other_policy]);
+        assert_eq!(certificate_type.level, ValidationLevel::Dv);
+        assert!(certificate_type.inferred);
+
+        let certificate_type = certificate_type_of(&[("CN", "example.com")], Vec::new());
+        assert_eq!(certificate_type.level, ValidationLevel::Dv);
+        assert!(certificate_type.inferred);
+
+        let certificate_type = certificate_type_of(&[("O", "Example Inc"), ("CN", "example.com")], Vec::new());
+        assert_eq!(certificate_type.level, ValidationLevel::Ov);
+        assert!(certificate_type.inferred);
+    }
 }
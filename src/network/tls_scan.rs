@@ -0,0 +1,498 @@
+use color_eyre::eyre::{Result, eyre};
+use openssl::ssl::{SslConnector, SslMethod, SslVerifyMode, SslVersion};
+use serde::Serialize;
+use std::fmt;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+use crate::batch::Summary;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+const PROTOCOLS: [(SslVersion, &str); 4] = [
+    (SslVersion::TLS1, "TLS 1.0"),
+    (SslVersion::TLS1_1, "TLS 1.1"),
+    (SslVersion::TLS1_2, "TLS 1.2"),
+    (SslVersion::TLS1_3, "TLS 1.3"),
+];
+
+const SWEET32: &str = "3DES is vulnerable to Sweet32";
+
+/// Cipher families a server may still accept, as OpenSSL cipher lists, with
+/// how the report names them.
+const LEGACY_CIPHER_FAMILIES: [(&str, &str); 4] = [
+    ("RC4", "RC4"),
+    ("EXP", "export-grade"),
+    ("DES", "single DES"),
+    ("3DES", "3DES"),
+];
+
+const TLS13_CIPHERSUITES: [&str; 5] = [
+    "TLS_AES_256_GCM_SHA384",
+    "TLS_CHACHA20_POLY1305_SHA256",
+    "TLS_AES_128_GCM_SHA256",
+    "TLS_AES_128_CCM_SHA256",
+    "TLS_AES_128_CCM_8_SHA256",
+];
+
+#[derive(Serialize)]
+pub struct TlsScanReport {
+    pub hostname: String,
+    pub address: SocketAddr,
+    pub grade: char,
+    pub protocols: Vec<ProtocolSupport>,
+    pub weaknesses: Vec<String>,
+    /// Cipher families the TLS library in use cannot offer, so the scan
+    /// cannot tell whether the server accepts them.
+    pub untestable: Vec<&'static str>,
+}
+
+#[derive(Serialize)]
+pub struct ProtocolSupport {
+    pub protocol: &'static str,
+    pub accepted: bool,
+    pub ciphers: Vec<CipherSupport>,
+}
+
+#[derive(Serialize)]
+pub struct CipherSupport {
+    pub name: String,
+    pub standard_name: Option<String>,
+    pub bits: i32,
+    pub strength: Strength,
+    pub issues: Vec<&'static str>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Strength {
+    Strong,
+    Weak,
+    Insecure,
+}
+
+pub fn scan_tls(hostname: &str) -> Result<TlsScanReport> {
+    let addresses: Vec<SocketAddr> = (hostname, 443)
+        .to_socket_addrs()
+        .map_err(|e| eyre!("Failed to resolve {}: {}", hostname, e))?
+        .collect();
+
+    // Every probe goes to the first address that accepts a connection, so an
+    // unreachable IPv6 address does not fail the whole scan. Failing early
+    // also keeps a closed port from being reported as "no protocols
+    // accepted".
+    let mut failures = Vec::new();
+    let address = addresses
+        .iter()
+        .copied()
+        .find(|address| match TcpStream::connect_timeout(address, TIMEOUT) {
+            Ok(_) => true,
+            Err(e) => {
+                failures.push(format!("{}: {}", address, e));
+                false
+            }
+        })
+        .ok_or_else(|| {
+            if failures.is_empty() {
+                eyre!("No address found for {}", hostname)
+            } else {
+                eyre!("Failed to connect to {}:443 ({})", hostname, failures.join("; "))
+            }
+        })?;
+
+    let mut protocols = Vec::new();
+    for (version, protocol) in PROTOCOLS {
+        let ciphers = accepted_ciphers(hostname, address, version)?;
+        protocols.push(ProtocolSupport {
+            protocol,
+            accepted: !ciphers.is_empty(),
+            ciphers,
+        });
+    }
+
+    if protocols.iter().all(|p| !p.accepted) {
+        return Err(eyre!("No TLS handshake succeeded with {}", hostname));
+    }
+
+    let (grade, weaknesses) = grade(&protocols);
+
+    Ok(TlsScanReport {
+        hostname: hostname.to_string(),
+        address,
+        grade,
+        protocols,
+        weaknesses,
+        untestable: untestable_cipher_families(),
+    })
+}
+
+/// The legacy cipher families this build of OpenSSL cannot offer. The
+/// bundled OpenSSL 3 leaves out RC4, export-grade and DES suites entirely.
+fn untestable_cipher_families() -> Vec<&'static str> {
+    LEGACY_CIPHER_FAMILIES
+        .iter()
+        .filter(|(cipher_list, _)| {
+            SslConnector::builder(SslMethod::tls_client()).map_or(true, |mut builder| {
+                builder.set_security_level(0);
+                builder.set_cipher_list(cipher_list).is_err()
+            })
+        })
+        .map(|(_, family)| *family)
+        .collect()
+}
+
+/// Finds every cipher the server accepts for one protocol version by
+/// repeatedly handshaking and excluding the cipher the server picked, until
+/// the handshake fails.
+fn accepted_ciphers(
+    hostname: &str,
+    address: SocketAddr,
+    version: SslVersion,
+) -> Result<Vec<CipherSupport>> {
+    let mut accepted = Vec::new();
+    let mut excluded: Vec<String> = Vec::new();
+
+    loop {
+        let mut builder = SslConnector::builder(SslMethod::tls_client())?;
+        builder.set_verify(SslVerifyMode::NONE);
+        builder.set_min_proto_version(Some(version))?;
+        builder.set_max_proto_version(Some(version))?;
+
+        if version == SslVersion::TLS1_3 {
+            let remaining: Vec<&str> = TLS13_CIPHERSUITES
+                .iter()
+                .copied()
+                .filter(|suite| !excluded.iter().any(|e| e == suite))
+                .collect();
+            if remaining.is_empty() || builder.set_ciphersuites(&remaining.join(":")).is_err() {
+                break;
+            }
+        } else {
+            let exclusions: String = excluded.iter().map(|name| format!(":!{}", name)).collect();
+            if builder.set_cipher_list(&format!("ALL:COMPLEMENTOFALL{}", exclusions)).is_err() {
+                break;
+            }
+        }
+        // Security level 0 re-enables old protocols and ciphers so they can
+        // be tested at all.
+        builder.set_security_level(0);
+        let connector = builder.build();
+
+        let Ok(stream) = TcpStream::connect_timeout(&address, TIMEOUT) else {
+            break;
+        };
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+
+        let Ok(ssl_stream) = connector.configure()?.verify_hostname(false).connect(hostname, stream) else {
+            break;
+        };
+        let Some(cipher) = ssl_stream.ssl().current_cipher() else {
+            break;
+        };
+
+        let name = cipher.name().to_string();
+        if excluded.contains(&name) {
+            break;
+        }
+
+        let issues = cipher_issues(&name, cipher.bits().secret);
+        accepted.push(CipherSupport {
+            standard_name: cipher.standard_name().map(str::to_string),
+            bits: cipher.bits().secret,
+            strength: strength(&issues),
+            issues: issues.iter().map(|(_, issue)| *issue).collect(),
+            name: name.clone(),
+        });
+        excluded.push(name);
+    }
+
+    Ok(accepted)
+}
+
+/// Known problems with a cipher, based on its OpenSSL name.
+fn cipher_issues(name: &str, bits: i32) -> Vec<(Strength, &'static str)> {
+    let mut issues = Vec::new();
+
+    if name.contains("NULL") {
+        issues.push((Strength::Insecure, "no encryption (NULL cipher)"));
+    }
+    if name.starts_with("EXP") {
+        issues.push((Strength::Insecure, "export-grade cipher"));
+    }
+    if name.starts_with("ADH") || name.starts_with("AECDH") || name.contains("anon") {
+        issues.push((Strength::Insecure, "anonymous key exchange (no authentication)"));
+    }
+    if name.contains("RC4") {
+        issues.push((Strength::Insecure, "RC4 is broken"));
+    }
+    if name.contains("MD5") {
+        issues.push((Strength::Insecure, "MD5 MAC"));
+    }
+    let triple_des = name.contains("DES-CBC3") || name.contains("3DES");
+    if triple_des {
+        issues.push((Strength::Weak, SWEET32));
+    } else if name.contains("DES") {
+        issues.push((Strength::Insecure, "single DES is broken"));
+    }
+    if name.contains("CCM8") || name.contains("CCM_8") {
+        // OpenSSL reports these as 64-bit because of the truncated tag.
+        issues.push((Strength::Weak, "truncated 8-byte authentication tag (CCM8)"));
+    } else if bits > 0 && bits < 128 && !triple_des {
+        // 3DES counts as 112 bits, which Sweet32 already covers.
+        issues.push((Strength::Insecure, "key shorter than 128 bits"));
+    }
+
+    let is_tls13 = name.starts_with("TLS_");
+    let forward_secret = name.starts_with("ECDHE") || name.starts_with("DHE");
+    let aead = name.contains("GCM") || name.contains("CHACHA20") || name.contains("CCM");
+    if !(is_tls13 || forward_secret) {
+        issues.push((Strength::Weak, "no forward secrecy"));
+    }
+    if !(is_tls13 || aead) {
+        issues.push((Strength::Weak, "CBC mode (prefer AEAD ciphers)"));
+    }
+
+    issues
+}
+
+fn strength(issues: &[(Strength, &'static str)]) -> Strength {
+    issues
+        .iter()
+        .map(|(strength, _)| *strength)
+        .max()
+        .unwrap_or(Strength::Strong)
+}
+
+/// Grades the configuration from A (modern) to F (insecure) and lists the
+/// problems behind the grade.
+fn grade(protocols: &[ProtocolSupport]) -> (char, Vec<String>) {
+    let mut grade = 'A';
+    let mut weaknesses = Vec::new();
+    // Grades only ever get worse: 'F' > 'C' > 'B' > 'A'.
+    let cap = |limit: char, grade: &mut char| *grade = (*grade).max(limit);
+
+    let accepts = |name: &str| protocols.iter().any(|p| p.protocol == name && p.accepted);
+
+    for old in ["TLS 1.0", "TLS 1.1"] {
+        if accepts(old) {
+            weaknesses.push(format!("{} is enabled (deprecated by RFC 8996)", old));
+            cap('B', &mut grade);
+        }
+    }
+    if !accepts("TLS 1.2") && !accepts("TLS 1.3") {
+        weaknesses.push("Neither TLS 1.2 nor TLS 1.3 is supported".to_string());
+        cap('C', &mut grade);
+    }
+
+    // A cipher accepted under several protocol versions is listed once.
+    let mut weak_ciphers: Vec<(&CipherSupport, Vec<&str>)> = Vec::new();
+    for protocol in protocols {
+        for cipher in protocol.ciphers.iter().filter(|c| c.strength != Strength::Strong) {
+            match weak_ciphers.iter_mut().find(|(seen, _)| seen.name == cipher.name) {
+                Some((_, versions)) => versions.push(protocol.protocol),
+                None => weak_ciphers.push((cipher, vec![protocol.protocol])),
+            }
+        }
+    }
+
+    for (cipher, versions) in weak_ciphers {
+        weaknesses.push(format!(
+            "{} ({}): {}",
+            cipher.name,
+            versions.join(", "),
+            cipher.issues.join(", ")
+        ));
+        match cipher.strength {
+            Strength::Insecure => cap('F', &mut grade),
+            _ if cipher.issues.contains(&SWEET32) => cap('C', &mut grade),
+            _ => cap('B', &mut grade),
+        }
+    }
+
+    (grade, weaknesses)
+}
+
+impl fmt::Display for TlsScanReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "TLS scan for: {} ({})\n", self.hostname, self.address)?;
+
+        writeln!(f, "Protocols:")?;
+        for protocol in &self.protocols {
+            if protocol.accepted {
+                writeln!(
+                    f,
+                    "  {}  ✓ accepted ({} cipher suites)",
+                    protocol.protocol,
+                    protocol.ciphers.len()
+                )?;
+            } else {
+                writeln!(f, "  {}  ✗ not accepted", protocol.protocol)?;
+            }
+        }
+
+        for protocol in self.protocols.iter().filter(|p| p.accepted) {
+            writeln!(f, "\n{} cipher suites:", protocol.protocol)?;
+            for cipher in &protocol.ciphers {
+                let marker = match cipher.strength {
+                    Strength::Strong => "✓".to_string(),
+                    Strength::Weak => format!("⚠️  {}", cipher.issues.join(", ")),
+                    Strength::Insecure => format!("❌ {}", cipher.issues.join(", ")),
+                };
+                writeln!(f, "  {:<36} {:>3} bits  {}", cipher.name, cipher.bits, marker)?;
+            }
+        }
+
+        if self.weaknesses.is_empty() {
+            writeln!(f, "\nNo weak protocols or ciphers found.")?;
+        } else {
+            writeln!(f, "\nWeak items found:")?;
+            for weakness in &self.weaknesses {
+                writeln!(f, "  - {}", weakness)?;
+            }
+        }
+
+        writeln!(f, "\nGrade: {}", self.grade)?;
+        if !self.untestable.is_empty() {
+            writeln!(
+                f,
+                "Not testable with this TLS library: {} cipher suites; the grade does not cover them.",
+                self.untestable.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Summary for TlsScanReport {
+    fn summary(&self) -> String {
+        let accepted: Vec<&str> = self.protocols
+            .iter()
+            .filter(|p| p.accepted)
+            .map(|p| p.protocol)
+            .collect();
+        format!(
+            "grade {}, {}, {} weak items",
+            self.grade,
+            accepted.join(", "),
+            self.weaknesses.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cipher(name: &str, bits: i32) -> CipherSupport {
+        let issues = cipher_issues(name, bits);
+        CipherSupport {
+            name: name.to_string(),
+            standard_name: None,
+            bits,
+            strength: strength(&issues),
+            issues: issues.iter().map(|(_, issue)| *issue).collect(),
+        }
+    }
+
+    /// The four protocol versions, each accepting the given ciphers.
+    fn protocols(tls10: &[(&str, i32)], tls12: &[(&str, i32)], tls13: &[(&str, i32)]) -> Vec<ProtocolSupport> {
+        [tls10, &[], tls12, tls13]
+            .iter()
+            .zip(PROTOCOLS)
+            .map(|(ciphers, (_, protocol))| ProtocolSupport {
+                protocol,
+                accepted: !ciphers.is_empty(),
+                ciphers: ciphers.iter().map(|(name, bits)| cipher(name, *bits)).collect(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn cipher_strengths() {
+        let cases = [
+            ("TLS_AES_256_GCM_SHA384", 256, Strength::Strong),
+            ("ECDHE-RSA-AES128-GCM-SHA256", 128, Strength::Strong),
+            ("ECDHE-RSA-CHACHA20-POLY1305", 256, Strength::Strong),
+            ("ECDHE-RSA-AES128-SHA", 128, Strength::Weak),
+            ("AES128-GCM-SHA256", 128, Strength::Weak),
+            ("TLS_AES_128_CCM_8_SHA256", 64, Strength::Weak),
+            ("ECDHE-RSA-DES-CBC3-SHA", 112, Strength::Weak),
+            ("DES-CBC3-SHA", 112, Strength::Weak),
+            ("RC4-SHA", 128, Strength::Insecure),
+            ("EXP-RC4-MD5", 40, Strength::Insecure),
+            ("EXP-EDH-RSA-DES-CBC-SHA", 40, Strength::Insecure),
+            ("DES-CBC-SHA", 56, Strength::Insecure),
+            ("ADH-AES256-GCM-SHA384", 256, Strength::Insecure),
+            ("NULL-SHA256", 0, Strength::Insecure),
+        ];
+        for (name, bits, expected) in cases {
+            assert_eq!(cipher(name, bits).strength, expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn triple_des_is_only_flagged_for_sweet32() {
+        let issues = cipher("ECDHE-RSA-DES-CBC3-SHA", 112).issues;
+        assert!(issues.contains(&SWEET32));
+        assert!(!issues.contains(&"key shorter than 128 bits"));
+    }
+
+    #[test]
+    fn grades() {
+        let modern = [("ECDHE-RSA-AES128-GCM-SHA256", 128)];
+        let tls13 = [("TLS_AES_256_GCM_SHA384", 256), ("TLS_CHACHA20_POLY1305_SHA256", 256)];
+        let cases: [(&str, Vec<ProtocolSupport>, char); 7] = [
+            ("TLS 1.3 only", protocols(&[], &[], &tls13), 'A'),
+            ("TLS 1.2 and 1.3 with AEAD", protocols(&[], &modern, &tls13), 'A'),
+            ("TLS 1.0 enabled", protocols(&modern, &modern, &tls13), 'B'),
+            ("only TLS 1.0", protocols(&modern, &[], &[]), 'C'),
+            ("3DES", protocols(&[], &[modern[0], ("ECDHE-RSA-DES-CBC3-SHA", 112)], &tls13), 'C'),
+            ("RC4", protocols(&[], &[modern[0], ("RC4-SHA", 128)], &tls13), 'F'),
+            ("export", protocols(&[("EXP-RC4-MD5", 40)], &modern, &tls13), 'F'),
+        ];
+        for (case, protocols, expected) in cases {
+            let (grade, weaknesses) = grade(&protocols);
+            assert_eq!(grade, expected, "{}: {:?}", case, weaknesses);
+            assert_eq!(weaknesses.is_empty(), expected == 'A', "{}: {:?}", case, weaknesses);
+        }
+    }
+
+    #[test]
+    fn a_cipher_accepted_under_several_versions_is_listed_once() {
+        let cbc = [("ECDHE-RSA-AES128-SHA", 128)];
+        let (_, weaknesses) = grade(&protocols(&cbc, &cbc, &[]));
+        assert_eq!(
+            weaknesses,
+            vec![
+                "TLS 1.0 is enabled (deprecated by RFC 8996)",
+                "ECDHE-RSA-AES128-SHA (TLS 1.0, TLS 1.2): CBC mode (prefer AEAD ciphers)",
+            ]
+        );
+    }
+
+    #[test]
+    fn untestable_families_are_known_ones() {
+        let families: Vec<&str> = LEGACY_CIPHER_FAMILIES.iter().map(|(_, family)| *family).collect();
+        for family in untestable_cipher_families() {
+            assert!(families.contains(&family), "{}", family);
+        }
+    }
+
+    #[test]
+    fn untestable_families_are_reported() {
+        let report = TlsScanReport {
+            hostname: "example.com".to_string(),
+            address: "192.0.2.1:443".parse().unwrap(),
+            grade: 'A',
+            protocols: protocols(&[], &[], &[("TLS_AES_256_GCM_SHA384", 256)]),
+            weaknesses: Vec::new(),
+            untestable: vec!["RC4", "export-grade"],
+        };
+        assert!(report
+            .to_string()
+            .contains("Not testable with this TLS library: RC4, export-grade cipher suites"));
+
+        let report = TlsScanReport { untestable: Vec::new(), ..report };
+        assert!(!report.to_string().contains("Not testable"));
+    }
+}
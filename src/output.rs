@@ -0,0 +1,89 @@
+use clap::ValueEnum;
+use color_eyre::eyre::{Result, WrapErr};
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::time::Duration;
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// Prints a diagnostic report either as human-readable text (its `Display`
+/// impl) or as pretty-printed JSON.
+pub fn print<T: Serialize + fmt::Display>(report: &T, format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Text => print!("{}", report),
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(report)
+                .wrap_err("Failed to serialize report to JSON")?;
+            println!("{}", json);
+        }
+    }
+    Ok(())
+}
+
+/// Serializes a `Duration` as fractional milliseconds.
+pub fn serialize_millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
+}
+
+/// Formats a byte count for humans, e.g. `12.3 KB`.
+pub fn format_bytes(bytes: usize) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        1024..=1_048_575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+/// A plain column-aligned table for summaries printed after batch runs.
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(headers: &[&str]) -> Self {
+        Table {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn add_row(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| h.chars().count()).collect();
+        for row in &self.rows {
+            for (i, cell) in row.iter().enumerate() {
+                if let Some(width) = widths.get_mut(i) {
+                    *width = (*width).max(cell.chars().count());
+                }
+            }
+        }
+
+        let write_row = |f: &mut fmt::Formatter<'_>, cells: &[String]| -> fmt::Result {
+            let line: Vec<String> = cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect();
+            writeln!(f, "  {}", line.join("  ").trim_end())
+        };
+
+        write_row(f, &self.headers)?;
+        let separator: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+        write_row(f, &separator)?;
+        for row in &self.rows {
+            write_row(f, row)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+use color_eyre::eyre::{Result, eyre};
+use serde::Serialize;
+use std::fmt;
+use url::Url;
+
+use crate::cli::SitesCommand;
+use crate::config::{Environment, SiteEntry, load_config, save_config};
+use crate::output::{self, OutputFormat, Table};
+
+/// A registered site as shown by `sites list` and `sites show`. The password
+/// itself is never printed.
+#[derive(Serialize)]
+struct SiteView {
+    alias: String,
+    url: String,
+    environment: Option<Environment>,
+    group: Option<String>,
+    tags: Vec<String>,
+    admin_path: Option<String>,
+    username: Option<String>,
+    has_password: bool,
+    api_user: Option<String>,
+    has_app_password: bool,
+}
+
+#[derive(Serialize)]
+#[serde(transparent)]
+struct SiteList(Vec<SiteView>);
+
+impl From<&SiteEntry> for SiteView {
+    fn from(entry: &SiteEntry) -> Self {
+        SiteView {
+            alias: entry.alias.clone(),
+            url: entry.url.clone(),
+            environment: entry.environment,
+            group: entry.group.clone(),
+            tags: entry.tags.clone(),
+            admin_path: entry.admin_path.clone(),
+            username: entry.username.clone(),
+            has_password: entry.password.is_some(),
+            api_user: entry.api_user.clone(),
+            has_app_password: entry.app_password.is_some(),
+        }
+    }
+}
+
+pub fn sites(command: SitesCommand, format: OutputFormat) -> Result<()> {
+    match command {
+        SitesCommand::Add {
+            alias,
+            url,
+            env,
+            group,
+            tags,
+            admin_path,
+            username,
+            password,
+            api_user,
+            app_password,
+        } => add(SiteEntry {
+            alias,
+            url,
+            environment: env,
+            group,
+            tags,
+            admin_path,
+            username,
+            password,
+            api_user,
+            app_password,
+        }),
+        SitesCommand::List => list(format),
+        SitesCommand::Remove { alias } => remove(&alias),
+        SitesCommand::Show { alias } => show(&alias, format),
+    }
+}
+
+fn add(mut entry: SiteEntry) -> Result<()> {
+    // Aliases must not look like hostnames, otherwise they would shadow them.
+    if entry.alias.is_empty() || entry.alias.contains(['.', '/', ':']) {
+        return Err(eyre!(
+            "Invalid alias '{}'. Aliases may not be empty or contain '.', '/' or ':'.",
+            entry.alias
+        ));
+    }
+
+    if !entry.url.contains("://") {
+        entry.url = format!("https://{}", entry.url);
+    }
+    Url::parse(&entry.url).map_err(|e| eyre!("Invalid URL '{}': {}", entry.url, e))?;
+
+    let mut config = load_config()?;
+    let alias = entry.alias.clone();
+
+    match config.sites.iter_mut().find(|existing| existing.alias == alias) {
+        Some(existing) => {
+            *existing = entry;
+            println!("Updated site '{}'.", alias);
+        }
+        None => {
+            config.sites.push(entry);
+            println!("Added site '{}'.", alias);
+        }
+    }
+
+    save_config(&config)
+}
+
+fn list(format: OutputFormat) -> Result<()> {
+    let config = load_config()?;
+    let sites = SiteList(config.sites.iter().map(SiteView::from).collect());
+    output::print(&sites, format)
+}
+
+fn remove(alias: &str) -> Result<()> {
+    let mut config = load_config()?;
+    let before = config.sites.len();
+    config.sites.retain(|entry| entry.alias != alias);
+
+    if config.sites.len() == before {
+        return Err(eyre!("No site registered with alias '{}'", alias));
+    }
+
+    save_config(&config)?;
+    println!("Removed site '{}'.", alias);
+    Ok(())
+}
+
+fn show(alias: &str, format: OutputFormat) -> Result<()> {
+    let config = load_config()?;
+    let entry = config
+        .find_site(alias)
+        .ok_or_else(|| eyre!("No site registered with alias '{}'", alias))?;
+    output::print(&SiteView::from(entry), format)
+}
+
+impl fmt::Display for SiteList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return writeln!(f, "No sites registered. Add one with `wpust sites add <alias> <url>`.");
+        }
+
+        let mut table = Table::new(&["Alias", "URL", "Environment", "Group", "Tags"]);
+        for site in &self.0 {
+            table.add_row(vec![
+                site.alias.clone(),
+                site.url.clone(),
+                site.environment.map(|e| e.to_string()).unwrap_or_default(),
+                site.group.clone().unwrap_or_default(),
+                site.tags.join(", "),
+            ]);
+        }
+        write!(f, "{}", table)
+    }
+}
+
+impl fmt::Display for SiteView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Site: {}\n", self.alias)?;
+        writeln!(f, "  URL:         {}", self.url)?;
+        writeln!(
+            f,
+            "  Environment: {}",
+            self.environment.map(|e| e.to_string()).unwrap_or_else(|| "-".to_string())
+        )?;
+        writeln!(f, "  Group:       {}", self.group.as_deref().unwrap_or("-"))?;
+        writeln!(
+            f,
+            "  Tags:        {}",
+            if self.tags.is_empty() { "-".to_string() } else { self.tags.join(", ") }
+        )?;
+        writeln!(f, "  Admin path:  {}", self.admin_path.as_deref().unwrap_or("(default)"))?;
+        match &self.username {
+            Some(username) => writeln!(
+                f,
+                "  Basic auth:  {} ({})",
+                username,
+                if self.has_password { "password set" } else { "no password" }
+            )?,
+            None => writeln!(f, "  Basic auth:  -")?,
+        }
+        match &self.api_user {
+            Some(user) => writeln!(
+                f,
+                "  REST API:    {} ({})",
+                user,
+                if self.has_app_password { "application password set" } else { "no application password" }
+            ),
+            None => writeln!(f, "  REST API:    -"),
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A UTC calendar date and time, derived from a Unix timestamp.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UtcDateTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+impl UtcDateTime {
+    pub fn from_unix(timestamp: i64) -> Self {
+        let days = timestamp.div_euclid(86_400);
+        let seconds = timestamp.rem_euclid(86_400) as u32;
+
+        // Civil-from-days conversion (Howard Hinnant's algorithm).
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + i64::from(month <= 2);
+
+        UtcDateTime {
+            year,
+            month,
+            day,
+            hour: seconds / 3_600,
+            minute: seconds % 3_600 / 60,
+            second: seconds % 60,
+        }
+    }
+
+    /// `2025-03-20`
+    pub fn date(&self) -> String {
+        format!("{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+
+    /// `2025-03-20T12:00:00Z`
+    pub fn iso8601(&self) -> String {
+        format!(
+            "{}T{:02}:{:02}:{:02}Z",
+            self.date(),
+            self.hour,
+            self.minute,
+            self.second
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(year: i64, month: u32, day: u32, hour: u32, minute: u32, second: u32) -> UtcDateTime {
+        UtcDateTime { year, month, day, hour, minute, second }
+    }
+
+    #[test]
+    fn epoch() {
+        assert_eq!(UtcDateTime::from_unix(0), at(1970, 1, 1, 0, 0, 0));
+        assert_eq!(UtcDateTime::from_unix(0).iso8601(), "1970-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn leap_days() {
+        assert_eq!(UtcDateTime::from_unix(951_782_400), at(2000, 2, 29, 0, 0, 0));
+        assert_eq!(UtcDateTime::from_unix(1_709_251_199), at(2024, 2, 29, 23, 59, 59));
+        assert_eq!(UtcDateTime::from_unix(1_709_251_200), at(2024, 3, 1, 0, 0, 0));
+        // 2100 is not a leap year.
+        assert_eq!(UtcDateTime::from_unix(4_107_542_400).date(), "2100-03-01");
+    }
+
+    #[test]
+    fn negative_timestamps() {
+        assert_eq!(UtcDateTime::from_unix(-1), at(1969, 12, 31, 23, 59, 59));
+        assert_eq!(UtcDateTime::from_unix(-86_400), at(1969, 12, 31, 0, 0, 0));
+        assert_eq!(UtcDateTime::from_unix(-2_208_988_800), at(1900, 1, 1, 0, 0, 0));
+    }
+
+    #[test]
+    fn time_of_day() {
+        assert_eq!(UtcDateTime::from_unix(1_700_000_000).iso8601(), "2023-11-14T22:13:20Z");
+    }
+}
@@ -0,0 +1,86 @@
+/// The contents of every `<name ...>` tag in a document, without the angle
+/// brackets. Tag names match case-insensitively, and `<name>` and `<name/>`
+/// count too. This is a plain text scan, good enough for the tags WordPress
+/// and its plugins print, not a full HTML parser.
+pub fn tags<'a>(html: &'a str, name: &str) -> Vec<&'a str> {
+    html.split('<')
+        .skip(1)
+        .map(|tag| tag.split('>').next().unwrap_or_default())
+        .filter(|tag| {
+            let bytes = tag.as_bytes();
+            bytes.get(..name.len()).is_some_and(|prefix| prefix.eq_ignore_ascii_case(name.as_bytes()))
+                && bytes.get(name.len()).is_none_or(|&next| next.is_ascii_whitespace() || next == b'/')
+        })
+        .collect()
+}
+
+/// The value of an attribute in a tag returned by [`tags`], with `&amp;`
+/// decoded.
+pub fn attribute_value(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut search = 0;
+
+    while let Some(found) = lower[search..].find(name) {
+        let start = search + found;
+        search = start + name.len();
+        // Skip matches inside other attribute names, like data-src.
+        if start > 0 && !lower.as_bytes()[start - 1].is_ascii_whitespace() {
+            continue;
+        }
+        let rest = tag[search..].trim_start();
+        let Some(rest) = rest.strip_prefix('=') else {
+            continue;
+        };
+        let rest = rest.trim_start();
+        let value = match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => rest[1..].split(quote).next(),
+            _ => rest.split_whitespace().next(),
+        };
+        return value.map(|value| value.replace("&amp;", "&"));
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_tags_by_name() {
+        let page = r#"<p><a href="/one">One</a> <abbr title="x">X</abbr> <A HREF='/two'>Two</A></p>"#;
+        assert_eq!(tags(page, "a"), vec![r#"a href="/one""#, "A HREF='/two'"]);
+    }
+
+    #[test]
+    fn matches_self_closing_and_bare_tags() {
+        let page = r#"<br/><link rel="stylesheet" href="/style.css"/><hr>"#;
+        assert_eq!(tags(page, "br"), vec!["br/"]);
+        assert_eq!(tags(page, "hr"), vec!["hr"]);
+        let link = tags(page, "link")[0];
+        assert_eq!(attribute_value(link, "href").as_deref(), Some("/style.css"));
+    }
+
+    #[test]
+    fn non_ascii_text_does_not_panic() {
+        assert!(tags("<p>Preis <€5</p>", "a").is_empty());
+        assert!(tags("<é>", "ab").is_empty());
+        assert_eq!(tags("<a title=\"Café\">", "a"), vec!["a title=\"Café\""]);
+        assert_eq!(attribute_value("a title=\"Café\"", "title").as_deref(), Some("Café"));
+    }
+
+    #[test]
+    fn tag_name_at_the_end_of_input() {
+        assert_eq!(tags("<p>text<a", "a"), vec!["a"]);
+        assert!(tags("<p>text<", "a").is_empty());
+        assert!(tags("<p>text<ab", "abc").is_empty());
+    }
+
+    #[test]
+    fn attribute_values() {
+        let tag = r#"img data-src="/lazy.png" src='/a.png?x=1&amp;y=2' alt=plain"#;
+        assert_eq!(attribute_value(tag, "src").as_deref(), Some("/a.png?x=1&y=2"));
+        assert_eq!(attribute_value(tag, "alt").as_deref(), Some("plain"));
+        assert_eq!(attribute_value(tag, "title"), None);
+    }
+}
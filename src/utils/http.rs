@@ -0,0 +1,35 @@
+use color_eyre::eyre::Result;
+use reqwest::blocking::{Client, ClientBuilder};
+use reqwest::redirect::Policy;
+use std::time::Duration;
+
+/// A blocking HTTP client for fetching pages from sites, with the user agent
+/// and timeout every command shares.
+pub fn http_client() -> Result<Client> {
+    http_client_with(Policy::default())
+}
+
+/// Like [`http_client`], with the given redirect policy, e.g.
+/// `Policy::none()` to see redirects instead of following them.
+pub fn http_client_with(redirect: Policy) -> Result<Client> {
+    Ok(builder(redirect).build()?)
+}
+
+/// Like [`http_client`], but never decompresses: bodies and their
+/// `Content-Length` stay exactly as the server sent them, even if a
+/// dependency enables one of reqwest's decompression features.
+pub fn raw_http_client() -> Result<Client> {
+    Ok(builder(Policy::default())
+        .no_gzip()
+        .no_brotli()
+        .no_deflate()
+        .no_zstd()
+        .build()?)
+}
+
+fn builder(redirect: Policy) -> ClientBuilder {
+    Client::builder()
+        .redirect(redirect)
+        .timeout(Duration::from_secs(15))
+        .user_agent(concat!("wpust/", env!("CARGO_PKG_VERSION")))
+}
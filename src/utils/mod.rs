@@ -1,3 +1,10 @@
+mod date;
+pub mod html;
+mod http;
 mod url;
+mod version;
 
 pub use url::{extract_hostname, extract_root_domain};
+pub use date::{UtcDateTime, unix_now};
+pub use version::compare_versions;
+pub use http::{http_client, http_client_with, raw_http_client};
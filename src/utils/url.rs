@@ -1,7 +1,13 @@
 use color_eyre::eyre::Result;
+use std::net::IpAddr;
 use url::Url;
 
 pub fn extract_hostname(url_str: &str) -> Result<String> {
+    // A bare IPv6 address is not a valid URL host without brackets.
+    if let Ok(ip) = url_str.trim().parse::<IpAddr>() {
+        return Ok(ip.to_string());
+    }
+
     let parsed = Url::parse(url_str)
         .or_else(|_| Url::parse(&format!("https://{}", url_str)))?;
 
@@ -18,3 +24,22 @@ pub fn extract_root_domain(hostname: &str) -> Result<String> {
         Ok(hostname.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_the_host_of_a_url_or_name() {
+        assert_eq!(extract_hostname("https://example.com/blog/").unwrap(), "example.com");
+        assert_eq!(extract_hostname("example.com/blog").unwrap(), "example.com");
+        assert_eq!(extract_hostname("example.com").unwrap(), "example.com");
+    }
+
+    #[test]
+    fn accepts_bare_ip_addresses() {
+        assert_eq!(extract_hostname("192.0.2.1").unwrap(), "192.0.2.1");
+        assert_eq!(extract_hostname("2001:db8::1").unwrap(), "2001:db8::1");
+        assert_eq!(extract_hostname(" 2001:DB8:0::1 ").unwrap(), "2001:db8::1");
+    }
+}
@@ -0,0 +1,84 @@
+use std::cmp::Ordering;
+
+/// Compares dotted version numbers part by part, so `5.10` is newer than
+/// `5.9` and `6.4` equals `6.4.0`. A pre-release suffix sorts before the
+/// release it leads up to, in the order dev, alpha, beta, RC, so
+/// `5.0-beta2` < `5.0-RC1` < `5.0`. Other suffixes, like build metadata or
+/// a `-hotfix`, are ignored.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (a_parts, a_stage) = parse(a);
+    let (b_parts, b_stage) = parse(b);
+
+    (0..a_parts.len().max(b_parts.len()))
+        .map(|i| a_parts.get(i).unwrap_or(&0).cmp(b_parts.get(i).unwrap_or(&0)))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+        .then(a_stage.cmp(&b_stage))
+}
+
+/// How far along the release cycle a version is: the rank of its
+/// pre-release label (a release ranks highest) and the label's number.
+type Stage = (u8, u64);
+
+const RELEASE: Stage = (4, 0);
+
+fn parse(version: &str) -> (Vec<u64>, Stage) {
+    let version = version.trim().trim_start_matches(['v', 'V']);
+    let numbers_end = version
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(version.len());
+    let (numbers, suffix) = version.split_at(numbers_end);
+
+    let parts = numbers
+        .split('.')
+        .filter(|part| !part.is_empty())
+        .map(|part| part.parse().unwrap_or(0))
+        .collect();
+    (parts, stage(suffix))
+}
+
+fn stage(suffix: &str) -> Stage {
+    let suffix = suffix.trim_start_matches(['-', '.', '_']).to_ascii_lowercase();
+    let label: String = suffix.chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+    let rank = match label.as_str() {
+        "dev" | "nightly" => 0,
+        "alpha" | "a" => 1,
+        "beta" | "b" => 2,
+        "rc" | "pre" | "preview" => 3,
+        _ => return RELEASE,
+    };
+    let number: String = suffix[label.len()..]
+        .trim_start_matches(['-', '.', '_'])
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    (rank, number.parse().unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_parts_numerically() {
+        assert_eq!(compare_versions("5.10", "5.9"), Ordering::Greater);
+        assert_eq!(compare_versions("6.4", "6.4.0"), Ordering::Equal);
+        assert_eq!(compare_versions("v1.2.3", "1.2.4"), Ordering::Less);
+    }
+
+    #[test]
+    fn pre_releases_come_before_the_release() {
+        assert_eq!(compare_versions("5.0-RC1", "5.0"), Ordering::Less);
+        assert_eq!(compare_versions("5.0-beta2", "5.0-RC1"), Ordering::Less);
+        assert_eq!(compare_versions("5.0-alpha-45000", "5.0-beta1"), Ordering::Less);
+        assert_eq!(compare_versions("5.0-RC2", "5.0-rc1"), Ordering::Greater);
+        assert_eq!(compare_versions("2.0beta1", "2.0"), Ordering::Less);
+        assert_eq!(compare_versions("5.0-RC1", "4.9.9"), Ordering::Greater);
+    }
+
+    #[test]
+    fn other_suffixes_are_ignored() {
+        assert_eq!(compare_versions("1.2.3-hotfix", "1.2.3"), Ordering::Equal);
+        assert_eq!(compare_versions("1.2.3+build.7", "1.2.3"), Ordering::Equal);
+    }
+}
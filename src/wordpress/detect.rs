@@ -0,0 +1,438 @@
+use color_eyre::eyre::{Result, eyre};
+use reqwest::blocking::Client;
+use serde::Serialize;
+use std::fmt;
+use url::Url;
+
+use crate::batch::Summary;
+use crate::config::{Config, load_config};
+use crate::utils::{html, http_client};
+use crate::wordpress::utils::site_get;
+
+/// Evidence weights add up to a score; this much or more means WordPress.
+const WORDPRESS_THRESHOLD: u32 = 40;
+const HIGH_CONFIDENCE: u32 = 80;
+
+/// Core assets that carry the WordPress version in `?ver=`. Bundled
+/// libraries such as jQuery under /wp-includes/js/ carry their own version.
+const CORE_VERSIONED_ASSETS: [&str; 5] = [
+    "/wp-includes/css/",
+    "/wp-includes/js/wp-emoji-release.min.js",
+    "/wp-includes/js/wp-embed.min.js",
+    "/wp-includes/js/comment-reply.min.js",
+    "/wp-includes/js/admin-bar.min.js",
+];
+
+#[derive(Serialize)]
+pub struct DetectReport {
+    pub hostname: String,
+    pub is_wordpress: bool,
+    pub confidence: Confidence,
+    pub version: Option<String>,
+    pub evidence: Vec<Evidence>,
+}
+
+#[derive(Serialize)]
+pub struct Evidence {
+    pub source: &'static str,
+    pub detail: String,
+    pub version: Option<String>,
+    #[serde(skip)]
+    weight: u32,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Confidence {
+    None,
+    Low,
+    Medium,
+    High,
+}
+
+/// Decides whether a site runs WordPress, and which core version, from what
+/// it exposes publicly: the generator meta tag, core asset versions, REST API
+/// discovery links, the feed generator and `readme.html`.
+pub fn detect_wordpress(hostname: &str) -> Result<DetectReport> {
+    let config = load_config()?;
+    let client = http_client()?;
+
+    let response = site_get(&client, &config, hostname, "/")
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| eyre!("HTTP request failed: {}", e))?;
+    let base = response.url().clone();
+    let link_header: Vec<String> = response
+        .headers()
+        .get_all("link")
+        .iter()
+        .filter_map(|value| value.to_str().ok().map(str::to_string))
+        .collect();
+    let page = response.text().map_err(|e| eyre!("Failed to read response: {}", e))?;
+
+    let mut evidence = Vec::new();
+    home_page_evidence(&base, &page, &link_header, &mut evidence);
+    feed_evidence(&client, &config, hostname, &mut evidence);
+    readme_evidence(&client, &config, hostname, &mut evidence);
+
+    let score: u32 = evidence.iter().map(|e| e.weight).sum();
+
+    Ok(DetectReport {
+        hostname: hostname.to_string(),
+        is_wordpress: score >= WORDPRESS_THRESHOLD,
+        confidence: confidence(score),
+        version: consensus_version(&evidence),
+        evidence,
+    })
+}
+
+fn confidence(score: u32) -> Confidence {
+    match score {
+        0 => Confidence::None,
+        s if s < WORDPRESS_THRESHOLD => Confidence::Low,
+        s if s < HIGH_CONFIDENCE => Confidence::Medium,
+        _ => Confidence::High,
+    }
+}
+
+fn home_page_evidence(base: &Url, page: &str, link_header: &[String], evidence: &mut Vec<Evidence>) {
+    for tag in html::tags(page, "meta") {
+        let is_generator = html::attribute_value(tag, "name").is_some_and(|name| name.eq_ignore_ascii_case("generator"));
+        let Some(content) = html::attribute_value(tag, "content").filter(|_| is_generator) else {
+            continue;
+        };
+        if let Some(rest) = content.strip_prefix("WordPress") {
+            evidence.push(Evidence {
+                source: "generator meta tag",
+                version: version_prefix(rest.trim()),
+                detail: content.clone(),
+                weight: 40,
+            });
+        }
+    }
+
+    if link_header.iter().any(|link| link.contains("api.w.org")) {
+        evidence.push(Evidence {
+            source: "REST API Link header",
+            detail: "Link: rel=\"https://api.w.org/\"".to_string(),
+            version: None,
+            weight: 40,
+        });
+    } else if let Some(href) = html::tags(page, "link")
+        .into_iter()
+        .filter(|tag| html::attribute_value(tag, "rel").is_some_and(|rel| rel.contains("api.w.org")))
+        .find_map(|tag| html::attribute_value(tag, "href"))
+    {
+        evidence.push(Evidence {
+            source: "REST API discovery link",
+            detail: href,
+            version: None,
+            weight: 40,
+        });
+    }
+
+    let assets: Vec<Url> = html::tags(page, "script")
+        .into_iter()
+        .filter_map(|tag| html::attribute_value(tag, "src"))
+        .chain(html::tags(page, "link").into_iter().filter_map(|tag| html::attribute_value(tag, "href")))
+        .filter_map(|value| base.join(&value).ok())
+        .collect();
+
+    let mut core_versions: Vec<(String, String)> = Vec::new();
+    for asset in assets.iter().filter(|url| CORE_VERSIONED_ASSETS.iter().any(|core| url.path().contains(core))) {
+        let version = asset
+            .query_pairs()
+            .find(|(key, _)| key == "ver")
+            .and_then(|(_, value)| version_prefix(&value));
+        if let Some(version) = version {
+            if !core_versions.iter().any(|(seen, _)| *seen == version) {
+                core_versions.push((version, asset.path().to_string()));
+            }
+        }
+    }
+    for (version, path) in core_versions {
+        evidence.push(Evidence {
+            source: "core asset ?ver=",
+            detail: format!("{}?ver={}", path, version),
+            version: Some(version),
+            weight: 10,
+        });
+    }
+
+    if page.contains("/wp-content/") || page.contains("/wp-includes/") {
+        evidence.push(Evidence {
+            source: "page content",
+            detail: "references /wp-content/ or /wp-includes/".to_string(),
+            version: None,
+            weight: 30,
+        });
+    }
+}
+
+fn feed_evidence(client: &Client, config: &Config, hostname: &str, evidence: &mut Vec<Evidence>) {
+    let Some(feed) = fetch_text(client, config, hostname, "/feed/") else {
+        return;
+    };
+    let Some(generator) = feed
+        .split("<generator>")
+        .nth(1)
+        .and_then(|rest| rest.split("</generator>").next())
+    else {
+        return;
+    };
+
+    if let Some(version) = generator.split_once("wordpress.org/?v=").map(|(_, v)| v) {
+        evidence.push(Evidence {
+            source: "feed generator",
+            detail: generator.trim().to_string(),
+            version: version_prefix(version),
+            weight: 40,
+        });
+    }
+}
+
+fn readme_evidence(client: &Client, config: &Config, hostname: &str, evidence: &mut Vec<Evidence>) {
+    let Some(readme) = fetch_text(client, config, hostname, "/readme.html") else {
+        return;
+    };
+    if !readme.contains("WordPress") {
+        return;
+    }
+
+    // Older releases print "Version 4.9.8" below the logo.
+    let version = readme
+        .split("Version ")
+        .skip(1)
+        .find_map(version_prefix);
+    evidence.push(Evidence {
+        source: "readme.html",
+        detail: "/readme.html is publicly accessible".to_string(),
+        version,
+        weight: 20,
+    });
+}
+
+fn fetch_text(client: &Client, config: &Config, hostname: &str, path: &str) -> Option<String> {
+    site_get(client, config, hostname, path)
+        .ok()
+        .filter(|response| response.status().is_success())?
+        .text()
+        .ok()
+}
+
+/// The leading version number of `text`, like `6.4.2` in `6.4.2-beta`.
+fn version_prefix(text: &str) -> Option<String> {
+    let version: String = text.chars().take_while(|c| c.is_ascii_digit() || *c == '.').collect();
+    let version = version.trim_end_matches('.');
+    (version.contains('.') && version.starts_with(|c: char| c.is_ascii_digit())).then(|| version.to_string())
+}
+
+/// The version reported by the most sources; ties go to the more reliable
+/// source, which comes first in the evidence list.
+fn consensus_version(evidence: &[Evidence]) -> Option<String> {
+    let versions: Vec<&String> = evidence.iter().filter_map(|e| e.version.as_ref()).collect();
+    versions
+        .iter()
+        .max_by_key(|candidate| {
+            let count = versions.iter().filter(|v| v == candidate).count();
+            let first = versions.iter().position(|v| v == *candidate).unwrap_or_default();
+            (count, std::cmp::Reverse(first))
+        })
+        .map(|version| version.to_string())
+}
+
+impl fmt::Display for Confidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Confidence::None => "none",
+            Confidence::Low => "low",
+            Confidence::Medium => "medium",
+            Confidence::High => "high",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+impl fmt::Display for DetectReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "WordPress detection for: {}\n", self.hostname)?;
+
+        if self.is_wordpress {
+            writeln!(f, "✓ WordPress detected (confidence: {})", self.confidence)?;
+            match &self.version {
+                Some(version) => writeln!(f, "Core version: {}", version)?,
+                None => writeln!(f, "Core version: unknown (hidden)")?,
+            }
+        } else {
+            writeln!(f, "✗ Not detected as WordPress (confidence: {})", self.confidence)?;
+        }
+
+        let mut versions: Vec<&String> = self.evidence.iter().filter_map(|e| e.version.as_ref()).collect();
+        versions.sort();
+        versions.dedup();
+        if versions.len() > 1 {
+            writeln!(f, "⚠️  Sources disagree on the version; asset versions can be stale or set by plugins")?;
+        }
+
+        if self.evidence.is_empty() {
+            return writeln!(f, "\nNo WordPress evidence found.");
+        }
+        writeln!(f, "\nEvidence:")?;
+        for evidence in &self.evidence {
+            match &evidence.version {
+                Some(version) => writeln!(f, "  - {}: {} (version {})", evidence.source, evidence.detail, version)?,
+                None => writeln!(f, "  - {}: {}", evidence.source, evidence.detail)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Summary for DetectReport {
+    fn summary(&self) -> String {
+        if self.is_wordpress {
+            format!(
+                "WordPress {} ({} confidence)",
+                self.version.as_deref().unwrap_or("(version hidden)"),
+                self.confidence
+            )
+        } else {
+            format!("not WordPress ({} confidence)", self.confidence)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evidence(page: &str, link_header: &[&str]) -> Vec<Evidence> {
+        let base = Url::parse("https://example.com/").unwrap();
+        let link_header: Vec<String> = link_header.iter().map(|link| link.to_string()).collect();
+        let mut evidence = Vec::new();
+        home_page_evidence(&base, page, &link_header, &mut evidence);
+        evidence
+    }
+
+    fn versioned(version: &str) -> Evidence {
+        Evidence {
+            source: "test",
+            detail: String::new(),
+            version: Some(version.to_string()),
+            weight: 10,
+        }
+    }
+
+    #[test]
+    fn version_prefix_takes_the_leading_number() {
+        assert_eq!(version_prefix("6.4.2-beta").as_deref(), Some("6.4.2"));
+        assert_eq!(version_prefix("6.4.2").as_deref(), Some("6.4.2"));
+        assert_eq!(version_prefix("6.4.").as_deref(), Some("6.4"));
+        assert_eq!(version_prefix("6."), None);
+        assert_eq!(version_prefix("6"), None);
+        assert_eq!(version_prefix(".6.4"), None);
+        assert_eq!(version_prefix("abc"), None);
+        assert_eq!(version_prefix(""), None);
+    }
+
+    #[test]
+    fn consensus_prefers_the_most_reported_version() {
+        let evidence = [versioned("6.3"), versioned("6.4.2"), versioned("6.4.2")];
+        assert_eq!(consensus_version(&evidence).as_deref(), Some("6.4.2"));
+    }
+
+    #[test]
+    fn consensus_tie_goes_to_the_earlier_source() {
+        let evidence = [versioned("6.4.2"), versioned("6.3"), versioned("6.3"), versioned("6.4.2")];
+        assert_eq!(consensus_version(&evidence).as_deref(), Some("6.4.2"));
+        let evidence = [versioned("6.3"), versioned("6.4.2")];
+        assert_eq!(consensus_version(&evidence).as_deref(), Some("6.3"));
+    }
+
+    #[test]
+    fn consensus_without_versions() {
+        let mut unversioned = versioned("6.4");
+        unversioned.version = None;
+        assert_eq!(consensus_version(&[unversioned]), None);
+        assert_eq!(consensus_version(&[]), None);
+    }
+
+    #[test]
+    fn confidence_thresholds() {
+        assert_eq!(confidence(0), Confidence::None);
+        assert_eq!(confidence(10), Confidence::Low);
+        assert_eq!(confidence(WORDPRESS_THRESHOLD - 1), Confidence::Low);
+        assert_eq!(confidence(WORDPRESS_THRESHOLD), Confidence::Medium);
+        assert_eq!(confidence(HIGH_CONFIDENCE - 1), Confidence::Medium);
+        assert_eq!(confidence(HIGH_CONFIDENCE), Confidence::High);
+        assert_eq!(confidence(200), Confidence::High);
+    }
+
+    #[test]
+    fn generator_meta_tag() {
+        let evidence = evidence(r#"<meta name="Generator" content="WordPress 6.4.2-alpha">"#, &[]);
+        assert_eq!(evidence.len(), 1);
+        assert_eq!(evidence[0].source, "generator meta tag");
+        assert_eq!(evidence[0].detail, "WordPress 6.4.2-alpha");
+        assert_eq!(evidence[0].version.as_deref(), Some("6.4.2"));
+        assert_eq!(evidence[0].weight, 40);
+    }
+
+    #[test]
+    fn other_generators_are_ignored() {
+        assert!(evidence(r#"<meta name="generator" content="Hugo 0.120">"#, &[]).is_empty());
+        assert!(evidence(r#"<meta name="description" content="WordPress 6.4">"#, &[]).is_empty());
+    }
+
+    #[test]
+    fn link_header_wins_over_the_link_tag() {
+        let page = r#"<link rel="https://api.w.org/" href="https://example.com/wp-json/">"#;
+        let evidence = evidence(page, &["<https://example.com/wp-json/>; rel=\"https://api.w.org/\""]);
+        assert_eq!(evidence.len(), 1);
+        assert_eq!(evidence[0].source, "REST API Link header");
+    }
+
+    #[test]
+    fn link_tag_is_the_fallback() {
+        let page = r#"<link rel="https://api.w.org/" href="https://example.com/wp-json/">"#;
+        let evidence = evidence(page, &["<https://example.com/>; rel=shortlink"]);
+        assert_eq!(evidence.len(), 1);
+        assert_eq!(evidence[0].source, "REST API discovery link");
+        assert_eq!(evidence[0].detail, "https://example.com/wp-json/");
+    }
+
+    #[test]
+    fn only_core_assets_carry_the_version() {
+        let page = r#"
+            <script src="/wp-includes/js/jquery/jquery.min.js?ver=3.7.1"></script>
+            <script src="/wp-includes/js/wp-emoji-release.min.js?ver=6.4.2"></script>
+            <link rel="stylesheet" href="/wp-includes/css/dist/block-library/style.min.css?ver=6.4.2">
+            <link rel="stylesheet" href="/wp-content/plugins/foo/style.css?ver=1.2.3">
+        "#;
+        let evidence = evidence(page, &[]);
+        let assets: Vec<&Evidence> = evidence.iter().filter(|e| e.source == "core asset ?ver=").collect();
+        assert_eq!(assets.len(), 1);
+        assert_eq!(assets[0].version.as_deref(), Some("6.4.2"));
+        assert_eq!(assets[0].detail, "/wp-includes/js/wp-emoji-release.min.js?ver=6.4.2");
+        assert!(evidence.iter().any(|e| e.source == "page content"));
+    }
+
+    #[test]
+    fn unrelated_page_has_no_evidence() {
+        assert!(evidence("<html><body><p>Hello</p></body></html>", &[]).is_empty());
+    }
+
+    #[test]
+    fn version_line_only_for_wordpress() {
+        let report = DetectReport {
+            hostname: "example.com".to_string(),
+            is_wordpress: false,
+            confidence: Confidence::Low,
+            version: None,
+            evidence: Vec::new(),
+        };
+        assert!(!report.to_string().contains("Core version"));
+
+        let report = DetectReport { is_wordpress: true, ..report };
+        assert!(report.to_string().contains("Core version: unknown (hidden)"));
+    }
+}
@@ -0,0 +1,389 @@
+use color_eyre::eyre::{Result, eyre};
+use serde::Serialize;
+use std::fmt;
+use url::Url;
+
+use crate::batch::Summary;
+use crate::config::load_config;
+use crate::network::engine::{DEFAULT_CONCURRENCY, run_bounded};
+use crate::output::Table;
+use crate::utils::{html, http_client};
+use crate::wordpress::utils::site_get;
+
+#[derive(Serialize)]
+pub struct InventoryReport {
+    pub hostname: String,
+    pub pages_scanned: Vec<String>,
+    pub plugins: Vec<Component>,
+    pub themes: Vec<Component>,
+}
+
+#[derive(Serialize)]
+pub struct Component {
+    pub slug: String,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub version_source: Option<&'static str>,
+    /// For child themes, the slug of the parent theme.
+    pub parent: Option<String>,
+    #[serde(skip)]
+    asset_version: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Plugin,
+    Theme,
+}
+
+/// Lists the plugins and themes a site references in its front end, and
+/// reads their versions from the public `readme.txt` and `style.css` files.
+/// Besides the home page, up to `extra_pages` linked pages on the same host
+/// are scanned.
+pub fn inventory(hostname: &str, extra_pages: usize) -> Result<InventoryReport> {
+    let config = load_config()?;
+    let client = http_client()?;
+
+    let home = site_get(&client, &config, hostname, "/")
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| eyre!("HTTP request failed: {}", e))?;
+    let base = home.url().clone();
+    let home = home.text().map_err(|e| eyre!("Failed to read response: {}", e))?;
+
+    let mut pages_scanned = vec![base.to_string()];
+    let mut references = Vec::new();
+    find_references(&home, &mut references);
+
+    for link in internal_links(&base, &home).into_iter().take(extra_pages) {
+        let path = match link.query() {
+            Some(query) => format!("{}?{}", link.path(), query),
+            None => link.path().to_string(),
+        };
+        let Ok(response) = site_get(&client, &config, hostname, &path) else {
+            continue;
+        };
+        if let Ok(page) = response.error_for_status().and_then(|r| r.text()) {
+            find_references(&page, &mut references);
+            pages_scanned.push(link.to_string());
+        }
+    }
+
+    let components = run_bounded(references, DEFAULT_CONCURRENCY, |(kind, slug, asset_version)| {
+        let path = match kind {
+            Kind::Plugin => format!("/wp-content/plugins/{}/readme.txt", slug),
+            Kind::Theme => format!("/wp-content/themes/{}/style.css", slug),
+        };
+        let text = site_get(&client, &config, hostname, &path)
+            .ok()
+            .filter(|response| response.status().is_success())
+            .and_then(|response| response.text().ok());
+        (kind, read_component(kind, slug, asset_version, text.as_deref()))
+    });
+
+    let (plugins, themes): (Vec<_>, Vec<_>) = components.into_iter().partition(|(kind, _)| *kind == Kind::Plugin);
+
+    Ok(InventoryReport {
+        hostname: hostname.to_string(),
+        pages_scanned,
+        plugins: plugins.into_iter().map(|(_, component)| component).collect(),
+        themes: themes.into_iter().map(|(_, component)| component).collect(),
+    })
+}
+
+/// Collects every `/wp-content/plugins/<slug>/` and `/wp-content/themes/<slug>/`
+/// reference, with the `?ver=` of the first asset that had one.
+fn find_references(page: &str, references: &mut Vec<(Kind, String, Option<String>)>) {
+    // Inline JSON escapes slashes.
+    let page = page.replace("\\/", "/");
+
+    for (kind, marker) in [(Kind::Plugin, "/wp-content/plugins/"), (Kind::Theme, "/wp-content/themes/")] {
+        for (index, _) in page.match_indices(marker) {
+            let reference = page[index + marker.len()..]
+                .split(|c: char| c == '"' || c == '\'' || c == ')' || c == '<' || c.is_whitespace())
+                .next()
+                .unwrap_or_default();
+            let Some((slug, _)) = reference.split_once('/') else {
+                continue;
+            };
+            if slug.is_empty() || !slug.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')) {
+                continue;
+            }
+            let version = reference
+                .split_once('?')
+                .and_then(|(_, query)| query.split('&').find_map(|pair| pair.strip_prefix("ver=")))
+                .map(str::to_string);
+
+            match references.iter_mut().find(|(k, s, _)| *k == kind && s == slug) {
+                Some((_, _, existing)) => {
+                    if existing.is_none() {
+                        *existing = version;
+                    }
+                }
+                None => references.push((kind, slug.to_string(), version)),
+            }
+        }
+    }
+}
+
+/// Links to other HTML pages on the same host, in page order.
+fn internal_links(base: &Url, page: &str) -> Vec<Url> {
+    let mut links: Vec<Url> = Vec::new();
+    for href in html::tags(page, "a").into_iter().filter_map(|tag| html::attribute_value(tag, "href")) {
+        let Ok(mut url) = base.join(&href) else {
+            continue;
+        };
+        url.set_fragment(None);
+        let path = url.path();
+        let is_page = !path.contains("/wp-admin") && !path.contains("/wp-content/") && !path.contains("/feed");
+        let is_file = path.rsplit('/').next().is_some_and(|last| last.contains('.') && !last.ends_with(".html"));
+        if url.host() == base.host() && url != *base && is_page && !is_file && !links.contains(&url) {
+            links.push(url);
+        }
+    }
+    links
+}
+
+fn read_component(kind: Kind, slug: String, asset_version: Option<String>, text: Option<&str>) -> Component {
+    let mut component = Component {
+        slug,
+        name: None,
+        version: None,
+        version_source: None,
+        parent: None,
+        asset_version,
+    };
+
+    if let Some(text) = text {
+        match kind {
+            Kind::Plugin => {
+                // readme.txt starts with "=== Plugin Name ===".
+                component.name = text
+                    .lines()
+                    .next()
+                    .filter(|line| line.starts_with("==="))
+                    .map(|line| line.trim().trim_matches('=').trim().to_string())
+                    .filter(|name| !name.is_empty());
+                component.version = header_field(text, "Stable tag").filter(|tag| tag != "trunk");
+                component.version_source = component.version.as_ref().map(|_| "readme.txt Stable tag");
+            }
+            Kind::Theme => {
+                component.name = header_field(text, "Theme Name");
+                component.version = header_field(text, "Version");
+                component.version_source = component.version.as_ref().map(|_| "style.css Version");
+                component.parent = header_field(text, "Template");
+            }
+        }
+    }
+
+    if component.version.is_none() {
+        component.version = component.asset_version.clone();
+        component.version_source = component.version.as_ref().map(|_| "asset ?ver=");
+    }
+
+    component
+}
+
+/// The value of a `Field: value` header line in readme.txt or style.css.
+fn header_field(text: &str, field: &str) -> Option<String> {
+    text.lines()
+        .map(|line| line.trim().trim_start_matches('*').trim())
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case(field))
+        .map(|(_, value)| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+impl fmt::Display for InventoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Inventory for: {}", self.hostname)?;
+        writeln!(f, "Pages scanned: {}\n", self.pages_scanned.len())?;
+
+        for (title, components) in [("Plugins", &self.plugins), ("Themes", &self.themes)] {
+            writeln!(f, "{} ({}):", title, components.len())?;
+            if components.is_empty() {
+                writeln!(f, "  none found\n")?;
+                continue;
+            }
+            let mut table = Table::new(&["Slug", "Name", "Version", "Source"]);
+            for component in components {
+                let name = match &component.parent {
+                    Some(parent) => format!(
+                        "{} (child of {})",
+                        component.name.as_deref().unwrap_or("-"),
+                        parent
+                    ),
+                    None => component.name.clone().unwrap_or_else(|| "-".to_string()),
+                };
+                table.add_row(vec![
+                    component.slug.clone(),
+                    name,
+                    component.version.clone().unwrap_or_else(|| "unknown".to_string()),
+                    component.version_source.unwrap_or("-").to_string(),
+                ]);
+            }
+            writeln!(f, "{}", table)?;
+        }
+
+        writeln!(
+            f,
+            "Only components that leave a trace in the front end are found; inactive or back-end-only plugins are not."
+        )
+    }
+}
+
+impl Summary for InventoryReport {
+    fn summary(&self) -> String {
+        let unknown = self
+            .plugins
+            .iter()
+            .chain(&self.themes)
+            .filter(|component| component.version.is_none())
+            .count();
+        format!(
+            "{} plugins, {} themes, {} without version",
+            self.plugins.len(),
+            self.themes.len(),
+            unknown
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn references(page: &str) -> Vec<(Kind, String, Option<String>)> {
+        let mut references = Vec::new();
+        find_references(page, &mut references);
+        references
+    }
+
+    fn reference(kind: Kind, slug: &str, version: Option<&str>) -> (Kind, String, Option<String>) {
+        (kind, slug.to_string(), version.map(str::to_string))
+    }
+
+    fn links(page: &str) -> Vec<String> {
+        let base = Url::parse("https://example.com/").unwrap();
+        internal_links(&base, page).into_iter().map(|url| url.to_string()).collect()
+    }
+
+    #[test]
+    fn finds_plugins_and_themes() {
+        let page = r#"
+            <link rel="stylesheet" href="https://example.com/wp-content/themes/astra/style.css?ver=4.5.0">
+            <script src="/wp-content/plugins/contact-form-7/includes/js/index.js?ver=5.8.4"></script>
+        "#;
+        assert_eq!(
+            references(page),
+            vec![
+                reference(Kind::Plugin, "contact-form-7", Some("5.8.4")),
+                reference(Kind::Theme, "astra", Some("4.5.0")),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_escaped_json() {
+        let page = r#"<script>var data = {"url":"https:\/\/example.com\/wp-content\/plugins\/woocommerce\/assets\/"};</script>"#;
+        assert_eq!(references(page), vec![reference(Kind::Plugin, "woocommerce", None)]);
+    }
+
+    #[test]
+    fn deduplicates_and_keeps_the_first_version() {
+        let page = r#"
+            <img src="/wp-content/plugins/foo/logo.png">
+            <script src="/wp-content/plugins/foo/a.js?ver=1.0"></script>
+            <script src="/wp-content/plugins/foo/b.js?ver=2.0"></script>
+        "#;
+        assert_eq!(references(page), vec![reference(Kind::Plugin, "foo", Some("1.0"))]);
+    }
+
+    #[test]
+    fn version_can_follow_other_query_parameters() {
+        let page = r#"<script src="/wp-content/plugins/foo/a.js?x=1&ver=3.2"></script>"#;
+        assert_eq!(references(page), vec![reference(Kind::Plugin, "foo", Some("3.2"))]);
+    }
+
+    #[test]
+    fn rejects_bad_slugs() {
+        let page = r#"
+            <a href="/wp-content/plugins/">plugins</a>
+            <a href="/wp-content/plugins//x.js">empty</a>
+            <a href="/wp-content/plugins/%3Cscript%3E/x.js">encoded</a>
+            <a href="/wp-content/themes/bad*slug/style.css">star</a>
+            <a href="/wp-content/plugins/no-trailing-slash">bare</a>
+        "#;
+        assert!(references(page).is_empty());
+    }
+
+    #[test]
+    fn internal_links_stay_on_the_host() {
+        let page = r#"
+            <a href="/about/">About</a>
+            <a href="https://example.com/contact/#form">Contact</a>
+            <a href="https://other.example/">Elsewhere</a>
+            <a href="/about/#team">About again</a>
+            <a href="/">Home</a>
+        "#;
+        assert_eq!(links(page), vec!["https://example.com/about/", "https://example.com/contact/"]);
+    }
+
+    #[test]
+    fn internal_links_skip_files_feeds_and_admin() {
+        let page = r#"
+            <a href="/wp-admin/">Admin</a>
+            <a href="/feed/">Feed</a>
+            <a href="/wp-content/uploads/report.pdf">Report</a>
+            <a href="/brochure.pdf">Brochure</a>
+            <a href="/page.html">Page</a>
+        "#;
+        assert_eq!(links(page), vec!["https://example.com/page.html"]);
+    }
+
+    #[test]
+    fn header_field_reads_readme_and_style_headers() {
+        let style = "/*\n * Theme Name: Twenty Child\n * Template: twentytwentyfour\n * Version:\n */";
+        assert_eq!(header_field(style, "theme name").as_deref(), Some("Twenty Child"));
+        assert_eq!(header_field(style, "Template").as_deref(), Some("twentytwentyfour"));
+        assert_eq!(header_field(style, "Version"), None);
+        assert_eq!(header_field(style, "Author"), None);
+
+        let readme = "=== Foo ===\nStable tag: 1.2.3\nTested up to: 6.4";
+        assert_eq!(header_field(readme, "Stable tag").as_deref(), Some("1.2.3"));
+    }
+
+    #[test]
+    fn plugin_version_from_the_stable_tag() {
+        let readme = "=== Contact Form 7 ===\nContributors: takayukister\nStable tag: 5.8.4\n";
+        let component = read_component(Kind::Plugin, "contact-form-7".to_string(), Some("5.8".to_string()), Some(readme));
+        assert_eq!(component.name.as_deref(), Some("Contact Form 7"));
+        assert_eq!(component.version.as_deref(), Some("5.8.4"));
+        assert_eq!(component.version_source, Some("readme.txt Stable tag"));
+    }
+
+    #[test]
+    fn trunk_stable_tag_falls_back_to_the_asset_version() {
+        let readme = "=== Foo ===\nStable tag: trunk\n";
+        let component = read_component(Kind::Plugin, "foo".to_string(), Some("1.4".to_string()), Some(readme));
+        assert_eq!(component.version.as_deref(), Some("1.4"));
+        assert_eq!(component.version_source, Some("asset ?ver="));
+    }
+
+    #[test]
+    fn missing_file_and_asset_version_leave_the_version_unknown() {
+        let component = read_component(Kind::Plugin, "foo".to_string(), None, None);
+        assert_eq!(component.name, None);
+        assert_eq!(component.version, None);
+        assert_eq!(component.version_source, None);
+    }
+
+    #[test]
+    fn child_theme_names_its_parent() {
+        let style = "/*\nTheme Name: Astra Child\nTemplate: astra\nVersion: 1.0.0\n*/";
+        let component = read_component(Kind::Theme, "astra-child".to_string(), None, Some(style));
+        assert_eq!(component.name.as_deref(), Some("Astra Child"));
+        assert_eq!(component.version.as_deref(), Some("1.0.0"));
+        assert_eq!(component.version_source, Some("style.css Version"));
+        assert_eq!(component.parent.as_deref(), Some("astra"));
+    }
+}
@@ -1,9 +1,28 @@
+mod detect;
+mod inventory;
+mod plugins;
+mod rest;
+mod themes;
 mod utils;
+mod vulndb;
+mod vulns;
+mod wporg;
 
 use color_eyre::eyre::{Result, eyre};
+use reqwest::Method;
+use serde_json::Value;
+use crate::cli::ApiMethod;
 use crate::config::load_config;
+use crate::wordpress::rest::RestClient;
 use crate::wordpress::utils::prepare_wordpress_url;
 
+pub use detect::detect_wordpress;
+pub use inventory::inventory;
+pub use plugins::{list_plugins, set_plugin_status};
+pub use themes::list_themes;
+pub use vulndb::load_vuln_db;
+pub use vulns::{scan_vulnerabilities, update_vulnerabilities};
+
 fn open_wordpress_admin_page(
     site: String,
     page: &str,
@@ -15,7 +34,21 @@ fn open_wordpress_admin_page(
     }
 
     let config = load_config()?;
-    let admin_path = config.wp_admin_path.as_deref().unwrap_or("wp-admin");
+    let entry = config.find_site(&site);
+
+    // A registered site's own settings take precedence over the global ones,
+    // and credentials given on the command line take precedence over both.
+    let admin_path = entry
+        .and_then(|e| e.admin_path.as_deref())
+        .or(config.wp_admin_path.as_deref())
+        .unwrap_or("wp-admin");
+    let (username, password) = match (username, password, entry) {
+        (Some(user), Some(pass), _) => (Some(user), Some(pass)),
+        (_, _, Some(e)) => (e.username.clone(), e.password.clone()),
+        _ => (None, None),
+    };
+    let site = entry.map(|e| e.address().to_string()).unwrap_or(site);
+
     let prepared_url = prepare_wordpress_url(
         site,
         &config,
@@ -30,7 +63,7 @@ fn open_wordpress_admin_page(
         Some(browser) => open::with(&url, browser),
     };
 
-    if let Err(_) = open_result {
+    if open_result.is_err() {
         return Err(eyre!(
             "Failed to open URL in browser. \
              Is a default browser configured?\n\
@@ -57,3 +90,23 @@ pub fn plugins(site: String, username: Option<String>, password: Option<String>)
 pub fn site_health(site: String, username: Option<String>, password: Option<String>) -> Result<()> {
     open_wordpress_admin_page(site, "site-health.php?tab=debug", username, password)
 }
+
+/// Sends a raw request to the site's REST API and prints the JSON response.
+pub fn api(site: String, route: String, method: ApiMethod, data: Option<String>) -> Result<()> {
+    let client = RestClient::for_site(&site)?;
+    let body: Option<Value> = data
+        .map(|data| serde_json::from_str(&data).map_err(|e| eyre!("--data is not valid JSON: {}", e)))
+        .transpose()?;
+
+    let method = match method {
+        ApiMethod::Get => Method::GET,
+        ApiMethod::Post => Method::POST,
+        ApiMethod::Put => Method::PUT,
+        ApiMethod::Patch => Method::PATCH,
+        ApiMethod::Delete => Method::DELETE,
+    };
+
+    let response: Value = client.request(method, &route, body.as_ref())?;
+    println!("{}", serde_json::to_string_pretty(&response)?);
+    Ok(())
+}
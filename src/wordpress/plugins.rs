@@ -0,0 +1,149 @@
+use color_eyre::eyre::{Result, eyre};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fmt;
+
+use crate::config::load_config;
+use crate::network::engine::{DEFAULT_CONCURRENCY, run_bounded};
+use crate::output::Table;
+use crate::wordpress::rest::RestClient;
+use crate::wordpress::wporg::{ComponentKind, Directory, DirectoryInfo, write_maintenance_notes};
+
+#[derive(Serialize)]
+pub struct PluginsReport {
+    pub site: String,
+    pub plugins: Vec<InstalledPlugin>,
+}
+
+#[derive(Serialize)]
+pub struct InstalledPlugin {
+    /// The plugin file without `.php`, e.g. `akismet/akismet`.
+    pub plugin: String,
+    pub name: String,
+    pub version: String,
+    pub status: String,
+    /// Only known when the site includes it in the REST response.
+    pub auto_update: Option<bool>,
+    pub directory: DirectoryInfo,
+}
+
+/// A plugin as returned by the `wp/v2/plugins` endpoint.
+#[derive(Deserialize)]
+struct RestPlugin {
+    plugin: String,
+    name: String,
+    #[serde(default)]
+    version: String,
+    status: String,
+    #[serde(default)]
+    auto_update: Option<bool>,
+}
+
+/// Lists the installed plugins through the REST API, and looks up each one
+/// in the WordPress.org directory to see whether it is outdated, closed or
+/// not listed.
+pub fn list_plugins(site: &str) -> Result<PluginsReport> {
+    let client = RestClient::for_site(site)?;
+    let installed: Vec<RestPlugin> = client.get("/wp/v2/plugins")?;
+
+    let directory = Directory::new(&load_config()?)?;
+    let plugins = run_bounded(installed, DEFAULT_CONCURRENCY, |plugin| {
+        let info = directory.check(ComponentKind::Plugin, directory_slug(&plugin.plugin), &plugin.version);
+        InstalledPlugin {
+            plugin: plugin.plugin,
+            name: plugin.name,
+            version: plugin.version,
+            status: plugin.status,
+            auto_update: plugin.auto_update,
+            directory: info,
+        }
+    });
+
+    Ok(PluginsReport {
+        site: client.label().to_string(),
+        plugins,
+    })
+}
+
+/// The WordPress.org slug and version of every installed plugin.
+pub fn plugin_versions(client: &RestClient) -> Result<Vec<(String, String)>> {
+    let installed: Vec<RestPlugin> = client.get("/wp/v2/plugins")?;
+    Ok(installed
+        .into_iter()
+        .map(|plugin| (directory_slug(&plugin.plugin).to_string(), plugin.version))
+        .collect())
+}
+
+/// Activates or deactivates a plugin, given as `akismet/akismet` or just
+/// its directory name, `akismet`.
+pub fn set_plugin_status(site: &str, plugin: &str, activate: bool) -> Result<()> {
+    let client = RestClient::for_site(site)?;
+    let installed: Vec<RestPlugin> = client.get("/wp/v2/plugins")?;
+
+    let target = installed
+        .iter()
+        .find(|p| p.plugin == plugin)
+        .or_else(|| installed.iter().find(|p| directory_slug(&p.plugin) == plugin))
+        .ok_or_else(|| {
+            let available: Vec<&str> = installed.iter().map(|p| p.plugin.as_str()).collect();
+            eyre!(
+                "Plugin '{}' is not installed on {}. Installed plugins: {}",
+                plugin,
+                client.label(),
+                available.join(", ")
+            )
+        })?;
+
+    let (status, verb) = if activate { ("active", "Activated") } else { ("inactive", "Deactivated") };
+    if target.status == status || (activate && target.status == "network-active") {
+        println!("{} ({}) is already {}", target.name, target.plugin, target.status);
+        return Ok(());
+    }
+
+    let updated: RestPlugin = client.post(&format!("/wp/v2/plugins/{}", target.plugin), &json!({ "status": status }))?;
+    println!("✓ {} {} ({}) on {}", verb, updated.name, updated.plugin, client.label());
+    Ok(())
+}
+
+/// The plugin's directory, which is its slug on WordPress.org. Single-file
+/// plugins like `hello` have no directory.
+fn directory_slug(plugin: &str) -> &str {
+    plugin.split('/').next().unwrap_or(plugin)
+}
+
+impl fmt::Display for PluginsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let active = self.plugins.iter().filter(|p| p.status != "inactive").count();
+        writeln!(
+            f,
+            "Plugins on: {} ({} installed, {} active)\n",
+            self.site,
+            self.plugins.len(),
+            active
+        )?;
+
+        if self.plugins.is_empty() {
+            return writeln!(f, "No plugins installed.");
+        }
+
+        let mut table = Table::new(&["Plugin", "Name", "Version", "Status", "Auto-update", "WordPress.org"]);
+        for plugin in &self.plugins {
+            let auto_update = match plugin.auto_update {
+                Some(true) => "on",
+                Some(false) => "off",
+                None => "-",
+            };
+            table.add_row(vec![
+                plugin.plugin.clone(),
+                plugin.name.clone(),
+                plugin.version.clone(),
+                plugin.status.clone(),
+                auto_update.to_string(),
+                plugin.directory.to_string(),
+            ]);
+        }
+        write!(f, "{}", table)?;
+
+        write_maintenance_notes(f, self.plugins.iter().map(|p| (p.plugin.as_str(), &p.directory)))
+    }
+}
@@ -0,0 +1,402 @@
+use color_eyre::eyre::{Result, eyre};
+use reqwest::Method;
+use reqwest::blocking::{Client, Response};
+use reqwest::redirect::Policy;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::config::{Config, load_config};
+use crate::utils::{extract_hostname, http_client_with};
+
+/// A client for a site's WordPress REST API (`/wp-json/`), authenticated
+/// with the Application Password stored for the site in the registry.
+pub struct RestClient {
+    client: Client,
+    /// The site root, e.g. `https://example.com/blog`, without a trailing slash.
+    site_url: String,
+    auth: Option<(String, String)>,
+    label: String,
+    registered: bool,
+}
+
+impl RestClient {
+    /// Builds a client for a registered alias, hostname or URL. Requests are
+    /// only authenticated when the site is registered with an API user and
+    /// Application Password.
+    pub fn for_site(site: &str) -> Result<Self> {
+        let config = load_config()?;
+        Self::from_config(&config, site)
+    }
+
+    fn from_config(config: &Config, site: &str) -> Result<Self> {
+        let entry = match config.find_site(site) {
+            Some(entry) => Some(entry),
+            None => config.find_site_by_host(&extract_hostname(site)?),
+        };
+
+        let site_url = match entry {
+            Some(entry) => format!("https://{}", entry.address()),
+            None => format!("https://{}", extract_hostname(site)?),
+        };
+        let auth = entry.and_then(|entry| match (&entry.api_user, &entry.app_password) {
+            (Some(user), Some(password)) => Some((user.clone(), password.clone())),
+            _ => None,
+        });
+
+        // Redirects are not followed: reqwest turns a redirected POST into a
+        // GET and drops the Authorization header on a redirect to another
+        // host, so a plugin change could look successful without happening.
+        Ok(RestClient {
+            client: http_client_with(Policy::none())?,
+            site_url,
+            auth,
+            label: entry.map(|entry| entry.alias.clone()).unwrap_or_else(|| site.to_string()),
+            registered: entry.is_some(),
+        })
+    }
+
+    pub fn is_authenticated(&self) -> bool {
+        self.auth.is_some()
+    }
+
+    /// The site alias, or the site as given when it is not registered.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, route: &str) -> Result<T> {
+        self.request(Method::GET, route, None::<&()>)
+    }
+
+    pub fn post<T: DeserializeOwned, B: Serialize>(&self, route: &str, body: &B) -> Result<T> {
+        self.request(Method::POST, route, Some(body))
+    }
+
+    /// Sends a request to `route` (e.g. `/wp/v2/plugins`) and decodes the
+    /// JSON response, turning WordPress error responses into readable
+    /// errors.
+    pub fn request<T: DeserializeOwned, B: Serialize>(&self, method: Method, route: &str, body: Option<&B>) -> Result<T> {
+        let route = format!("/{}", route.trim_start_matches('/'));
+
+        let mut response = self.send(method.clone(), &format!("{}/wp-json{}", self.site_url, route), body)?;
+
+        // Without pretty permalinks /wp-json/ does not exist, but the API is
+        // still reachable through the rest_route query parameter.
+        if response.status() == 404 && !is_json(&response) {
+            response = self.send(method, &format!("{}/?rest_route={}", self.site_url, query_route(&route)), body)?;
+        }
+
+        let status = response.status();
+        let json = is_json(&response);
+        let text = response
+            .text()
+            .map_err(|e| eyre!("Failed to read the REST API response: {}", e))?;
+
+        if !json {
+            return Err(if status == 404 {
+                eyre!(
+                    "No REST API found at {}/wp-json/. Is this a WordPress site?",
+                    self.site_url
+                )
+            } else {
+                eyre!(
+                    "The REST API returned HTTP {} with a non-JSON response; it may be blocked by a \
+                     security plugin, firewall or basic auth",
+                    status.as_u16()
+                )
+            });
+        }
+
+        let value: Value = serde_json::from_str(&text)
+            .map_err(|e| eyre!("The REST API returned invalid JSON: {}", e))?;
+
+        if !status.is_success() {
+            return Err(self.map_error(status.as_u16(), &route, &value));
+        }
+
+        serde_json::from_value(value).map_err(|e| eyre!("Unexpected REST API response for {}: {}", route, e))
+    }
+
+    fn send<B: Serialize>(&self, method: Method, url: &str, body: Option<&B>) -> Result<Response> {
+        let mut request = self.client.request(method, url);
+        if let Some((user, password)) = &self.auth {
+            request = request.basic_auth(user, Some(password));
+        }
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        let response = request.send().map_err(|e| eyre!("REST API request failed: {}", e))?;
+
+        if response.status().is_redirection() {
+            let location = response
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default();
+            let target = response
+                .url()
+                .join(location)
+                .map(|target| target.to_string())
+                .unwrap_or_else(|_| location.to_string());
+            let new_site_url = target
+                .split_once("/wp-json")
+                .or_else(|| target.split_once("/?rest_route="))
+                .map_or("<url>", |(root, _)| root);
+            return Err(eyre!(
+                "The REST API at {} redirects to {} (HTTP {}). Redirects are not followed, since they would turn \
+                 a POST into a GET and can drop the credentials. {}",
+                url,
+                target,
+                response.status().as_u16(),
+                self.url_hint(new_site_url)
+            ));
+        }
+
+        Ok(response)
+    }
+
+    /// Turns a WordPress `{ "code": ..., "message": ... }` error into an
+    /// explanation of what to do about it.
+    fn map_error(&self, status: u16, route: &str, value: &Value) -> color_eyre::Report {
+        let code = value.get("code").and_then(Value::as_str).unwrap_or_default();
+        let message = value.get("message").and_then(Value::as_str).unwrap_or("no message");
+
+        match (status, code) {
+            (_, "rest_disabled" | "rest_cannot_access") if self.is_authenticated() => {
+                eyre!("The REST API is disabled on this site ({}: {})", code, message)
+            }
+            (_, "rest_disabled" | "rest_cannot_access") => eyre!(
+                "The REST API is disabled or restricted to logged-in users ({}: {}). {}",
+                code,
+                message,
+                self.register_hint()
+            ),
+            (401, _) if self.is_authenticated() => eyre!(
+                "Authentication failed for '{}' ({}: {}). Check the API user and Application Password; \
+                 some hosts strip the Authorization header",
+                self.label,
+                code,
+                message
+            ),
+            (401, _) => eyre!(
+                "{} requires authentication ({}: {}). {}",
+                route,
+                code,
+                message,
+                self.register_hint()
+            ),
+            (403, _) => eyre!(
+                "Permission denied for {} ({}: {}). The API user lacks the required capability",
+                route,
+                code,
+                message
+            ),
+            (404, "rest_no_route") => eyre!(
+                "The route {} does not exist on this site; the plugin or WordPress version providing it may be missing",
+                route
+            ),
+            (404, _) => eyre!("Not found: {} ({}: {})", route, code, message),
+            _ => eyre!("REST API error {} for {} ({}: {})", status, route, code, message),
+        }
+    }
+
+    fn register_hint(&self) -> String {
+        if self.registered {
+            format!(
+                "Add an Application Password with `wpust sites add {} {} --api-user <user> --app-password <password>`; \
+                 this replaces the entry, so repeat its other options (see `wpust sites show {}`)",
+                self.label, self.site_url, self.label
+            )
+        } else {
+            format!(
+                "Register the site with an Application Password: \
+                 `wpust sites add <alias> {} --api-user <user> --app-password <password>`",
+                self.site_url
+            )
+        }
+    }
+
+    /// How to point the client at `new_site_url`, the address the site
+    /// redirects to.
+    fn url_hint(&self, new_site_url: &str) -> String {
+        if self.registered {
+            format!(
+                "Update the URL of '{}' with `wpust sites add {} {}`; this replaces the entry, so repeat its \
+                 other options, including --api-user and --app-password (see `wpust sites show {}`)",
+                self.label, self.label, new_site_url, self.label
+            )
+        } else {
+            format!("Use the address it redirects to instead: {}", new_site_url)
+        }
+    }
+}
+
+/// `route` as a `rest_route` query value: its own query string has to
+/// continue the outer one, so `/wp/v2/plugins?status=active` becomes
+/// `/wp/v2/plugins&status=active`.
+fn query_route(route: &str) -> String {
+    match route.split_once('?') {
+        Some((path, query)) => format!("{}&{}", path, query),
+        None => route.to_string(),
+    }
+}
+
+fn is_json(response: &Response) -> bool {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains("json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SiteEntry;
+    use serde_json::json;
+
+    fn config(api_user: Option<&str>, app_password: Option<&str>) -> Config {
+        Config {
+            sites: vec![SiteEntry {
+                alias: "blog".to_string(),
+                url: "https://example.com/blog/".to_string(),
+                environment: None,
+                group: None,
+                tags: Vec::new(),
+                admin_path: None,
+                username: None,
+                password: None,
+                api_user: api_user.map(str::to_string),
+                app_password: app_password.map(str::to_string),
+            }],
+            ..Config::default()
+        }
+    }
+
+    fn authenticated() -> RestClient {
+        RestClient::from_config(&config(Some("admin"), Some("abcd efgh")), "blog").unwrap()
+    }
+
+    fn anonymous() -> RestClient {
+        RestClient::from_config(&config(None, None), "blog").unwrap()
+    }
+
+    fn error(client: &RestClient, status: u16, code: &str) -> String {
+        let value = json!({"code": code, "message": "Sorry."});
+        client.map_error(status, "/wp/v2/plugins", &value).to_string()
+    }
+
+    #[test]
+    fn registered_site_with_credentials_is_authenticated() {
+        let client = authenticated();
+        assert!(client.is_authenticated());
+        assert_eq!(client.label(), "blog");
+        assert_eq!(client.site_url, "https://example.com/blog");
+    }
+
+    #[test]
+    fn credentials_need_both_user_and_password() {
+        let config = config(Some("admin"), None);
+        assert!(!RestClient::from_config(&config, "blog").unwrap().is_authenticated());
+    }
+
+    #[test]
+    fn unregistered_site_uses_its_host() {
+        let client = RestClient::from_config(&Config::default(), "https://other.example/page").unwrap();
+        assert!(!client.is_authenticated());
+        assert_eq!(client.site_url, "https://other.example");
+        assert_eq!(client.label(), "https://other.example/page");
+    }
+
+    #[test]
+    fn registered_site_is_found_by_host() {
+        let client = RestClient::from_config(&config(Some("admin"), Some("pw")), "example.com").unwrap();
+        assert_eq!(client.label(), "blog");
+        assert!(client.is_authenticated());
+    }
+
+    #[test]
+    fn disabled_api() {
+        assert_eq!(
+            error(&authenticated(), 403, "rest_disabled"),
+            "The REST API is disabled on this site (rest_disabled: Sorry.)"
+        );
+        let message = error(&anonymous(), 401, "rest_disabled");
+        assert!(message.starts_with("The REST API is disabled or restricted to logged-in users (rest_disabled: Sorry.)"));
+        assert!(message.contains("wpust sites add blog https://example.com/blog --api-user"));
+    }
+
+    #[test]
+    fn api_restricted_to_logged_in_users() {
+        assert_eq!(
+            error(&authenticated(), 401, "rest_cannot_access"),
+            "The REST API is disabled on this site (rest_cannot_access: Sorry.)"
+        );
+        let message = error(&anonymous(), 401, "rest_cannot_access");
+        assert!(message.starts_with("The REST API is disabled or restricted to logged-in users"));
+        assert!(message.contains("--app-password"));
+    }
+
+    #[test]
+    fn unauthorized_with_credentials() {
+        let message = error(&authenticated(), 401, "incorrect_password");
+        assert!(message.starts_with("Authentication failed for 'blog' (incorrect_password: Sorry.)"));
+        assert!(message.contains("Authorization header"));
+    }
+
+    #[test]
+    fn unauthorized_without_credentials() {
+        let message = error(&anonymous(), 401, "rest_forbidden");
+        assert!(message.starts_with("/wp/v2/plugins requires authentication (rest_forbidden: Sorry.)"));
+        assert!(message.contains("this replaces the entry"));
+    }
+
+    #[test]
+    fn unregistered_site_is_told_to_register() {
+        let client = RestClient::from_config(&Config::default(), "other.example").unwrap();
+        let message = error(&client, 401, "rest_forbidden");
+        assert!(message.contains("wpust sites add <alias> https://other.example --api-user"));
+    }
+
+    #[test]
+    fn forbidden() {
+        assert_eq!(
+            error(&authenticated(), 403, "rest_cannot_manage_plugins"),
+            "Permission denied for /wp/v2/plugins (rest_cannot_manage_plugins: Sorry.). \
+             The API user lacks the required capability"
+        );
+    }
+
+    #[test]
+    fn missing_route() {
+        assert_eq!(
+            error(&authenticated(), 404, "rest_no_route"),
+            "The route /wp/v2/plugins does not exist on this site; the plugin or WordPress version providing it \
+             may be missing"
+        );
+    }
+
+    #[test]
+    fn other_not_found() {
+        assert_eq!(
+            error(&authenticated(), 404, "rest_plugin_not_found"),
+            "Not found: /wp/v2/plugins (rest_plugin_not_found: Sorry.)"
+        );
+    }
+
+    #[test]
+    fn other_errors_keep_the_status() {
+        assert_eq!(
+            error(&authenticated(), 500, "internal_server_error"),
+            "REST API error 500 for /wp/v2/plugins (internal_server_error: Sorry.)"
+        );
+    }
+
+    #[test]
+    fn query_route_continues_the_outer_query() {
+        assert_eq!(query_route("/wp/v2/plugins"), "/wp/v2/plugins");
+        assert_eq!(query_route("/wp/v2/plugins?status=active"), "/wp/v2/plugins&status=active");
+        assert_eq!(query_route("/wp/v2/plugins?a=1&b=2"), "/wp/v2/plugins&a=1&b=2");
+    }
+}
@@ -0,0 +1,106 @@
+use color_eyre::eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::config::load_config;
+use crate::network::engine::{DEFAULT_CONCURRENCY, run_bounded};
+use crate::output::Table;
+use crate::wordpress::rest::RestClient;
+use crate::wordpress::wporg::{ComponentKind, Directory, DirectoryInfo, write_maintenance_notes};
+
+#[derive(Serialize)]
+pub struct ThemesReport {
+    pub site: String,
+    pub themes: Vec<InstalledTheme>,
+}
+
+#[derive(Serialize)]
+pub struct InstalledTheme {
+    /// The theme directory, e.g. `twentytwentyfour`.
+    pub stylesheet: String,
+    pub name: String,
+    pub version: String,
+    pub status: String,
+    /// For child themes, the directory of the parent theme.
+    pub parent: Option<String>,
+    pub directory: DirectoryInfo,
+}
+
+/// A theme as returned by the `wp/v2/themes` endpoint.
+#[derive(Deserialize)]
+struct RestTheme {
+    stylesheet: String,
+    #[serde(default)]
+    template: String,
+    name: Rendered,
+    #[serde(default)]
+    version: String,
+    status: String,
+}
+
+#[derive(Deserialize)]
+struct Rendered {
+    #[serde(default)]
+    raw: Option<String>,
+    rendered: String,
+}
+
+/// Lists the installed themes through the REST API, and looks up each one
+/// in the WordPress.org directory to see whether it is outdated or no
+/// longer listed.
+pub fn list_themes(site: &str) -> Result<ThemesReport> {
+    let client = RestClient::for_site(site)?;
+    let installed: Vec<RestTheme> = client.get("/wp/v2/themes")?;
+
+    let directory = Directory::new(&load_config()?)?;
+    let themes = run_bounded(installed, DEFAULT_CONCURRENCY, |theme| {
+        let info = directory.check(ComponentKind::Theme, &theme.stylesheet, &theme.version);
+        InstalledTheme {
+            parent: (!theme.template.is_empty() && theme.template != theme.stylesheet).then_some(theme.template),
+            stylesheet: theme.stylesheet,
+            name: theme.name.raw.unwrap_or(theme.name.rendered),
+            version: theme.version,
+            status: theme.status,
+            directory: info,
+        }
+    });
+
+    Ok(ThemesReport {
+        site: client.label().to_string(),
+        themes,
+    })
+}
+
+/// The slug and version of every installed theme.
+pub fn theme_versions(client: &RestClient) -> Result<Vec<(String, String)>> {
+    let installed: Vec<RestTheme> = client.get("/wp/v2/themes")?;
+    Ok(installed.into_iter().map(|theme| (theme.stylesheet, theme.version)).collect())
+}
+
+impl fmt::Display for ThemesReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Themes on: {} ({} installed)\n", self.site, self.themes.len())?;
+
+        if self.themes.is_empty() {
+            return writeln!(f, "No themes installed.");
+        }
+
+        let mut table = Table::new(&["Theme", "Name", "Version", "Status", "WordPress.org"]);
+        for theme in &self.themes {
+            let name = match &theme.parent {
+                Some(parent) => format!("{} (child of {})", theme.name, parent),
+                None => theme.name.clone(),
+            };
+            table.add_row(vec![
+                theme.stylesheet.clone(),
+                name,
+                theme.version.clone(),
+                theme.status.clone(),
+                theme.directory.to_string(),
+            ]);
+        }
+        write!(f, "{}", table)?;
+
+        write_maintenance_notes(f, self.themes.iter().map(|t| (t.stylesheet.as_str(), &t.directory)))
+    }
+}
@@ -1,4 +1,5 @@
-use crate::config::Config;
+use reqwest::blocking::{Client, Response};
+use crate::config::{Config, SiteEntry};
 
 pub fn prepare_wordpress_url(
     site: String,
@@ -14,3 +15,16 @@ pub fn prepare_wordpress_url(
         format!("https://{}", site)
     }
 }
+
+/// Sends a GET request for `path` on the site, with the basic auth
+/// credentials of a registered site or matching rule for the host, so
+/// protected staging sites can be read too.
+pub fn site_get(client: &Client, config: &Config, hostname: &str, path: &str) -> reqwest::Result<Response> {
+    let request = client.get(format!("https://{}{}", hostname, path));
+    match (config.find_site_by_host(hostname), config.find_basic_auth(hostname)) {
+        (Some(SiteEntry { username: Some(user), password, .. }), _) => request.basic_auth(user, password.as_ref()),
+        (_, Some(rule)) => request.basic_auth(&rule.username, Some(&rule.password)),
+        _ => request,
+    }
+    .send()
+}
@@ -0,0 +1,707 @@
+use color_eyre::eyre::{Result, WrapErr, eyre};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::cli::FeedKind;
+use crate::config::config_path;
+use crate::utils::{compare_versions, unix_now};
+
+/// The local vulnerability database, imported from an advisory feed.
+#[derive(Deserialize, Serialize, Default)]
+pub struct VulnDb {
+    /// Unix time of the last import.
+    #[serde(default)]
+    pub updated_at: Option<i64>,
+    #[serde(default)]
+    pub advisories: Vec<Advisory>,
+}
+
+/// One vulnerability in one component. Feed entries that cover several
+/// components are split into one advisory per component.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Advisory {
+    pub id: String,
+    pub title: String,
+    pub component: ComponentType,
+    /// The plugin or theme slug; `wordpress` for core.
+    pub slug: String,
+    pub cves: Vec<String>,
+    pub severity: Severity,
+    pub cvss_score: Option<f64>,
+    pub affected: Vec<VersionRange>,
+    pub fixed_in: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ComponentType {
+    Core,
+    Plugin,
+    Theme,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Unknown,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+/// A range of affected versions; a missing bound is open.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct VersionRange {
+    pub from: Option<String>,
+    pub from_inclusive: bool,
+    pub to: Option<String>,
+    pub to_inclusive: bool,
+}
+
+/// The outcome of an import, for the confirmation message.
+pub struct ImportSummary {
+    pub format: &'static str,
+    pub imported: usize,
+    pub added: usize,
+    pub total: usize,
+}
+
+pub fn vuln_db_path() -> Result<PathBuf> {
+    let config = config_path()?;
+    let dir = config.parent().ok_or_else(|| eyre!("Could not determine config directory"))?;
+    Ok(dir.join("vulnerabilities.json"))
+}
+
+pub fn load_vuln_db() -> Result<VulnDb> {
+    let path = vuln_db_path()?;
+
+    if !path.exists() {
+        return Err(eyre!(
+            "No vulnerability database yet. Import an advisory feed with `wpust vulns update --from feed.json`"
+        ));
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .wrap_err_with(|| format!("Failed to read vulnerability database: {}", path.display()))?;
+
+    serde_json::from_str(&contents)
+        .wrap_err_with(|| format!("Failed to parse vulnerability database: {}", path.display()))
+}
+
+fn save_vuln_db(db: &VulnDb) -> Result<()> {
+    let path = vuln_db_path()?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .wrap_err_with(|| format!("Failed to create config directory: {}", parent.display()))?;
+    }
+
+    let contents = serde_json::to_string(db).wrap_err("Failed to serialize vulnerability database")?;
+
+    std::fs::write(&path, contents)
+        .wrap_err_with(|| format!("Failed to write vulnerability database: {}", path.display()))
+}
+
+/// Reads a Wordfence Intelligence or WPScan feed from `file` and merges it
+/// into the local database. Advisories already in the database are
+/// replaced by the imported version.
+pub fn import_feed(file: &Path, kind: FeedKind) -> Result<ImportSummary> {
+    let contents = std::fs::read_to_string(file)
+        .wrap_err_with(|| format!("Failed to read feed: {}", file.display()))?;
+    let feed: Value = serde_json::from_str(&contents)
+        .wrap_err_with(|| format!("Failed to parse feed as JSON: {}", file.display()))?;
+    let entries = feed
+        .as_object()
+        .ok_or_else(|| eyre!("Unrecognised feed: expected a JSON object keyed by advisory ID or slug"))?;
+
+    let is_wordfence = entries.values().any(|entry| entry.get("software").is_some());
+    let is_wpscan = entries.values().any(|entry| entry.get("vulnerabilities").is_some());
+    let (format, imported) = if is_wordfence {
+        ("Wordfence Intelligence", parse_wordfence(entries))
+    } else if is_wpscan {
+        ("WPScan", parse_wpscan(entries, kind))
+    } else {
+        return Err(eyre!(
+            "Unrecognised feed: expected Wordfence Intelligence entries with \"software\" or WPScan entries with \"vulnerabilities\""
+        ));
+    };
+
+    let mut db = if vuln_db_path()?.exists() { load_vuln_db()? } else { VulnDb::default() };
+    let imported_count = imported.len();
+    let added = db.merge(imported);
+    db.updated_at = Some(unix_now());
+
+    save_vuln_db(&db)?;
+    Ok(ImportSummary {
+        format,
+        imported: imported_count,
+        added,
+        total: db.advisories.len(),
+    })
+}
+
+impl VulnDb {
+    /// Adds `imported` to the database, replacing advisories with the same
+    /// ID for the same component. Returns how many were new.
+    fn merge(&mut self, imported: Vec<Advisory>) -> usize {
+        let mut added = 0;
+        for advisory in imported {
+            match self
+                .advisories
+                .iter_mut()
+                .find(|a| a.id == advisory.id && a.component == advisory.component && a.slug == advisory.slug)
+            {
+                Some(existing) => *existing = advisory,
+                None => {
+                    self.advisories.push(advisory);
+                    added += 1;
+                }
+            }
+        }
+        added
+    }
+}
+
+/// Wordfence Intelligence v2 feeds: `{ "<uuid>": { "title", "cve", "cvss",
+/// "software": [{ "type", "slug", "affected_versions", "patched_versions" }] } }`.
+fn parse_wordfence(entries: &Map<String, Value>) -> Vec<Advisory> {
+    let mut advisories = Vec::new();
+
+    for (key, entry) in entries {
+        let id = string(entry, "id").unwrap_or_else(|| key.clone());
+        let title = string(entry, "title").unwrap_or_else(|| id.clone());
+        let cves: Vec<String> = string(entry, "cve").into_iter().collect();
+        let cvss = entry.get("cvss");
+        let cvss_score = cvss.and_then(|cvss| number(cvss, "score"));
+        let severity = cvss
+            .and_then(|cvss| string(cvss, "rating"))
+            .map(|rating| Severity::from_label(&rating))
+            .unwrap_or_else(|| Severity::from_score(cvss_score));
+
+        for software in entry.get("software").and_then(Value::as_array).into_iter().flatten() {
+            let component = match string(software, "type").as_deref() {
+                Some("core") => ComponentType::Core,
+                Some("theme") => ComponentType::Theme,
+                _ => ComponentType::Plugin,
+            };
+            let slug = match component {
+                ComponentType::Core => "wordpress".to_string(),
+                _ => match string(software, "slug") {
+                    Some(slug) => slug,
+                    None => continue,
+                },
+            };
+
+            let affected = software
+                .get("affected_versions")
+                .and_then(Value::as_object)
+                .map(|ranges| {
+                    ranges
+                        .values()
+                        .map(|range| VersionRange {
+                            from: string(range, "from_version").filter(|v| v != "*"),
+                            from_inclusive: range.get("from_inclusive").and_then(Value::as_bool).unwrap_or(true),
+                            to: string(range, "to_version").filter(|v| v != "*"),
+                            to_inclusive: range.get("to_inclusive").and_then(Value::as_bool).unwrap_or(true),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let fixed_in = software
+                .get("patched_versions")
+                .and_then(Value::as_array)
+                .and_then(|versions| {
+                    versions
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .min_by(|a, b| compare_versions(a, b))
+                })
+                .map(str::to_string);
+
+            advisories.push(Advisory {
+                id: id.clone(),
+                title: title.clone(),
+                component,
+                slug,
+                cves: cves.clone(),
+                severity,
+                cvss_score,
+                affected,
+                fixed_in,
+            });
+        }
+    }
+
+    advisories
+}
+
+/// WPScan API responses: `{ "<slug>": { "vulnerabilities": [{ "id", "title",
+/// "references": { "cve" }, "cvss", "introduced_in", "fixed_in" }] } }`. The
+/// core response is keyed by WordPress version instead of slug.
+fn parse_wpscan(entries: &Map<String, Value>, kind: FeedKind) -> Vec<Advisory> {
+    let is_core = matches!(kind, FeedKind::Core)
+        || entries.keys().all(|key| key.contains('.') && key.chars().all(|c| c.is_ascii_digit() || c == '.'));
+    let mut advisories: Vec<Advisory> = Vec::new();
+
+    for (key, entry) in entries {
+        for vulnerability in entry.get("vulnerabilities").and_then(Value::as_array).into_iter().flatten() {
+            let Some(id) = string(vulnerability, "id") else {
+                continue;
+            };
+            let fixed_in = string(vulnerability, "fixed_in");
+            let (component, slug, affected) = if is_core {
+                // Each core version lists its own vulnerabilities.
+                let range = VersionRange {
+                    from: Some(key.clone()),
+                    from_inclusive: true,
+                    to: Some(key.clone()),
+                    to_inclusive: true,
+                };
+                (ComponentType::Core, "wordpress".to_string(), range)
+            } else {
+                let range = VersionRange {
+                    from: string(vulnerability, "introduced_in"),
+                    from_inclusive: true,
+                    to: fixed_in.clone(),
+                    to_inclusive: false,
+                };
+                let component = match kind {
+                    FeedKind::Theme => ComponentType::Theme,
+                    _ => ComponentType::Plugin,
+                };
+                (component, key.clone(), range)
+            };
+
+            let cves = vulnerability
+                .pointer("/references/cve")
+                .and_then(Value::as_array)
+                .map(|cves| {
+                    cves.iter()
+                        .filter_map(Value::as_str)
+                        .map(|cve| if cve.starts_with("CVE-") { cve.to_string() } else { format!("CVE-{}", cve) })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let cvss = vulnerability.get("cvss");
+            let cvss_score = cvss.and_then(|cvss| number(cvss, "score"));
+            let severity = cvss
+                .and_then(|cvss| string(cvss, "severity"))
+                .map(|label| Severity::from_label(&label))
+                .unwrap_or_else(|| Severity::from_score(cvss_score));
+
+            match advisories
+                .iter_mut()
+                .find(|a| a.id == id && a.slug == slug && a.component == component)
+            {
+                // A core vulnerability is repeated under every affected version.
+                Some(existing) => existing.affected.push(affected),
+                None => advisories.push(Advisory {
+                    title: string(vulnerability, "title").unwrap_or_else(|| id.clone()),
+                    id,
+                    component,
+                    slug,
+                    cves,
+                    severity,
+                    cvss_score,
+                    affected: vec![affected],
+                    fixed_in,
+                }),
+            }
+        }
+    }
+
+    advisories
+}
+
+fn string(value: &Value, key: &str) -> Option<String> {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+/// A number that some feeds send as a string, like WPScan's CVSS score.
+fn number(value: &Value, key: &str) -> Option<f64> {
+    match value.get(key)? {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+impl Advisory {
+    /// Whether `version` falls in one of the affected ranges. An advisory
+    /// without ranges affects every version before the fix, if any.
+    pub fn affects(&self, version: &str) -> bool {
+        if self.affected.is_empty() {
+            return self
+                .fixed_in
+                .as_deref()
+                .is_none_or(|fixed| compare_versions(version, fixed) == Ordering::Less);
+        }
+        self.affected.iter().any(|range| range.contains(version))
+    }
+}
+
+impl VersionRange {
+    pub fn contains(&self, version: &str) -> bool {
+        let above_from = self.from.as_deref().is_none_or(|from| match compare_versions(version, from) {
+            Ordering::Greater => true,
+            Ordering::Equal => self.from_inclusive,
+            Ordering::Less => false,
+        });
+        let below_to = self.to.as_deref().is_none_or(|to| match compare_versions(version, to) {
+            Ordering::Less => true,
+            Ordering::Equal => self.to_inclusive,
+            Ordering::Greater => false,
+        });
+        above_from && below_to
+    }
+}
+
+impl fmt::Display for VersionRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lower = if self.from_inclusive { ">=" } else { ">" };
+        let upper = if self.to_inclusive { "<=" } else { "<" };
+        match (&self.from, &self.to) {
+            (Some(from), Some(to)) if from == to => write!(f, "{}", from),
+            (Some(from), Some(to)) => write!(f, "{} {}, {} {}", lower, from, upper, to),
+            (Some(from), None) => write!(f, "{} {}", lower, from),
+            (None, Some(to)) => write!(f, "{} {}", upper, to),
+            (None, None) => write!(f, "all versions"),
+        }
+    }
+}
+
+impl Severity {
+    fn from_label(label: &str) -> Self {
+        match label.to_ascii_lowercase().as_str() {
+            "critical" => Severity::Critical,
+            "high" => Severity::High,
+            "medium" => Severity::Medium,
+            "low" => Severity::Low,
+            _ => Severity::Unknown,
+        }
+    }
+
+    /// The CVSS v3 rating for a score.
+    fn from_score(score: Option<f64>) -> Self {
+        match score {
+            Some(s) if s >= 9.0 => Severity::Critical,
+            Some(s) if s >= 7.0 => Severity::High,
+            Some(s) if s >= 4.0 => Severity::Medium,
+            Some(s) if s > 0.0 => Severity::Low,
+            _ => Severity::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Severity::Unknown => "unknown",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+impl fmt::Display for ComponentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            ComponentType::Core => "core",
+            ComponentType::Plugin => "plugin",
+            ComponentType::Theme => "theme",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entries(feed: Value) -> Map<String, Value> {
+        feed.as_object().cloned().expect("feed fixture is an object")
+    }
+
+    fn range(from: Option<&str>, from_inclusive: bool, to: Option<&str>, to_inclusive: bool) -> VersionRange {
+        VersionRange {
+            from: from.map(str::to_string),
+            from_inclusive,
+            to: to.map(str::to_string),
+            to_inclusive,
+        }
+    }
+
+    fn wordfence_feed() -> Value {
+        json!({
+            "0d4a7c5e-1111-4c2b-9e57-3a2f6b7c8d90": {
+                "id": "0d4a7c5e-1111-4c2b-9e57-3a2f6b7c8d90",
+                "title": "Contact Form 7 <= 5.8.3 - Unrestricted File Upload",
+                "software": [{
+                    "type": "plugin",
+                    "slug": "contact-form-7",
+                    "affected_versions": {
+                        "* - 5.8.3": {
+                            "from_version": "*",
+                            "from_inclusive": true,
+                            "to_version": "5.8.3",
+                            "to_inclusive": true
+                        }
+                    },
+                    "patched_versions": ["5.8.4", "5.9"]
+                }],
+                "cve": "CVE-2023-6449",
+                "cvss": { "score": 7.2, "rating": "High" }
+            },
+            "7f3c2b1a-2222-4d5e-8f9a-0b1c2d3e4f5a": {
+                "title": "Example Theme 2.0 - 2.3 (exclusive) - Reflected XSS",
+                "software": [{
+                    "type": "theme",
+                    "slug": "example-theme",
+                    "affected_versions": {
+                        "2.0 - 2.3": {
+                            "from_version": "2.0",
+                            "from_inclusive": false,
+                            "to_version": "2.3",
+                            "to_inclusive": false
+                        }
+                    },
+                    "patched_versions": []
+                }],
+                "cve": null,
+                "cvss": { "score": 9.8 }
+            },
+            "9a8b7c6d-3333-4e5f-a0b1-c2d3e4f5a6b7": {
+                "title": "WordPress Core < 6.4.2 - POP Chain",
+                "software": [{
+                    "type": "core",
+                    "slug": "wordpress",
+                    "affected_versions": {
+                        "* - 6.4.2": {
+                            "from_version": "*",
+                            "from_inclusive": true,
+                            "to_version": "6.4.2",
+                            "to_inclusive": false
+                        }
+                    },
+                    "patched_versions": ["6.4.2"]
+                }],
+                "cvss": { "score": 4.4, "rating": "Medium" }
+            }
+        })
+    }
+
+    fn find<'a>(advisories: &'a [Advisory], slug: &str) -> &'a Advisory {
+        advisories.iter().find(|a| a.slug == slug).expect("advisory for slug")
+    }
+
+    #[test]
+    fn version_range_bounds() {
+        let inclusive = range(Some("1.0"), true, Some("2.0"), true);
+        assert!(inclusive.contains("1.0"));
+        assert!(inclusive.contains("1.5"));
+        assert!(inclusive.contains("2.0"));
+        assert!(!inclusive.contains("0.9"));
+        assert!(!inclusive.contains("2.0.1"));
+
+        let exclusive = range(Some("1.0"), false, Some("2.0"), false);
+        assert!(!exclusive.contains("1.0"));
+        assert!(exclusive.contains("1.0.1"));
+        assert!(exclusive.contains("1.9.9"));
+        assert!(!exclusive.contains("2.0"));
+
+        let open = range(None, true, None, true);
+        assert!(open.contains("0.1"));
+        assert!(open.contains("99"));
+    }
+
+    #[test]
+    fn version_range_pre_releases() {
+        let before_fix = range(None, true, Some("5.0"), false);
+        assert!(before_fix.contains("5.0-RC1"));
+        assert!(!before_fix.contains("5.0"));
+
+        let from_release = range(Some("5.0"), true, None, true);
+        assert!(!from_release.contains("5.0-beta1"));
+    }
+
+    #[test]
+    fn wordfence_plugin_with_wildcard_lower_bound() {
+        let advisories = parse_wordfence(&entries(wordfence_feed()));
+        assert_eq!(advisories.len(), 3);
+
+        let plugin = find(&advisories, "contact-form-7");
+        assert_eq!(plugin.component, ComponentType::Plugin);
+        assert_eq!(plugin.id, "0d4a7c5e-1111-4c2b-9e57-3a2f6b7c8d90");
+        assert_eq!(plugin.cves, vec!["CVE-2023-6449"]);
+        assert_eq!(plugin.severity, Severity::High);
+        assert_eq!(plugin.cvss_score, Some(7.2));
+        assert_eq!(plugin.affected[0].from, None);
+        assert_eq!(plugin.fixed_in.as_deref(), Some("5.8.4"));
+        assert!(plugin.affects("1.0"));
+        assert!(plugin.affects("5.8.3"));
+        assert!(!plugin.affects("5.8.4"));
+    }
+
+    #[test]
+    fn wordfence_exclusive_bounds_and_missing_fix() {
+        let advisories = parse_wordfence(&entries(wordfence_feed()));
+
+        let theme = find(&advisories, "example-theme");
+        assert_eq!(theme.component, ComponentType::Theme);
+        assert!(theme.cves.is_empty());
+        assert_eq!(theme.fixed_in, None);
+        assert_eq!(theme.severity, Severity::Critical, "rating derived from the score");
+        assert!(!theme.affects("2.0"));
+        assert!(theme.affects("2.1"));
+        assert!(!theme.affects("2.3"));
+    }
+
+    #[test]
+    fn wordfence_core() {
+        let advisories = parse_wordfence(&entries(wordfence_feed()));
+
+        let core = find(&advisories, "wordpress");
+        assert_eq!(core.component, ComponentType::Core);
+        assert!(core.affects("6.4.1"));
+        assert!(core.affects("6.4.2-RC1"));
+        assert!(!core.affects("6.4.2"));
+    }
+
+    #[test]
+    fn wpscan_plugin_ranges() {
+        let feed = json!({
+            "contact-form-7": {
+                "friendly_name": "Contact Form 7",
+                "vulnerabilities": [
+                    {
+                        "id": "e2a8c1b3-aaaa",
+                        "title": "Contact Form 7 < 5.3.2 - Unrestricted File Upload",
+                        "references": { "cve": ["2020-35489"] },
+                        "cvss": { "score": "10.0", "severity": "critical" },
+                        "fixed_in": "5.3.2"
+                    },
+                    {
+                        "id": "f3b9d2c4-bbbb",
+                        "title": "Contact Form 7 2.0 - 3.0 - XSS",
+                        "introduced_in": "2.0",
+                        "fixed_in": "3.0"
+                    },
+                    {
+                        "id": "a4c0e3d5-cccc",
+                        "title": "Contact Form 7 - Unpatched CSRF",
+                        "fixed_in": null
+                    }
+                ]
+            }
+        });
+        let advisories = parse_wpscan(&entries(feed), FeedKind::Plugin);
+        assert_eq!(advisories.len(), 3);
+        assert!(advisories.iter().all(|a| a.component == ComponentType::Plugin && a.slug == "contact-form-7"));
+
+        let upload = &advisories[0];
+        assert_eq!(upload.cves, vec!["CVE-2020-35489"]);
+        assert_eq!(upload.cvss_score, Some(10.0));
+        assert_eq!(upload.severity, Severity::Critical);
+        assert!(upload.affects("5.3.1"));
+        assert!(!upload.affects("5.3.2"));
+
+        let xss = &advisories[1];
+        assert!(!xss.affects("1.9"));
+        assert!(xss.affects("2.0"));
+        assert!(!xss.affects("3.0"));
+
+        let unpatched = &advisories[2];
+        assert_eq!(unpatched.fixed_in, None);
+        assert_eq!(unpatched.severity, Severity::Unknown);
+        assert!(unpatched.affects("0.1"));
+        assert!(unpatched.affects("99.0"));
+    }
+
+    #[test]
+    fn wpscan_theme_kind() {
+        let feed = json!({ "twentyfifteen": { "vulnerabilities": [{ "id": "t1", "fixed_in": "1.2" }] } });
+        let advisories = parse_wpscan(&entries(feed), FeedKind::Theme);
+        assert_eq!(advisories[0].component, ComponentType::Theme);
+        assert_eq!(advisories[0].title, "t1");
+    }
+
+    #[test]
+    fn wpscan_core_feed_keyed_by_version() {
+        let shared = json!({ "id": "core-1", "title": "WordPress < 6.3.2 - Shortcode Execution", "fixed_in": "6.3.2" });
+        let feed = json!({
+            "6.3": { "release_date": "2023-08-08", "vulnerabilities": [shared.clone()] },
+            "6.3.1": {
+                "release_date": "2023-08-29",
+                "vulnerabilities": [shared, { "id": "core-2", "title": "Only in 6.3.1" }]
+            }
+        });
+        // The kind is not core, but version keys are recognised.
+        let advisories = parse_wpscan(&entries(feed), FeedKind::Plugin);
+        assert_eq!(advisories.len(), 2, "a repeated ID is merged, not duplicated");
+
+        let shared = advisories.iter().find(|a| a.id == "core-1").expect("core-1");
+        assert_eq!(shared.component, ComponentType::Core);
+        assert_eq!(shared.slug, "wordpress");
+        assert_eq!(shared.affected.len(), 2);
+        assert!(shared.affects("6.3"));
+        assert!(shared.affects("6.3.1"));
+        assert!(!shared.affects("6.3.2"));
+        assert!(!shared.affects("6.2"), "only the listed versions are affected");
+
+        let single = advisories.iter().find(|a| a.id == "core-2").expect("core-2");
+        assert_eq!(single.fixed_in, None);
+        assert!(single.affects("6.3.1"));
+        assert!(!single.affects("6.3"));
+    }
+
+    #[test]
+    fn advisory_without_ranges_uses_fixed_in() {
+        let mut advisory = parse_wpscan(&entries(json!({ "x": { "vulnerabilities": [{ "id": "1", "fixed_in": "2.0" }] } })), FeedKind::Plugin)
+            .remove(0);
+        advisory.affected.clear();
+        assert!(advisory.affects("1.9"));
+        assert!(advisory.affects("2.0-beta1"));
+        assert!(!advisory.affects("2.0"));
+
+        advisory.fixed_in = None;
+        assert!(advisory.affects("2.0"));
+    }
+
+    #[test]
+    fn reimport_replaces_advisories() {
+        let mut db = VulnDb::default();
+        assert_eq!(db.merge(parse_wordfence(&entries(wordfence_feed()))), 3);
+
+        let mut feed = wordfence_feed();
+        feed["0d4a7c5e-1111-4c2b-9e57-3a2f6b7c8d90"]["title"] = json!("Updated title");
+        feed["0d4a7c5e-1111-4c2b-9e57-3a2f6b7c8d90"]["software"][0]["patched_versions"] = json!(["5.8.5"]);
+        assert_eq!(db.merge(parse_wordfence(&entries(feed))), 0);
+        assert_eq!(db.advisories.len(), 3);
+
+        let plugin = find(&db.advisories, "contact-form-7");
+        assert_eq!(plugin.title, "Updated title");
+        assert_eq!(plugin.fixed_in.as_deref(), Some("5.8.5"));
+    }
+
+    #[test]
+    fn same_id_for_another_component_is_added() {
+        let mut db = VulnDb::default();
+        db.merge(parse_wpscan(&entries(json!({ "a": { "vulnerabilities": [{ "id": "1" }] } })), FeedKind::Plugin));
+        let added = db.merge(parse_wpscan(&entries(json!({ "b": { "vulnerabilities": [{ "id": "1" }] } })), FeedKind::Plugin));
+        assert_eq!(added, 1);
+        assert_eq!(db.advisories.len(), 2);
+    }
+}
@@ -0,0 +1,224 @@
+use color_eyre::eyre::Result;
+use serde::Serialize;
+use std::fmt;
+use std::path::Path;
+
+use crate::batch::Summary;
+use crate::cli::FeedKind;
+use crate::output::Table;
+use crate::utils::UtcDateTime;
+use crate::wordpress::detect::detect_wordpress;
+use crate::wordpress::inventory::inventory;
+use crate::wordpress::plugins::plugin_versions;
+use crate::wordpress::rest::RestClient;
+use crate::wordpress::themes::theme_versions;
+use crate::wordpress::vulndb::{ComponentType, Severity, VulnDb, import_feed};
+
+#[derive(Serialize)]
+pub struct VulnsReport {
+    pub hostname: String,
+    /// Where the plugin and theme versions came from.
+    pub source: &'static str,
+    /// Why the REST API could not list the plugins and themes, when the
+    /// front end was used instead despite an Application Password.
+    pub rest_error: Option<String>,
+    pub database_updated_at: Option<String>,
+    pub components: Vec<CheckedComponent>,
+    pub findings: Vec<Finding>,
+}
+
+#[derive(Serialize)]
+pub struct CheckedComponent {
+    pub component: ComponentType,
+    pub slug: String,
+    pub version: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct Finding {
+    pub component: ComponentType,
+    pub slug: String,
+    pub installed_version: String,
+    pub id: String,
+    pub title: String,
+    pub cves: Vec<String>,
+    pub severity: Severity,
+    pub cvss_score: Option<f64>,
+    pub affected: Vec<String>,
+    pub fixed_in: Option<String>,
+}
+
+/// Matches the site's core, plugin and theme versions against the local
+/// vulnerability database. Plugins and themes are listed through the REST
+/// API when the site has an Application Password, and detected from the
+/// front end otherwise or when the REST API fails.
+pub fn scan_vulnerabilities(hostname: &str, db: &VulnDb) -> Result<VulnsReport> {
+    let mut components = vec![CheckedComponent {
+        component: ComponentType::Core,
+        slug: "wordpress".to_string(),
+        version: detect_wordpress(hostname).ok().and_then(|report| report.version),
+    }];
+
+    let client = RestClient::for_site(hostname)?;
+    let mut rest_error = None;
+    let installed = if client.is_authenticated() {
+        match rest_components(&client) {
+            Ok(installed) => Some(installed),
+            Err(e) => {
+                rest_error = Some(format!("{:#}", e));
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let source = if let Some(installed) = installed {
+        components.extend(installed);
+        "REST API"
+    } else {
+        let found = inventory(hostname, 0)?;
+        for (component, found) in [(ComponentType::Plugin, found.plugins), (ComponentType::Theme, found.themes)] {
+            components.extend(found.into_iter().map(|c| CheckedComponent {
+                component,
+                slug: c.slug,
+                version: c.version,
+            }));
+        }
+        "front end"
+    };
+
+    let mut findings = Vec::new();
+    for checked in &components {
+        let Some(version) = &checked.version else {
+            continue;
+        };
+        for advisory in db
+            .advisories
+            .iter()
+            .filter(|a| a.component == checked.component && a.slug == checked.slug && a.affects(version))
+        {
+            findings.push(Finding {
+                component: checked.component,
+                slug: checked.slug.clone(),
+                installed_version: version.clone(),
+                id: advisory.id.clone(),
+                title: advisory.title.clone(),
+                cves: advisory.cves.clone(),
+                severity: advisory.severity,
+                cvss_score: advisory.cvss_score,
+                affected: advisory.affected.iter().map(|range| range.to_string()).collect(),
+                fixed_in: advisory.fixed_in.clone(),
+            });
+        }
+    }
+    findings.sort_by(|a, b| b.severity.cmp(&a.severity).then_with(|| a.slug.cmp(&b.slug)));
+
+    Ok(VulnsReport {
+        hostname: hostname.to_string(),
+        source,
+        rest_error,
+        database_updated_at: db.updated_at.map(|at| UtcDateTime::from_unix(at).iso8601()),
+        components,
+        findings,
+    })
+}
+
+/// The installed plugins and themes with their versions, from the REST API.
+fn rest_components(client: &RestClient) -> Result<Vec<CheckedComponent>> {
+    let mut components = Vec::new();
+    for (component, versions) in [
+        (ComponentType::Plugin, plugin_versions(client)?),
+        (ComponentType::Theme, theme_versions(client)?),
+    ] {
+        components.extend(versions.into_iter().map(|(slug, version)| CheckedComponent {
+            component,
+            slug,
+            version: Some(version).filter(|v| !v.is_empty()),
+        }));
+    }
+    Ok(components)
+}
+
+/// Imports an advisory feed into the local vulnerability database.
+pub fn update_vulnerabilities(file: &Path, kind: FeedKind) -> Result<()> {
+    let summary = import_feed(file, kind)?;
+    println!(
+        "✓ Imported {} advisories from the {} feed ({} new); the database now holds {}",
+        summary.imported, summary.format, summary.added, summary.total
+    );
+    Ok(())
+}
+
+impl fmt::Display for VulnsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Vulnerabilities for: {}", self.hostname)?;
+        writeln!(
+            f,
+            "Components checked: {} (plugins and themes from the {})",
+            self.components.iter().filter(|c| c.version.is_some()).count(),
+            self.source
+        )?;
+        if let Some(updated_at) = &self.database_updated_at {
+            writeln!(f, "Database updated: {}", updated_at)?;
+        }
+        writeln!(f)?;
+
+        if self.findings.is_empty() {
+            writeln!(f, "✓ No known vulnerabilities in the detected versions")?;
+        } else {
+            let mut table = Table::new(&["Severity", "Component", "Installed", "Affected", "Fixed in", "CVE", "Title"]);
+            for finding in &self.findings {
+                table.add_row(vec![
+                    finding.severity.to_string(),
+                    match finding.component {
+                        ComponentType::Core => "WordPress core".to_string(),
+                        component => format!("{} {}", component, finding.slug),
+                    },
+                    finding.installed_version.clone(),
+                    finding.affected.join("; "),
+                    finding.fixed_in.clone().unwrap_or_else(|| "not fixed".to_string()),
+                    if finding.cves.is_empty() { "-".to_string() } else { finding.cves.join(", ") },
+                    finding.title.clone(),
+                ]);
+            }
+            write!(f, "{}", table)?;
+        }
+
+        let unknown: Vec<String> = self
+            .components
+            .iter()
+            .filter(|c| c.version.is_none())
+            .map(|c| match c.component {
+                ComponentType::Core => "WordPress core".to_string(),
+                component => format!("{} {}", component, c.slug),
+            })
+            .collect();
+        if !unknown.is_empty() {
+            writeln!(f, "\nNot checked, version unknown: {}", unknown.join(", "))?;
+        }
+        if let Some(error) = &self.rest_error {
+            writeln!(
+                f,
+                "\n⚠️  The REST API failed, so only plugins and themes visible in the front end were checked: {}",
+                error
+            )?;
+        } else if self.source == "front end" {
+            writeln!(
+                f,
+                "\nOnly plugins and themes visible in the front end were checked; register an Application Password for a complete check."
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Summary for VulnsReport {
+    fn summary(&self) -> String {
+        if self.findings.is_empty() {
+            return "no known vulnerabilities".to_string();
+        }
+        let serious = self.findings.iter().filter(|f| f.severity >= Severity::High).count();
+        format!("{} vulnerabilities ({} high or critical)", self.findings.len(), serious)
+    }
+}
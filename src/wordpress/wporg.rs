@@ -0,0 +1,272 @@
+use color_eyre::eyre::Result;
+use reqwest::blocking::Client;
+use serde::Serialize;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::config::Config;
+use crate::utils::{compare_versions, http_client};
+
+pub const DEFAULT_API_URL: &str = "https://api.wordpress.org";
+
+#[derive(Clone, Copy)]
+pub enum ComponentKind {
+    Plugin,
+    Theme,
+}
+
+/// A client for the WordPress.org plugins and themes info API.
+pub struct Directory {
+    client: Client,
+    base_url: String,
+}
+
+/// How an installed plugin or theme compares to the WordPress.org directory.
+#[derive(Serialize)]
+pub struct DirectoryInfo {
+    pub status: DirectoryStatus,
+    pub latest_version: Option<String>,
+    pub update_available: Option<bool>,
+    /// Why the directory closed the plugin, e.g. "Security Issue".
+    pub closed_reason: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DirectoryStatus {
+    Listed,
+    /// Taken down from the directory, often for an unfixed security issue.
+    Closed,
+    /// Not in the directory: premium, custom, or removed long ago.
+    NotListed,
+    /// The lookup failed.
+    Unknown,
+}
+
+impl Directory {
+    /// Uses `wporg_api_url` from the config when set, so reports can be
+    /// tested against a local fixture server.
+    pub fn new(config: &Config) -> Result<Self> {
+        let base_url = config.wporg_api_url.as_deref().unwrap_or(DEFAULT_API_URL);
+        Ok(Directory {
+            client: http_client()?,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// Looks up `slug` and compares the directory's version with the
+    /// installed one.
+    pub fn check(&self, kind: ComponentKind, slug: &str, installed_version: &str) -> DirectoryInfo {
+        let (path, action) = match kind {
+            ComponentKind::Plugin => ("plugins", "plugin_information"),
+            ComponentKind::Theme => ("themes", "theme_information"),
+        };
+        let response = self
+            .client
+            .get(format!("{}/{}/info/1.2/", self.base_url, path))
+            .query(&[
+                ("action", action),
+                ("request[slug]", slug),
+                ("request[fields][sections]", "0"),
+            ])
+            .send();
+
+        // Unknown and closed slugs are answered with an error object and,
+        // depending on the endpoint, a 404 or a 200.
+        match response.and_then(|response| response.json::<Value>()) {
+            Ok(value) => classify(&value, installed_version),
+            Err(e) => DirectoryInfo {
+                error: Some(e.to_string()),
+                ..DirectoryInfo::unknown()
+            },
+        }
+    }
+}
+
+/// Interprets an info API response for a component installed at
+/// `installed_version`.
+fn classify(value: &Value, installed_version: &str) -> DirectoryInfo {
+    let mut info = DirectoryInfo::unknown();
+
+    if let Some(version) = value.get("version").and_then(Value::as_str) {
+        info.status = DirectoryStatus::Listed;
+        info.update_available = Some(compare_versions(version, installed_version) == Ordering::Greater);
+        info.latest_version = Some(version.to_string());
+    } else if value.get("error").and_then(Value::as_str) == Some("closed")
+        || value.get("closed").and_then(Value::as_bool) == Some(true)
+    {
+        info.status = DirectoryStatus::Closed;
+        info.closed_reason = value
+            .get("reason_text")
+            .or_else(|| value.get("reason"))
+            .and_then(Value::as_str)
+            .filter(|reason| !reason.is_empty())
+            .map(str::to_string);
+    } else if value.get("error").is_some() || value.is_null() || *value == Value::Bool(false) {
+        info.status = DirectoryStatus::NotListed;
+    } else {
+        info.error = Some("unexpected response from the WordPress.org API".to_string());
+    }
+
+    info
+}
+
+impl DirectoryInfo {
+    fn unknown() -> Self {
+        DirectoryInfo {
+            status: DirectoryStatus::Unknown,
+            latest_version: None,
+            update_available: None,
+            closed_reason: None,
+            error: None,
+        }
+    }
+
+    pub fn is_outdated(&self) -> bool {
+        self.update_available == Some(true)
+    }
+}
+
+/// A short description for the "Directory" column of a report.
+impl fmt::Display for DirectoryInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.status, &self.latest_version) {
+            (DirectoryStatus::Listed, Some(latest)) if self.is_outdated() => write!(f, "{} available", latest),
+            (DirectoryStatus::Listed, _) => write!(f, "up to date"),
+            (DirectoryStatus::Closed, _) => match &self.closed_reason {
+                Some(reason) => write!(f, "closed ({})", reason),
+                None => write!(f, "closed"),
+            },
+            (DirectoryStatus::NotListed, _) => write!(f, "not in directory"),
+            (DirectoryStatus::Unknown, _) => write!(f, "lookup failed"),
+        }
+    }
+}
+
+/// The notes printed below a plugins or themes table: what is outdated,
+/// closed or missing from the directory.
+pub fn write_maintenance_notes<'a>(
+    f: &mut fmt::Formatter<'_>,
+    components: impl Iterator<Item = (&'a str, &'a DirectoryInfo)> + Clone,
+) -> fmt::Result {
+    let outdated: Vec<&str> = components.clone().filter(|(_, info)| info.is_outdated()).map(|(name, _)| name).collect();
+    let closed: Vec<&str> = components
+        .clone()
+        .filter(|(_, info)| info.status == DirectoryStatus::Closed)
+        .map(|(name, _)| name)
+        .collect();
+    let not_listed: Vec<&str> = components
+        .clone()
+        .filter(|(_, info)| info.status == DirectoryStatus::NotListed)
+        .map(|(name, _)| name)
+        .collect();
+    let failed = components.filter(|(_, info)| info.status == DirectoryStatus::Unknown).count();
+
+    if !outdated.is_empty() {
+        writeln!(f, "\n⚠️  Outdated: {}", outdated.join(", "))?;
+    }
+    if !closed.is_empty() {
+        writeln!(
+            f,
+            "\n✗ Closed on WordPress.org, so no more updates: {}. Replace these; closures are often for unfixed security issues.",
+            closed.join(", ")
+        )?;
+    }
+    if !not_listed.is_empty() {
+        writeln!(
+            f,
+            "\nNot in the WordPress.org directory (premium, custom or removed): {}",
+            not_listed.join(", ")
+        )?;
+    }
+    if failed > 0 {
+        writeln!(f, "\n{} WordPress.org lookup(s) failed; see --format json for the errors.", failed)?;
+    }
+    if outdated.is_empty() && closed.is_empty() && failed == 0 {
+        writeln!(f, "\n✓ Everything listed on WordPress.org is up to date")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn listed_and_up_to_date() {
+        let info = classify(&json!({"name": "Akismet", "slug": "akismet", "version": "5.3.1"}), "5.3.1");
+        assert_eq!(info.status, DirectoryStatus::Listed);
+        assert_eq!(info.latest_version.as_deref(), Some("5.3.1"));
+        assert_eq!(info.update_available, Some(false));
+        assert_eq!(info.to_string(), "up to date");
+    }
+
+    #[test]
+    fn listed_with_update_available() {
+        let info = classify(&json!({"slug": "akismet", "version": "5.3.10"}), "5.3.9");
+        assert_eq!(info.status, DirectoryStatus::Listed);
+        assert!(info.is_outdated());
+        assert_eq!(info.to_string(), "5.3.10 available");
+    }
+
+    #[test]
+    fn newer_install_than_directory_is_not_outdated() {
+        let info = classify(&json!({"version": "2.0"}), "2.1-beta");
+        assert_eq!(info.update_available, Some(false));
+    }
+
+    #[test]
+    fn closed_with_reason() {
+        let info = classify(
+            &json!({
+                "error": "closed",
+                "name": "Old Plugin",
+                "slug": "old-plugin",
+                "description": "This plugin has been closed as of May 1, 2024 and is not available for download.",
+                "closed": true,
+                "closed_date": "2024-05-01",
+                "reason": "security-issue",
+                "reason_text": "Security Issue"
+            }),
+            "1.0",
+        );
+        assert_eq!(info.status, DirectoryStatus::Closed);
+        assert_eq!(info.closed_reason.as_deref(), Some("Security Issue"));
+        assert_eq!(info.to_string(), "closed (Security Issue)");
+    }
+
+    #[test]
+    fn closed_without_reason() {
+        let info = classify(&json!({"error": "closed", "reason_text": ""}), "1.0");
+        assert_eq!(info.status, DirectoryStatus::Closed);
+        assert_eq!(info.closed_reason, None);
+        assert_eq!(info.to_string(), "closed");
+    }
+
+    #[test]
+    fn unknown_slug_is_not_listed() {
+        let info = classify(&json!({"error": "Plugin not found."}), "1.0");
+        assert_eq!(info.status, DirectoryStatus::NotListed);
+        assert_eq!(info.to_string(), "not in directory");
+    }
+
+    #[test]
+    fn false_and_null_bodies_are_not_listed() {
+        for body in [json!(false), Value::Null] {
+            let info = classify(&body, "1.0");
+            assert_eq!(info.status, DirectoryStatus::NotListed);
+            assert_eq!(info.error, None);
+        }
+    }
+
+    #[test]
+    fn unexpected_body_is_an_error() {
+        let info = classify(&json!({"name": "No version"}), "1.0");
+        assert_eq!(info.status, DirectoryStatus::Unknown);
+        assert!(info.error.is_some());
+        assert_eq!(info.to_string(), "lookup failed");
+    }
+}